@@ -0,0 +1,65 @@
+//! Reporting of which implementations were selected when this crate was
+//! compiled, see `backend_info`.
+
+use core::fmt;
+
+/// The names of the unwinding and symbolication implementations compiled
+/// into this crate, returned from `backend_info`.
+///
+/// The names are meant for humans, for example to include in bug reports or
+/// diagnostic output, and new ones may be added as more platforms are
+/// supported.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BackendInfo {
+    unwind: &'static str,
+    symbolize: &'static str,
+}
+
+impl BackendInfo {
+    /// Returns the name of the implementation used by `trace` to walk the
+    /// stack, one of `"libunwind"`, `"dbghelp"`, `"miri"` or `"noop"`.
+    ///
+    /// Note that `"libunwind"` covers any implementation of the
+    /// `_Unwind_Backtrace` interface, including the one in libgcc.
+    pub fn unwind(&self) -> &'static str {
+        self.unwind
+    }
+
+    /// Returns the name of the implementation used by `resolve` to look up
+    /// symbols, one of `"gimli"`, `"dbghelp"`, `"miri"` or `"noop"`.
+    ///
+    /// This is the name of the `SymbolizeBackend` reported by
+    /// `symbolization_status`.
+    pub fn symbolize(&self) -> &'static str {
+        self.symbolize
+    }
+}
+
+impl fmt::Display for BackendInfo {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "unwind: {}, symbolize: {}",
+            self.unwind, self.symbolize
+        )
+    }
+}
+
+/// Returns which unwinding and symbolication implementations were selected
+/// for the target this crate was compiled for.
+///
+/// This is decided entirely at compile time, so it doesn't say whether the
+/// implementations actually work at runtime. See `symbolization_status` for
+/// that.
+///
+/// # Example
+///
+/// ```
+/// println!("backtrace backends: {}", backtrace::backend_info());
+/// ```
+pub fn backend_info() -> BackendInfo {
+    BackendInfo {
+        unwind: super::backtrace::BACKEND_NAME,
+        symbolize: super::symbolize::BACKEND.name(),
+    }
+}
@@ -32,7 +32,20 @@ pub struct Frame {
 unsafe impl Send for Frame {}
 unsafe impl Sync for Frame {}
 
+#[derive(Default)]
+pub(crate) struct ModuleCache;
+
 impl Frame {
+    pub fn custom(ip: *mut c_void, sp: *mut c_void, _symbol_address: *mut c_void) -> Frame {
+        let mut frame = Frame {
+            stack_frame: StackFrame::Old(unsafe { mem::zeroed() }),
+            base_address: core::ptr::null_mut(),
+        };
+        frame.addr_pc_mut().Offset = ip as u64;
+        frame.addr_stack_mut().Offset = sp as u64;
+        frame
+    }
+
     pub fn ip(&self) -> *mut c_void {
         self.addr_pc().Offset as *mut _
     }
@@ -49,6 +62,24 @@ impl Frame {
         Some(self.base_address)
     }
 
+    pub fn module_build_id(&self) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn module_build_id_cached(&self, _cache: &mut ModuleCache) -> Option<&[u8]> {
+        None
+    }
+
+    // `StackWalkEx` doesn't name functions, that takes `SymFromAddrW`, which
+    // is what resolving the frame does anyway.
+    pub fn function_name(&self) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn ip_is_exact(&self) -> bool {
+        false
+    }
+
     #[cfg(not(target_env = "gnu"))]
     pub fn inline_context(&self) -> Option<u32> {
         match self.stack_frame {
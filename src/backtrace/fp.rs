@@ -0,0 +1,141 @@
+//! Walking a stack by following its chain of saved frame pointers.
+//!
+//! This doesn't need any unwind information, but only works for code which
+//! keeps a frame pointer, and it can start from any frame, not just the
+//! current one. On the architectures supported here each frame pointer points
+//! at the caller's saved frame pointer, directly followed by the return
+//! address into the caller.
+
+use super::Frame;
+use core::ffi::c_void;
+use core::mem;
+
+/// How many frames are walked at most, in case the chain ends up in a cycle
+/// that still moves up the stack, such as on a corrupt stack.
+const MAX_FRAMES: usize = 1 << 16;
+
+/// Walks the stack starting from the frame pointer `fp`, passing a frame for
+/// each return address found to `cb`, innermost frame first.
+///
+/// The first frame passed to `cb` is the caller of the function `fp` belongs
+/// to, as the return address saved in that function's frame is the first
+/// one found. The walk ends at a null or misaligned frame pointer, at a null
+/// return address, when the next frame pointer doesn't move up the stack, or
+/// when `cb` returns `false`. On architectures where the layout of frames
+/// isn't known no frames are found.
+///
+/// The frames have no symbol address, so `symbol_address` is just `ip`.
+///
+/// # Safety
+///
+/// `fp` must be null or point into a valid stack in which every function
+/// keeps a frame pointer, such as one compiled with `-C
+/// force-frame-pointers=yes`, and the stack must not be modified while it's
+/// walked. The memory each frame pointer in the chain points at is read
+/// without any further checks.
+pub unsafe fn trace_fp<F: FnMut(&Frame) -> bool>(fp: *mut c_void, cb: F) {
+    unsafe { walk(fp, 0..usize::MAX, cb) }
+}
+
+/// Walks the current thread's stack by its frame pointers, starting from the
+/// caller of this function, for when the unwinder found no frames at all.
+///
+/// Code built without frame pointers may use the frame pointer register for
+/// anything, so only frame pointers within the bounds of the current thread's
+/// stack are followed. Those bounds are only known on Linux and Android, and
+/// elsewhere no frames are found.
+#[cfg(all(
+    feature = "std",
+    any(target_os = "linux", target_os = "android"),
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+#[inline(never)] // want to make sure there's a frame here to start from
+pub(crate) fn trace_current<F: FnMut(&Frame) -> bool>(cb: F) {
+    let Some(stack) = current_stack() else {
+        return;
+    };
+    let fp: *mut c_void;
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        core::arch::asm!("mov {}, rbp", out(reg) fp, options(nomem, nostack));
+        #[cfg(target_arch = "aarch64")]
+        core::arch::asm!("mov {}, x29", out(reg) fp, options(nomem, nostack));
+        walk(fp, stack, cb)
+    }
+}
+
+#[cfg(all(
+    feature = "std",
+    not(all(
+        any(target_os = "linux", target_os = "android"),
+        any(target_arch = "x86_64", target_arch = "aarch64"),
+        not(miri),
+    )),
+))]
+pub(crate) fn trace_current<F: FnMut(&Frame) -> bool>(_cb: F) {}
+
+/// Returns the addresses the current thread's stack spans.
+#[cfg(all(
+    feature = "std",
+    any(target_os = "linux", target_os = "android"),
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+fn current_stack() -> Option<core::ops::Range<usize>> {
+    unsafe {
+        let mut attr = mem::zeroed::<libc::pthread_attr_t>();
+        if libc::pthread_getattr_np(libc::pthread_self(), &mut attr) != 0 {
+            return None;
+        }
+        let mut addr = core::ptr::null_mut();
+        let mut size = 0;
+        let ret = libc::pthread_attr_getstack(&attr, &mut addr, &mut size);
+        libc::pthread_attr_destroy(&mut attr);
+        if ret != 0 {
+            return None;
+        }
+        Some(addr as usize..(addr as usize).checked_add(size)?)
+    }
+}
+
+/// Same as `trace_fp`, except that the walk also ends at a frame which doesn't
+/// lie entirely within `stack`.
+unsafe fn walk<F: FnMut(&Frame) -> bool>(
+    fp: *mut c_void,
+    stack: core::ops::Range<usize>,
+    mut cb: F,
+) {
+    if !cfg!(any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "arm64ec",
+    )) {
+        return;
+    }
+
+    let mut fp = fp as *const usize;
+    for _ in 0..MAX_FRAMES {
+        if fp.is_null() || fp as usize % mem::align_of::<usize>() != 0 {
+            return;
+        }
+        let in_stack = (fp as usize)
+            .checked_add(2 * mem::size_of::<usize>())
+            .is_some_and(|end| stack.start <= fp as usize && end <= stack.end);
+        if !in_stack {
+            return;
+        }
+        let (next, ip) = unsafe { (*fp as *const usize, *fp.add(1) as *mut c_void) };
+        if ip.is_null() {
+            return;
+        }
+        // The caller's stack pointer is just past the saved frame pointer
+        // and return address.
+        let sp = unsafe { fp.add(2) } as *mut c_void;
+        if !cb(&Frame::new(ip, sp, ip)) || next <= fp {
+            return;
+        }
+        fp = next;
+    }
+}
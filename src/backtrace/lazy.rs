@@ -0,0 +1,129 @@
+use super::{trace_unsynchronized, Frame};
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+
+/// Inspects the current call-stack like `trace`, but hands the frames to `f`
+/// as an `Iterator` which walks the stack lazily.
+///
+/// The callback-based `trace` API can't be turned into an iterator directly,
+/// as many unwinders only offer a callback-based interface themselves. This
+/// function works around that by calling `f` on a separate thread while the
+/// current thread walks its own stack, handing frames over one at a time. The
+/// stack walk only advances when `f` asks for the next frame, so `f` is free
+/// to do expensive work (like resolving symbols) in between frames, or to stop
+/// early by dropping the iterator.
+///
+/// This is a convenience for callers which would otherwise have to restructure
+/// their code to work from within a callback, it is not suitable for hot
+/// paths. Every call spawns a thread and every frame involves a round trip
+/// between the two threads, which is far more expensive than calling `trace`
+/// and collecting the frames into a `Vec`.
+///
+/// The returned frames are clones of the frames yielded by `trace`, and can be
+/// passed to `resolve_frame` from within `f`. The global lock used to
+/// synchronize this crate is held for the whole walk, and handed over to the
+/// thread running `f`, which takes turns with the stack walk: the walk only
+/// moves on once `f` asks for the next frame, and `f` only runs while the
+/// walk is waiting on it. So `f` can use this crate just like the callback of
+/// `trace` can, while other threads are kept out until `f` returns.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Panics
+///
+/// If `f` panics then the stack walk is stopped and the panic is resumed on
+/// the calling thread.
+///
+/// # Example
+///
+/// ```
+/// let names = backtrace::trace_lazy(|frames| {
+///     let mut names = Vec::new();
+///     for frame in frames.take(5) {
+///         backtrace::resolve_frame(&frame, |symbol| {
+///             names.push(symbol.name().map(|name| name.to_string()));
+///         });
+///     }
+///     names
+/// });
+/// ```
+pub fn trace_lazy<F, R>(f: F) -> R
+where
+    F: FnOnce(LazyFrames<'_>) -> R + Send,
+    R: Send,
+{
+    // Rendezvous channels, so that the consumer asks for each frame and the
+    // stack walk waits for it to do so. Only one of the two threads runs at
+    // a time.
+    let (request_tx, request_rx) = mpsc::sync_channel(0);
+    let (frame_tx, frame_rx) = mpsc::sync_channel(0);
+
+    // Taken before the consumer starts, which relies on it being held.
+    let _guard = crate::lock::lock();
+    thread::scope(|scope| {
+        let consumer = scope.spawn(move || {
+            // SAFETY: this thread holds the lock until the consumer is
+            // joined, and only walks the stack while the consumer waits on
+            // it for a frame.
+            let _lock = unsafe { crate::lock::borrow() };
+            f(LazyFrames {
+                request_tx,
+                frame_rx,
+                _marker: PhantomData,
+            })
+        });
+
+        if request_rx.recv().is_ok() {
+            unsafe {
+                trace_unsynchronized(|frame| {
+                    frame_tx.send(frame.clone()).is_ok() && request_rx.recv().is_ok()
+                });
+            }
+        }
+        // Let the consumer know there are no more frames, whether it's
+        // waiting on one or about to ask for one.
+        drop(request_rx);
+        drop(frame_tx);
+
+        match consumer.join() {
+            Ok(ret) => ret,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    })
+}
+
+/// An iterator over the frames of the stack being walked by `trace_lazy`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+pub struct LazyFrames<'a> {
+    request_tx: SyncSender<()>,
+    frame_rx: Receiver<Frame>,
+    // Tie the iterator to the call of `trace_lazy` so it can't be stashed away
+    // and outlive the stack walk.
+    _marker: PhantomData<&'a mut ()>,
+}
+
+impl Iterator for LazyFrames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        // The stack walk stops listening for requests once it's done.
+        self.request_tx.send(()).ok()?;
+        self.frame_rx.recv().ok()
+    }
+}
+
+impl fmt::Debug for LazyFrames<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("LazyFrames").finish_non_exhaustive()
+    }
+}
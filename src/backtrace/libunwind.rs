@@ -18,12 +18,82 @@
 use core::ffi::c_void;
 use core::ptr::addr_of_mut;
 
+cfg_if::cfg_if! {
+    if #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd",
+        ),
+        not(target_env = "uclibc"),
+    ))] {
+        mod build_id;
+        use self::build_id::module_build_id;
+        pub(crate) use self::build_id::ModuleCache;
+    } else {
+        fn module_build_id(_addr: *mut c_void) -> Option<&'static [u8]> {
+            None
+        }
+
+        #[derive(Default)]
+        pub(crate) struct ModuleCache;
+
+        impl ModuleCache {
+            fn module_build_id(&mut self, _addr: *mut c_void) -> Option<&'static [u8]> {
+                None
+            }
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd",
+            target_vendor = "apple",
+        ),
+        not(target_env = "uclibc"),
+    ))] {
+        /// Returns the name of the dynamic symbol starting at `start`, if
+        /// that's the one containing `addr`, which stays valid while its
+        /// module is loaded.
+        ///
+        /// `dladdr` names the nearest dynamic symbol preceding `addr`, which
+        /// for a function that isn't exported is some unrelated one, so the
+        /// symbol is only trusted if it starts where the unwinder says the
+        /// function does.
+        fn dynamic_symbol_name(addr: *mut c_void, start: *mut c_void) -> Option<&'static [u8]> {
+            let mut info = core::mem::MaybeUninit::<libc::Dl_info>::uninit();
+            if unsafe { libc::dladdr(addr, info.as_mut_ptr()) } == 0 {
+                return None;
+            }
+            let info = unsafe { info.assume_init() };
+            if info.dli_sname.is_null() || info.dli_saddr != start {
+                return None;
+            }
+            let name = unsafe { core::ffi::CStr::from_ptr(info.dli_sname) };
+            Some(name.to_bytes()).filter(|name| !name.is_empty())
+        }
+    } else {
+        fn dynamic_symbol_name(_addr: *mut c_void, _start: *mut c_void) -> Option<&'static [u8]> {
+            None
+        }
+    }
+}
+
 pub enum Frame {
     Raw(*mut uw::_Unwind_Context),
     Cloned {
         ip: *mut c_void,
         sp: *mut c_void,
         symbol_address: *mut c_void,
+        ip_is_exact: bool,
     },
 }
 
@@ -87,6 +157,52 @@ impl Frame {
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         None
     }
+
+    pub fn module_build_id(&self) -> Option<&[u8]> {
+        module_build_id(self.module_addr()?)
+    }
+
+    pub fn module_build_id_cached(&self, cache: &mut ModuleCache) -> Option<&[u8]> {
+        cache.module_build_id(self.module_addr()?)
+    }
+
+    /// An address which is certain to be in the frame's module, or `None` if
+    /// the frame's build ID can't be read anymore.
+    fn module_addr(&self) -> Option<*mut c_void> {
+        match *self {
+            Frame::Raw(_) if self.ip_is_exact() => Some(self.ip()),
+            // The instruction before the return address is the one which is
+            // certain to be in the module, see `adjust_ip` in `symbolize`.
+            Frame::Raw(_) => Some(self.ip().wrapping_byte_sub(1)),
+            Frame::Cloned { .. } => None,
+        }
+    }
+
+    // The frames of signal handlers' callers are interrupted at an
+    // instruction, rather than returned to after a call.
+    pub fn ip_is_exact(&self) -> bool {
+        match *self {
+            Frame::Raw(ctx) => unsafe { uw::ip_before_insn(ctx) },
+            Frame::Cloned { ip_is_exact, .. } => ip_is_exact,
+        }
+    }
+
+    // `_Unwind_Backtrace` doesn't know the names of functions, but the
+    // dynamic linker knows those of the symbols it can link against.
+    pub fn function_name(&self) -> Option<&[u8]> {
+        dynamic_symbol_name(self.module_addr()?, self.symbol_address())
+    }
+}
+
+impl Frame {
+    pub fn custom(ip: *mut c_void, sp: *mut c_void, symbol_address: *mut c_void) -> Frame {
+        Frame::Cloned {
+            ip,
+            sp,
+            symbol_address,
+            ip_is_exact: false,
+        }
+    }
 }
 
 impl Clone for Frame {
@@ -95,6 +211,7 @@ impl Clone for Frame {
             ip: self.ip(),
             sp: self.sp(),
             symbol_address: self.symbol_address(),
+            ip_is_exact: self.ip_is_exact(),
         }
     }
 }
@@ -187,6 +304,10 @@ mod uw {
         ))] {
             extern "C" {
                 pub fn _Unwind_GetIP(ctx: *mut _Unwind_Context) -> libc::uintptr_t;
+                pub fn _Unwind_GetIPInfo(
+                    ctx: *mut _Unwind_Context,
+                    ip_before_insn: *mut libc::c_int,
+                ) -> libc::uintptr_t;
                 pub fn _Unwind_FindEnclosingFunction(pc: *mut c_void) -> *mut c_void;
 
                 #[cfg(not(all(target_os = "linux", target_arch = "s390x")))]
@@ -210,6 +331,15 @@ mod uw {
                 }
                 _Unwind_GetGR(ctx, 15)
             }
+
+            /// Returns whether the instruction pointer of `ctx` is the
+            /// instruction to execute rather than a return address, which is
+            /// the case for frames interrupted by a signal.
+            pub unsafe fn ip_before_insn(ctx: *mut _Unwind_Context) -> bool {
+                let mut ip_before_insn = 0;
+                _Unwind_GetIPInfo(ctx, &mut ip_before_insn);
+                ip_before_insn != 0
+            }
         } else {
             use core::ptr::addr_of_mut;
 
@@ -289,6 +419,12 @@ mod uw {
             pub unsafe fn _Unwind_FindEnclosingFunction(pc: *mut c_void) -> *mut c_void {
                 pc
             }
+
+            // Nor does `_Unwind_GetIPInfo`, so signal frames can't be told
+            // apart.
+            pub unsafe fn ip_before_insn(_ctx: *mut _Unwind_Context) -> bool {
+                false
+            }
         }
     }
 }
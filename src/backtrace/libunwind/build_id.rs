@@ -0,0 +1,135 @@
+//! Lookup of the GNU build ID of a loaded module, read out of the `PT_NOTE`
+//! segments the dynamic loader mapped into memory for it.
+//!
+//! Reading the note from memory rather than from the module's file means the
+//! build ID is the one of the code that's actually running, even if the file
+//! on disk has been replaced since.
+
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::ptr::addr_of_mut;
+use core::slice;
+use object::elf::{ELF_NOTE_GNU, NT_GNU_BUILD_ID, PT_LOAD, PT_NOTE};
+use object::read::elf::NoteIterator;
+use object::NativeEndian;
+
+#[cfg(target_pointer_width = "32")]
+type Elf = object::elf::FileHeader32<NativeEndian>;
+#[cfg(target_pointer_width = "64")]
+type Elf = object::elf::FileHeader64<NativeEndian>;
+
+/// The span of memory a module was loaded into, and its build ID.
+struct Module {
+    start: usize,
+    end: usize,
+    build_id: Option<&'static [u8]>,
+}
+
+struct Lookup {
+    addr: usize,
+    module: Option<Module>,
+}
+
+/// Returns the build ID of the module which has a segment loaded at `addr`.
+///
+/// The returned slice points into the module itself, so it's only valid for as
+/// long as the module stays loaded.
+pub(super) fn module_build_id(addr: *mut c_void) -> Option<&'static [u8]> {
+    find_module(addr as usize)?.build_id
+}
+
+fn find_module(addr: usize) -> Option<Module> {
+    let mut lookup = Lookup { addr, module: None };
+    unsafe {
+        libc::dl_iterate_phdr(Some(callback), addr_of_mut!(lookup).cast());
+    }
+    lookup.module
+}
+
+/// The modules found by earlier lookups, so that looking up the build IDs of
+/// many addresses, such as those of all frames of a backtrace, walks the list
+/// of loaded modules once per module rather than once per address.
+#[derive(Default)]
+pub(crate) struct ModuleCache {
+    modules: Vec<Module>,
+}
+
+impl ModuleCache {
+    /// Same as `module_build_id`, remembering the module for later lookups.
+    pub(super) fn module_build_id(&mut self, addr: *mut c_void) -> Option<&'static [u8]> {
+        let addr = addr as usize;
+        // The loader reserves the whole span of a module's segments, so no
+        // other module can be loaded in the gaps between them.
+        let cached = self
+            .modules
+            .iter()
+            .find(|module| (module.start..module.end).contains(&addr));
+        if let Some(module) = cached {
+            return module.build_id;
+        }
+        let module = find_module(addr)?;
+        let build_id = module.build_id;
+        self.modules.push(module);
+        build_id
+    }
+}
+
+/// # Safety
+/// `info` must be a valid pointer.
+/// `data` must be a valid pointer to `Lookup`.
+#[forbid(unsafe_op_in_unsafe_fn)]
+unsafe extern "C" fn callback(
+    info: *mut libc::dl_phdr_info,
+    _size: libc::size_t,
+    data: *mut libc::c_void,
+) -> libc::c_int {
+    // SAFETY: We are guaranteed these fields:
+    let dlpi_addr = unsafe { (*info).dlpi_addr } as usize;
+    let dlpi_phdr = unsafe { (*info).dlpi_phdr };
+    let dlpi_phnum = unsafe { (*info).dlpi_phnum };
+    // SAFETY: We assured this.
+    let lookup = unsafe { &mut *data.cast::<Lookup>() };
+    if dlpi_phdr.is_null() || dlpi_phnum == 0 {
+        return 0;
+    }
+    // SAFETY: We just checked for nullness or 0-len slices
+    let headers = unsafe { slice::from_raw_parts(dlpi_phdr, dlpi_phnum as usize) };
+
+    let contains_addr = headers.iter().any(|header| {
+        let start = dlpi_addr.wrapping_add(header.p_vaddr as usize);
+        header.p_type == PT_LOAD && lookup.addr.wrapping_sub(start) < header.p_memsz as usize
+    });
+    if !contains_addr {
+        return 0;
+    }
+
+    let loads = headers.iter().filter(|header| header.p_type == PT_LOAD);
+    let start = loads.clone().map(|header| header.p_vaddr as usize).min();
+    let end = loads
+        .map(|header| (header.p_vaddr + header.p_memsz) as usize)
+        .max();
+    let module = lookup.module.insert(Module {
+        start: dlpi_addr.wrapping_add(start.unwrap_or(0)),
+        end: dlpi_addr.wrapping_add(end.unwrap_or(0)),
+        build_id: None,
+    });
+
+    for header in headers.iter().filter(|header| header.p_type == PT_NOTE) {
+        let start = dlpi_addr.wrapping_add(header.p_vaddr as usize);
+        // SAFETY: The loader maps all of a module's `PT_NOTE` segments, which
+        // stay mapped for as long as the module is loaded.
+        let notes = unsafe { slice::from_raw_parts(start as *const u8, header.p_memsz as usize) };
+        let Ok(mut notes) = NoteIterator::<Elf>::new(NativeEndian, header.p_align as _, notes)
+        else {
+            continue;
+        };
+        while let Ok(Some(note)) = notes.next() {
+            if note.name() == ELF_NOTE_GNU && note.n_type(NativeEndian) == NT_GNU_BUILD_ID {
+                module.build_id = Some(note.desc());
+                break;
+            }
+        }
+    }
+    // This was the module we were looking for, whether it has a build ID or not.
+    1
+}
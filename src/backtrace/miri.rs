@@ -38,7 +38,23 @@ pub struct Frame {
 unsafe impl Send for Frame {}
 unsafe impl Sync for Frame {}
 
+#[derive(Default)]
+pub(crate) struct ModuleCache;
+
 impl Frame {
+    pub fn custom(ip: *mut c_void, _sp: *mut c_void, symbol_address: *mut c_void) -> Frame {
+        Frame {
+            addr: ip,
+            inner: FullMiriFrame {
+                name: Box::new([]),
+                filename: Box::new([]),
+                lineno: 0,
+                colno: 0,
+                fn_ptr: symbol_address,
+            },
+        }
+    }
+
     pub fn ip(&self) -> *mut c_void {
         self.addr
     }
@@ -54,6 +70,23 @@ impl Frame {
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         None
     }
+
+    pub fn module_build_id(&self) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn module_build_id_cached(&self, _cache: &mut ModuleCache) -> Option<&[u8]> {
+        None
+    }
+
+    // Miri resolves frames as it walks the stack.
+    pub fn function_name(&self) -> Option<&[u8]> {
+        Some(&*self.inner.name).filter(|name| !name.is_empty())
+    }
+
+    pub fn ip_is_exact(&self) -> bool {
+        false
+    }
 }
 
 pub fn trace<F: FnMut(&super::Frame) -> bool>(cb: F) {
@@ -1,3 +1,4 @@
+use super::symbolize::SymbolName;
 use core::ffi::c_void;
 use core::fmt;
 
@@ -53,6 +54,232 @@ pub fn trace<F: FnMut(&Frame) -> bool>(cb: F) {
     unsafe { trace_unsynchronized(cb) }
 }
 
+/// Captures the frames of the current call-stack and returns an iterator over
+/// them, innermost frame first.
+///
+/// This is the same as collecting the frames passed to the closure of `trace`
+/// into a `Vec`, which means the whole stack is walked before this returns,
+/// but lets the frames be processed with iterator adapters. The frames of
+/// this function itself and of the unwinder are left out, except on
+/// platforms where frames can't be told apart by their `symbol_address`
+/// (notably macOS). To walk the stack only as far as it's iterated, see
+/// `trace_lazy`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// for frame in backtrace::frames().skip(2).take(10) {
+///     println!("{:?}", frame.ip());
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[inline(never)] // want to make sure there's a frame here to remove
+pub fn frames() -> FrameIter {
+    let entry_point = frames as *const () as usize;
+    let mut frames = std::vec::Vec::new();
+    trace(|frame| {
+        frames.push(frame.clone());
+        // clear inner frames, and start with call site.
+        if frame.symbol_address() as usize == entry_point {
+            frames.clear();
+        }
+        true
+    });
+    FrameIter {
+        frames: frames.into_iter(),
+    }
+}
+
+/// An iterator over the frames captured by `frames`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct FrameIter {
+    frames: std::vec::IntoIter<Frame>,
+}
+
+#[cfg(feature = "std")]
+impl Iterator for FrameIter {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        self.frames.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+#[cfg(feature = "std")]
+impl DoubleEndedIterator for FrameIter {
+    fn next_back(&mut self) -> Option<Frame> {
+        self.frames.next_back()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ExactSizeIterator for FrameIter {}
+
+/// Same as `trace`, except that the walk stops at the first frame which looks
+/// like it was read from a corrupt stack, and the reason the walk ended is
+/// returned.
+///
+/// Unwinding a damaged stack, such as from a crash handler after a buffer
+/// overflow, can yield frames with garbage instruction pointers, and
+/// continuing from those can produce long runs of meaningless frames or even
+/// crash. Frames are considered implausible here if their instruction pointer
+/// is in the first page of memory or outside of every library loaded into the
+/// process. Frames with a null instruction pointer, which some unwinders
+/// yield at the end of the stack, are passed on as usual.
+///
+/// Checking for libraries is done by the symbolication backend, and is only
+/// done where it can enumerate the loaded libraries (the gimli backend on
+/// most Unix platforms). Code generated at runtime, such as by a JIT, isn't
+/// in any library and so is considered implausible.
+///
+/// The loaded libraries are listed once before the stack is walked, which
+/// takes the dynamic linker's lock and allocates, so like `trace` this isn't
+/// async-signal-safe.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// use backtrace::TraceResult;
+///
+/// let mut frames = 0;
+/// match backtrace::trace_validated(|_| {
+///     frames += 1;
+///     true
+/// }) {
+///     TraceResult::Implausible(ip) => println!("{frames} frames, then garbage at {ip:?}"),
+///     _ => println!("{frames} frames"),
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn trace_validated<F: FnMut(&Frame) -> bool>(mut cb: F) -> TraceResult {
+    let _guard = crate::lock::lock();
+    let mapped = crate::symbolize::mapped_ranges();
+    let is_mapped = |ip: usize| {
+        mapped.as_ref().map_or(true, |ranges| {
+            ranges.iter().any(|range| range.contains(&ip))
+        })
+    };
+    let mut result = TraceResult::Finished;
+    unsafe {
+        trace_unsynchronized(|frame| {
+            let ip = frame.ip();
+            if !ip.is_null() && ((ip as usize) < 4096 || !is_mapped(ip as usize)) {
+                result = TraceResult::Implausible(ip);
+                return false;
+            }
+            if !cb(frame) {
+                result = TraceResult::Stopped;
+                return false;
+            }
+            true
+        })
+    }
+    result
+}
+
+/// Same as `trace`, except that the walk stops once it has taken longer than
+/// `timeout`, and the reason the walk ended is returned.
+///
+/// Unwinding a corrupt or extremely deep stack can take a long time, which a
+/// crash handler may not have. The time is only checked every few frames, to
+/// keep the cost of reading the clock down, so the walk can overrun `timeout`
+/// by the time it takes to unwind those frames, and a single frame which
+/// takes forever to unwind isn't cut short. At least the first few frames
+/// are always passed to `cb`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// use backtrace::TraceResult;
+/// use std::time::Duration;
+///
+/// let mut frames = 0;
+/// let result = backtrace::trace_with_timeout(Duration::from_millis(10), |_| {
+///     frames += 1;
+///     true
+/// });
+/// if result == TraceResult::TimedOut {
+///     println!("gave up after {frames} frames");
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn trace_with_timeout<F: FnMut(&Frame) -> bool>(
+    timeout: std::time::Duration,
+    mut cb: F,
+) -> TraceResult {
+    use std::time::Instant;
+
+    /// How many frames are walked between looking at the clock.
+    const FRAMES_PER_CHECK: usize = 32;
+
+    let _guard = crate::lock::lock();
+    // A timeout too long to represent never passes.
+    let deadline = Instant::now().checked_add(timeout);
+    let mut result = TraceResult::Finished;
+    let mut frames = 0;
+    unsafe {
+        trace_unsynchronized(|frame| {
+            if !cb(frame) {
+                result = TraceResult::Stopped;
+                return false;
+            }
+            frames += 1;
+            if frames % FRAMES_PER_CHECK == 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                result = TraceResult::TimedOut;
+                return false;
+            }
+            true
+        })
+    }
+    result
+}
+
+/// How a walk of the stack with `trace_validated` or `trace_with_timeout`
+/// ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TraceResult {
+    /// The unwinder found no more frames.
+    Finished,
+    /// The callback returned `false`.
+    Stopped,
+    /// The walk was cut short at a frame with the given, implausible,
+    /// instruction pointer. This frame wasn't passed to the callback.
+    Implausible(*mut c_void),
+    /// The walk took longer than the timeout given to `trace_with_timeout`.
+    TimedOut,
+}
+
+mod fp;
+#[cfg(feature = "std")]
+pub(crate) use self::fp::trace_current;
+pub use self::fp::trace_fp;
+
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+pub use self::lazy::{trace_lazy, LazyFrames};
+
 /// Same as `trace`, only unsafe as it's unsynchronized.
 ///
 /// This function does not have synchronization guarantees but is available
@@ -63,9 +290,80 @@ pub fn trace<F: FnMut(&Frame) -> bool>(cb: F) {
 ///
 /// See information on `trace` for caveats on `cb` panicking.
 pub unsafe fn trace_unsynchronized<F: FnMut(&Frame) -> bool>(mut cb: F) {
+    // This may be called from a signal handler which interrupted a thread in
+    // the middle of `set_unwinder`, in which case waiting for it to finish
+    // would deadlock, so the built-in implementation is used instead.
+    #[cfg(feature = "std")]
+    if let Ok(Some(unwinder)) = UNWINDER.try_get() {
+        return unwinder(&mut cb);
+    }
     trace_imp(&mut cb)
 }
 
+#[cfg(feature = "std")]
+type UnwindFn = dyn Fn(&mut dyn FnMut(&Frame) -> bool) + Send + Sync;
+
+#[cfg(feature = "std")]
+static UNWINDER: crate::hook::Hook<UnwindFn> = crate::hook::Hook::new();
+
+/// Installs `unwinder` to walk the stack in place of this crate's own
+/// unwinding implementation.
+///
+/// This is meant for targets this crate can't unwind on by itself, such as an
+/// RTOS or emulator with its own way of walking the stack, and for overriding
+/// the built-in implementation where it doesn't work well. Once installed,
+/// `unwinder` is called by `trace` and everything built on it, including
+/// `Backtrace`. It should pass every frame of the stack, created with
+/// `Frame::new`, to the callback it's given, innermost frame first, and stop
+/// as soon as the callback returns `false`.
+///
+/// The frames are resolved with the symbolication backend of this crate as
+/// usual. Calls to the unwinder are serialized by the same lock as `trace`,
+/// unless it's called through `trace_unsynchronized`.
+///
+/// Installing another unwinder replaces this one, which is dropped once
+/// no thread is walking the stack with it anymore. Use `clear_unwinder` to go
+/// back to the built-in implementation.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// use backtrace::Frame;
+///
+/// backtrace::set_unwinder(|cb| {
+///     // A stack of a single frame, as if read from some other source.
+///     let ip = 0x1234 as *mut std::ffi::c_void;
+///     cb(&Frame::new(ip, std::ptr::null_mut(), ip));
+/// });
+/// ```
+#[cfg(feature = "std")]
+pub fn set_unwinder<F>(unwinder: F)
+where
+    F: Fn(&mut dyn FnMut(&Frame) -> bool) + Send + Sync + 'static,
+{
+    UNWINDER.set(std::sync::Arc::new(unwinder));
+}
+
+/// Uninstalls the unwinder installed with `set_unwinder`, if any, so that
+/// this crate's own unwinding implementation is used again.
+///
+/// The unwinder is dropped once no thread is walking the stack with it
+/// anymore.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn clear_unwinder() {
+    UNWINDER.clear();
+}
+
 /// A trait representing one frame of a backtrace, yielded to the `trace`
 /// function of this crate.
 ///
@@ -78,6 +376,19 @@ pub struct Frame {
 }
 
 impl Frame {
+    /// Creates a frame from its instruction pointer, stack pointer and
+    /// symbol address, for unwinders installed with `set_unwinder`.
+    ///
+    /// See `ip`, `sp` and `symbol_address` for what these are. Either pointer
+    /// other than `ip` may be null if it's unknown, and `symbol_address` may
+    /// also just be `ip` again. Some platforms ignore `symbol_address` and
+    /// always use `ip` instead.
+    pub fn new(ip: *mut c_void, sp: *mut c_void, symbol_address: *mut c_void) -> Frame {
+        Frame {
+            inner: FrameImp::custom(ip, sp, symbol_address),
+        }
+    }
+
     /// Returns the current instruction pointer of this frame.
     ///
     /// This is normally the next instruction to execute in the frame, but not
@@ -114,6 +425,56 @@ impl Frame {
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         self.inner.module_base_address()
     }
+
+    /// Returns the build ID of the module to which the frame belongs.
+    ///
+    /// This is the contents of the module's GNU build ID note, which
+    /// identifies the exact build of the module. Together with `ip` it lets a
+    /// symbolizer elsewhere pick the debug information matching this frame,
+    /// even if the module's file has since been replaced on disk.
+    ///
+    /// The build ID is read from the module's memory while the stack is being
+    /// walked, so it's only available for frames passed to the callback of
+    /// `trace`, and `None` for clones of them. Use `Backtrace` to keep build
+    /// IDs along with captured frames.
+    ///
+    /// This is currently only supported on ELF platforms using libunwind,
+    /// such as Linux and the BSDs. Elsewhere, or for modules which don't have
+    /// a build ID, `None` is returned.
+    pub fn module_build_id(&self) -> Option<&[u8]> {
+        self.inner.module_build_id()
+    }
+
+    /// Same as `module_build_id`, except that modules already found by an
+    /// earlier lookup through `cache` aren't searched for again.
+    ///
+    /// Every backend has a `ModuleCache`, but those which can't look up build
+    /// IDs have nothing to cache and leave it empty.
+    #[cfg(feature = "std")]
+    pub(crate) fn module_build_id_cached(&self, cache: &mut ModuleCache) -> Option<&[u8]> {
+        self.inner.module_build_id_cached(cache)
+    }
+
+    /// Returns the name of this frame's function as the unwinder reported it
+    /// while walking the stack, without any symbolication.
+    ///
+    /// Callers which only want names, such as sampling profilers walking
+    /// stacks in a tight loop, can use this to skip `resolve_frame` and the
+    /// loading and parsing of debug information it involves. Miri resolves
+    /// frames as it walks the stack, and on Unix the dynamic linker is asked
+    /// for the name of the exported symbol the frame is in, which is only
+    /// found for functions exported from their library (or executable, if
+    /// linked with `-rdynamic`) whose start the unwinder knows, as in
+    /// `symbol_address`. Elsewhere, and for frames which have been cloned or
+    /// created with `Frame::new`, `None` is returned and the frame should be
+    /// resolved instead.
+    ///
+    /// A name found this way may also be less precise than those found by
+    /// `resolve_frame`, for example by not accounting for functions inlined
+    /// into this one.
+    pub fn function_name_from_unwinder(&self) -> Option<SymbolName<'_>> {
+        self.inner.function_name().map(SymbolName::new)
+    }
 }
 
 impl fmt::Debug for Frame {
@@ -170,6 +531,9 @@ cfg_if::cfg_if! {
         pub(crate) mod miri;
         use self::miri::trace as trace_imp;
         pub(crate) use self::miri::Frame as FrameImp;
+        #[cfg(feature = "std")]
+        pub(crate) use self::miri::ModuleCache;
+        pub(crate) const BACKEND_NAME: &str = "miri";
     } else if #[cfg(
         any(
             all(
@@ -186,6 +550,9 @@ cfg_if::cfg_if! {
         mod libunwind;
         use self::libunwind::trace as trace_imp;
         pub(crate) use self::libunwind::Frame as FrameImp;
+        #[cfg(feature = "std")]
+        pub(crate) use self::libunwind::ModuleCache;
+        pub(crate) const BACKEND_NAME: &str = "libunwind";
     } else if #[cfg(all(windows, not(target_vendor = "uwp")))] {
         cfg_if::cfg_if! {
             if #[cfg(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "arm64ec"))] {
@@ -198,9 +565,15 @@ cfg_if::cfg_if! {
         }
         use self::dbghelp::trace as trace_imp;
         pub(crate) use self::dbghelp::Frame as FrameImp;
+        #[cfg(feature = "std")]
+        pub(crate) use self::dbghelp::ModuleCache;
+        pub(crate) const BACKEND_NAME: &str = "dbghelp";
     } else {
         mod noop;
         use self::noop::trace as trace_imp;
         pub(crate) use self::noop::Frame as FrameImp;
+        #[cfg(feature = "std")]
+        pub(crate) use self::noop::ModuleCache;
+        pub(crate) const BACKEND_NAME: &str = "noop";
     }
 }
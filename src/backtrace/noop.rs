@@ -2,28 +2,63 @@
 //! appropriate.
 
 use core::ffi::c_void;
-use core::ptr::null_mut;
 
 #[inline(always)]
 pub fn trace(_cb: &mut dyn FnMut(&super::Frame) -> bool) {}
 
+// Frames can only come from a custom unwinder here, see `set_unwinder`.
 #[derive(Clone)]
-pub struct Frame;
+pub struct Frame {
+    ip: *mut c_void,
+    sp: *mut c_void,
+    symbol_address: *mut c_void,
+}
+
+// These are just addresses which are never dereferenced.
+unsafe impl Send for Frame {}
+unsafe impl Sync for Frame {}
+
+#[derive(Default)]
+pub(crate) struct ModuleCache;
 
 impl Frame {
+    pub fn custom(ip: *mut c_void, sp: *mut c_void, symbol_address: *mut c_void) -> Frame {
+        Frame {
+            ip,
+            sp,
+            symbol_address,
+        }
+    }
+
     pub fn ip(&self) -> *mut c_void {
-        null_mut()
+        self.ip
     }
 
     pub fn sp(&self) -> *mut c_void {
-        null_mut()
+        self.sp
     }
 
     pub fn symbol_address(&self) -> *mut c_void {
-        null_mut()
+        self.symbol_address
     }
 
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         None
     }
+
+    pub fn module_build_id(&self) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn module_build_id_cached(&self, _cache: &mut ModuleCache) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn function_name(&self) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn ip_is_exact(&self) -> bool {
+        false
+    }
 }
@@ -1,24 +1,52 @@
 #![allow(clippy::from_over_into)]
 
-#[cfg(feature = "serde")]
+use crate::backtrace::ModuleCache;
 use crate::resolve;
 use crate::PrintFmt;
-use crate::{resolve_frame, trace, BacktraceFmt, Symbol, SymbolName};
+use crate::{resolve_frame, trace, BacktraceFmt, Symbol, SymbolName, TraceResult};
 use core::ffi::c_void;
-use std::fmt;
+use core::ops::Range;
+use std::borrow::Cow;
+use std::env;
+use std::fmt::{self, Write as _};
+use std::panic::Location;
 use std::path::{Path, PathBuf};
 use std::prelude::v1::*;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+mod compact;
+mod diff;
+mod fold;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "resolve-async")]
+mod resolve_async;
+mod ring;
+mod thread;
+pub use self::diff::BacktraceDiff;
+pub use self::fold::fold_stacks;
+#[cfg(feature = "resolve-async")]
+pub use self::resolve_async::{resolve_async, ResolveFuture};
+pub use self::ring::BacktraceRing;
+pub use self::thread::ThreadBacktrace;
+
+/// How many symbols `Backtrace::resolve` records for a whole backtrace, only
+/// there to keep pathological ones from taking up unbounded memory.
+const DEFAULT_SYMBOL_LIMIT: usize = 1 << 16;
+
 /// Representation of an owned and self-contained backtrace.
 ///
 /// This structure can be used to capture a backtrace at various points in a
 /// program and later used to inspect what the backtrace was at that time.
 ///
 /// `Backtrace` supports pretty-printing of backtraces through its `Debug`
-/// implementation.
+/// implementation. Formatting it with a precision, as in `{:.2?}`, also prints
+/// a snippet of the source code around each frame with that many lines of
+/// context, as [`BacktraceFmt::enable_source_snippets`] does.
 ///
 /// # Required features
 ///
@@ -29,6 +57,24 @@ use serde::{Deserialize, Serialize};
 pub struct Backtrace {
     // Frames here are listed from top-to-bottom of the stack
     frames: Vec<BacktraceFrame>,
+    // The target the frames were captured on. These are `None` for backtraces
+    // deserialized from before they were recorded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    target_arch: Option<Cow<'static, str>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pointer_width: Option<u32>,
+    // Indices of the frames at which the backtrace of a spawning task picks
+    // up, see `with_spawn_context`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    spawn_points: Vec<usize>,
+    // The OS error code of the capturing thread from right before the
+    // capture, see `new_with_os_error`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    os_error: Option<i32>,
+    // Whether the unwinder came up empty while capturing this backtrace, as
+    // opposed to it being built from no frames.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    nothing_unwound: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -42,7 +88,6 @@ impl TracePtr {
     fn into_void(self) -> *mut c_void {
         self.0
     }
-    #[cfg(feature = "serde")]
     fn from_addr(addr: usize) -> Self {
         TracePtr(addr as *mut c_void)
     }
@@ -143,13 +188,21 @@ fn _assert_send_sync() {
 #[derive(Clone)]
 pub struct BacktraceFrame {
     frame: Frame,
+    module_build_id: Option<Vec<u8>>,
+    // Only known for frames decoded from elsewhere, see `module_path`.
+    module_path: Option<PathBuf>,
     symbols: Option<Vec<BacktraceSymbol>>,
+    // What kind of region the frame was in if it resolved to no symbols, see
+    // `BacktraceFrame::label_region`.
+    region: Option<&'static str>,
+    // Whether `symbols` were resolved without source locations, see
+    // `BacktraceFrame::is_names_only`.
+    names_only: bool,
 }
 
 #[derive(Clone)]
 enum Frame {
     Raw(crate::Frame),
-    #[cfg(feature = "serde")]
     Deserialized {
         ip: TracePtr,
         symbol_address: TracePtr,
@@ -161,7 +214,6 @@ impl Frame {
     fn ip(&self) -> *mut c_void {
         match *self {
             Frame::Raw(ref f) => f.ip(),
-            #[cfg(feature = "serde")]
             Frame::Deserialized { ip, .. } => ip.into_void(),
         }
     }
@@ -169,7 +221,6 @@ impl Frame {
     fn symbol_address(&self) -> *mut c_void {
         match *self {
             Frame::Raw(ref f) => f.symbol_address(),
-            #[cfg(feature = "serde")]
             Frame::Deserialized { symbol_address, .. } => symbol_address.into_void(),
         }
     }
@@ -177,7 +228,6 @@ impl Frame {
     fn module_base_address(&self) -> Option<*mut c_void> {
         match *self {
             Frame::Raw(ref f) => f.module_base_address(),
-            #[cfg(feature = "serde")]
             Frame::Deserialized {
                 module_base_address,
                 ..
@@ -185,26 +235,47 @@ impl Frame {
         }
     }
 
-    /// Resolve all addresses in the frame to their symbolic names.
-    fn resolve_symbols(&self) -> Vec<BacktraceSymbol> {
+    /// Resolve all addresses in the frame to their symbolic names, along with
+    /// their source locations if `locations` is set.
+    fn resolve_symbols(&self, locations: bool) -> Vec<BacktraceSymbol> {
         let mut symbols = Vec::new();
-        let sym = |symbol: &Symbol| {
-            symbols.push(BacktraceSymbol {
-                name: symbol.name().map(|m| m.as_bytes().to_vec()),
-                addr: symbol.addr().map(TracePtr),
-                filename: symbol.filename().map(|m| m.to_owned()),
-                lineno: symbol.lineno(),
-                colno: symbol.colno(),
-            });
-        };
-        match *self {
-            Frame::Raw(ref f) => resolve_frame(f, sym),
-            #[cfg(feature = "serde")]
-            Frame::Deserialized { ip, .. } => {
-                resolve(ip.into_void(), sym);
+        self.resolve_with(|symbol| symbols.push(BacktraceSymbol::new(symbol, locations)));
+        symbols
+    }
+
+    /// Same as `resolve_symbols` with `locations` set, unless there are more
+    /// than `limit` symbols, in which case `None` is returned without copying
+    /// the symbols beyond the limit.
+    fn resolve_symbols_limited(&self, limit: usize) -> Option<Vec<BacktraceSymbol>> {
+        let mut symbols = Vec::new();
+        let mut over = false;
+        self.resolve_with(|symbol| {
+            if symbols.len() < limit {
+                symbols.push(BacktraceSymbol::new(symbol, true));
+            } else {
+                over = true;
             }
+        });
+        Some(symbols).filter(|_| !over)
+    }
+
+    /// Resolves just the name of the function the frame is in, leaving out
+    /// any functions inlined into it, and without source locations.
+    fn resolve_function_name(&self) -> Vec<BacktraceSymbol> {
+        // The outermost symbol is reported last, should the backend report
+        // inlined functions too.
+        let mut function = None;
+        crate::resolve_name_only(self.ip(), |symbol| {
+            function = Some(BacktraceSymbol::new(symbol, false));
+        });
+        function.into_iter().collect()
+    }
+
+    fn resolve_with(&self, cb: impl FnMut(&Symbol)) {
+        match *self {
+            Frame::Raw(ref f) => resolve_frame(f, cb),
+            Frame::Deserialized { ip, .. } => resolve(ip.into_void(), cb),
         }
-        symbols
     }
 }
 
@@ -225,6 +296,11 @@ pub struct BacktraceSymbol {
     filename: Option<PathBuf>,
     lineno: Option<u32>,
     colno: Option<u32>,
+    /// Set once known, see `BacktraceFrame::resolve_optimized`.
+    #[cfg_attr(feature = "serde", serde(default, with = "serde_impls::optimized"))]
+    optimized: OnceLock<Option<bool>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    demangled: Option<String>,
 }
 
 impl Backtrace {
@@ -242,6 +318,12 @@ impl Backtrace {
     /// avoids the symbol resolution step (which typically takes the longest)
     /// and allows deferring that to a later date.
     ///
+    /// The frames of this crate's own capturing machinery, from the unwinder
+    /// up to this function, are dropped before any symbols are resolved, so
+    /// no time is spent on them. They're found by their `symbol_address`,
+    /// which isn't known on some platforms (notably macOS), where they're
+    /// kept. Use `new_unfiltered` to keep them everywhere.
+    ///
     /// # Examples
     ///
     /// ```
@@ -256,11 +338,160 @@ impl Backtrace {
     /// enabled, and the `std` feature is enabled by default.
     #[inline(never)] // want to make sure there's a frame here to remove
     pub fn new() -> Backtrace {
-        let mut bt = Self::create(Self::new as usize);
+        let mut bt = Self::create(Some(Self::new as *const () as usize), None);
+        bt.resolve();
+        bt
+    }
+
+    /// Same as `new`, except that the frames of this crate's own capturing
+    /// machinery are kept at the top of the backtrace and resolved too.
+    ///
+    /// This is mostly useful for debugging this crate or the unwinder.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[inline(never)]
+    pub fn new_unfiltered() -> Backtrace {
+        let mut bt = Self::create(None, None);
+        bt.resolve();
+        bt
+    }
+
+    /// Same as `new`, except that the backtrace stops before the first frame
+    /// of the function at `boundary`, leaving out that frame and all of its
+    /// callers.
+    ///
+    /// This is meant for code which runs other code and wants backtraces
+    /// captured in there to stop at its own entry point, such as an async
+    /// executor polling tasks or a bridge called from another language. The
+    /// frames below that point are the same for every backtrace and usually
+    /// aren't interesting.
+    ///
+    /// Frames are matched by comparing their `symbol_address` with
+    /// `boundary`, so the boundary function should be marked
+    /// `#[inline(never)]`. Note that on some platforms, notably macOS,
+    /// `symbol_address` is just the instruction pointer, so the boundary is
+    /// never found and the whole stack is captured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// #[inline(never)]
+    /// fn run_task(task: &dyn Fn()) {
+    ///     task()
+    /// }
+    ///
+    /// run_task(&|| {
+    ///     let boundary = run_task as fn(&dyn Fn()) as *mut std::ffi::c_void;
+    ///     let bt = Backtrace::new_until(boundary);
+    ///     println!("{bt:?}"); // no frames of `run_task` or its callers
+    /// });
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn new_until(boundary: *mut c_void) -> Backtrace {
+        let mut bt = Self::create(Some(Self::new_until as *const () as usize), Some(boundary));
+        bt.resolve();
+        bt
+    }
+
+    /// Returns a logical backtrace for an async task, made up of this
+    /// backtrace followed by `spawn`, the backtrace of where the task was
+    /// spawned.
+    ///
+    /// A backtrace captured from within an async task only shows the stack
+    /// of the current poll of the task, which ends in the executor rather
+    /// than in the code that created the task. Async runtimes can make up for
+    /// that by capturing a backtrace whenever a task is spawned, and combining
+    /// it with backtraces captured while the task runs using this function.
+    /// Capturing the poll-time backtrace with `new_until`, passing the
+    /// executor's function that polls tasks, leaves out the executor's own
+    /// frames in between.
+    ///
+    /// The `Debug` output marks where the frames of `spawn` begin. Both
+    /// backtraces may already be combined ones themselves, for a task spawned
+    /// from within another task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// // When the task is spawned...
+    /// let spawned_at = Backtrace::new_unresolved();
+    ///
+    /// // ... and later while it's polled.
+    /// let bt = Backtrace::new().with_spawn_context(&spawned_at);
+    /// println!("{bt:?}");
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn with_spawn_context(&self, spawn: &Backtrace) -> Backtrace {
+        let mut combined = self.clone();
+        let offset = self.frames.len();
+        combined.spawn_points.push(offset);
+        combined
+            .spawn_points
+            .extend(spawn.spawn_points.iter().map(|i| i + offset));
+        combined.frames.extend(spawn.frames.iter().cloned());
+        combined
+    }
+
+    /// Same as `new`, except that at most `max_frames` frames are captured,
+    /// starting from the caller.
+    ///
+    /// Walking the stack stops as soon as `max_frames` frames are found, so
+    /// on a very deep stack, such as that of a deeply recursive parser, no
+    /// time or memory is spent on the frames beyond those, neither to capture
+    /// them nor to resolve their symbols. On platforms where the frames of
+    /// this crate's own capturing machinery are kept (see `new`), they count
+    /// towards `max_frames`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// let bt = Backtrace::new_limited(30);
+    /// assert!(bt.frames().len() <= 30);
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn new_limited(max_frames: usize) -> Backtrace {
+        let entry_point = Self::new_limited as *const () as usize;
+        let (mut bt, _) = Self::create_with(Some(entry_point), None, None, max_frames);
         bt.resolve();
         bt
     }
 
+    /// Same as `new_limited`, except that no symbols are resolved, like with
+    /// `new_unresolved`.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn new_unresolved_limited(max_frames: usize) -> Backtrace {
+        let entry_point = Self::new_unresolved_limited as *const () as usize;
+        Self::create_with(Some(entry_point), None, None, max_frames).0
+    }
+
     /// Similar to `new` except that this does not resolve any symbols, this
     /// simply captures the backtrace as a list of addresses.
     ///
@@ -286,27 +517,217 @@ impl Backtrace {
     /// enabled, and the `std` feature is enabled by default.
     #[inline(never)] // want to make sure there's a frame here to remove
     pub fn new_unresolved() -> Backtrace {
-        Self::create(Self::new_unresolved as usize)
+        Self::create(Some(Self::new_unresolved as *const () as usize), None)
+    }
+
+    /// Same as `new`, except that the last OS error of the calling thread
+    /// (`errno` on Unix, `GetLastError` on Windows) is recorded first, and
+    /// printed along with the backtrace.
+    ///
+    /// Capturing and resolving a backtrace makes system calls of its own,
+    /// which are free to overwrite the last OS error, so a backtrace captured
+    /// right after a failing system call can't be followed by a look at
+    /// `errno`. With this the error is read before anything else happens and
+    /// is available from `os_error` afterwards. Note that the last OS error of
+    /// the thread itself may still have changed by the time this returns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// if std::fs::File::open("/does/not/exist").is_err() {
+    ///     let bt = Backtrace::new_with_os_error();
+    ///     println!("{bt:?}"); // starts with the error of the failed `open`
+    /// }
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn new_with_os_error() -> Backtrace {
+        let os_error = std::io::Error::last_os_error().raw_os_error();
+        let mut bt = Self::create(Some(Self::new_with_os_error as *const () as usize), None);
+        bt.os_error = os_error;
+        bt.resolve();
+        bt
+    }
+
+    /// Captures the backtrace of a stack other than the current one, by
+    /// following the chain of frame pointers starting at `fp`, and resolves
+    /// it.
+    ///
+    /// This is meant for coroutine and fiber libraries, which know the saved
+    /// frame pointer of a suspended fiber and can use this to find out where
+    /// it's suspended. The first frame is the caller of the function `fp`
+    /// belongs to. See `trace_fp` for how the stack is walked and on which
+    /// architectures this works.
+    ///
+    /// # Safety
+    ///
+    /// `fp` must be null or point into a valid stack in which every function
+    /// keeps a frame pointer, and the stack must not be resumed or freed
+    /// until this returns. See `trace_fp` for details.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub unsafe fn from_fp(fp: *mut c_void) -> Backtrace {
+        let mut frames = Vec::new();
+        unsafe {
+            crate::trace_fp(fp, |frame| {
+                frames.push(BacktraceFrame {
+                    frame: Frame::Raw(frame.clone()),
+                    module_build_id: None,
+                    module_path: None,
+                    symbols: None,
+                    region: None,
+                    names_only: false,
+                });
+                true
+            })
+        };
+        let mut bt = Backtrace::from(frames);
+        bt.resolve();
+        bt
+    }
+
+    /// Same as `new`, except that unwinding the stack stops once it has taken
+    /// longer than `timeout`, returning the frames found until then along
+    /// with how capturing ended, which is `TraceResult::TimedOut` if the
+    /// backtrace was cut short.
+    ///
+    /// This protects crash handlers from hanging on pathological stacks. See
+    /// `trace_with_timeout` for how precisely the timeout is kept. The time
+    /// it takes to resolve the frames afterwards isn't limited, use
+    /// `new_unresolved_with_timeout` to resolve them separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::{Backtrace, TraceResult};
+    /// use std::time::Duration;
+    ///
+    /// let (bt, result) = Backtrace::new_with_timeout(Duration::from_millis(50));
+    /// if result == TraceResult::TimedOut {
+    ///     println!("truncated backtrace:");
+    /// }
+    /// println!("{bt:?}");
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn new_with_timeout(timeout: Duration) -> (Backtrace, TraceResult) {
+        let entry_point = Self::new_with_timeout as *const () as usize;
+        let (mut bt, result) =
+            Self::create_with(Some(entry_point), None, Some(timeout), usize::MAX);
+        bt.resolve();
+        (bt, result)
+    }
+
+    /// Same as `new_with_timeout`, except that no symbols are resolved, like
+    /// with `new_unresolved`.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn new_unresolved_with_timeout(timeout: Duration) -> (Backtrace, TraceResult) {
+        let entry_point = Self::new_unresolved_with_timeout as *const () as usize;
+        Self::create_with(Some(entry_point), None, Some(timeout), usize::MAX)
+    }
+
+    /// Captures the frames below the one of the function at `entry_point`, or
+    /// all frames if that's `None`, up to the one of the function at
+    /// `boundary`.
+    fn create(entry_point: Option<usize>, boundary: Option<*mut c_void>) -> Backtrace {
+        Self::create_with(entry_point, boundary, None, usize::MAX).0
     }
 
-    fn create(ip: usize) -> Backtrace {
+    /// Same as `create`, except that unwinding stops after `timeout`, if any,
+    /// and once `max_frames` frames are captured.
+    fn create_with(
+        entry_point: Option<usize>,
+        boundary: Option<*mut c_void>,
+        timeout: Option<Duration>,
+        max_frames: usize,
+    ) -> (Backtrace, TraceResult) {
+        // The frames of the capturing machinery are only cleared once the
+        // entry point is found, so this many more are allowed until then.
+        const MAX_INNER_FRAMES: usize = 32;
+
         let mut frames = Vec::new();
-        trace(|frame| {
+        let mut modules = ModuleCache::default();
+        let mut found_entry_point = entry_point.is_none();
+        let mut unwound = false;
+        let mut push = |frame: &crate::Frame| {
+            unwound = true;
+            if boundary == Some(frame.symbol_address()) {
+                return false;
+            }
+
             frames.push(BacktraceFrame {
                 frame: Frame::Raw(frame.clone()),
+                module_build_id: frame
+                    .module_build_id_cached(&mut modules)
+                    .map(|id| id.to_vec()),
+                module_path: None,
                 symbols: None,
+                region: None,
+                names_only: false,
             });
 
             // clear inner frames, and start with call site.
-            if frame.symbol_address() as usize == ip {
+            if Some(frame.symbol_address() as usize) == entry_point {
                 frames.clear();
+                found_entry_point = true;
             }
 
-            true
-        });
+            if found_entry_point {
+                frames.len() < max_frames
+            } else {
+                frames.len() < max_frames.saturating_add(MAX_INNER_FRAMES)
+            }
+        };
+        let result = match timeout {
+            Some(timeout) => crate::trace_with_timeout(timeout, &mut push),
+            None => {
+                trace(&mut push);
+                TraceResult::Finished
+            }
+        };
+        let nothing_unwound = !unwound;
+        if nothing_unwound {
+            // Frames found by following frame pointers have no symbol
+            // address, so the entry point and boundary can't be told apart
+            // and the frames of the capturing machinery are kept.
+            crate::backtrace::trace_current(|frame| {
+                frames.push(BacktraceFrame {
+                    frame: Frame::Raw(frame.clone()),
+                    module_build_id: frame
+                        .module_build_id_cached(&mut modules)
+                        .map(|id| id.to_vec()),
+                    module_path: None,
+                    symbols: None,
+                    region: None,
+                    names_only: false,
+                });
+                frames.len() < max_frames
+            });
+        }
+        frames.truncate(max_frames);
         frames.shrink_to_fit();
 
-        Backtrace { frames }
+        let mut bt = Backtrace::from(frames);
+        bt.nothing_unwound = nothing_unwound;
+        (bt, result)
     }
 
     /// Returns the frames from when this backtrace was captured.
@@ -315,6 +736,15 @@ impl Backtrace {
     /// and the last frame is likely something about how this thread or the main
     /// function started.
     ///
+    /// The platform's unwinder may not be able to walk the stack at all,
+    /// which typically happens when the binary lacks unwind information, for
+    /// example when built with `-C panic=abort` and without
+    /// `-C force-unwind-tables`. The stack is then walked by following frame
+    /// pointers instead, on Linux and Android, which finds the frames of code
+    /// built with `-C force-frame-pointers=yes`. This is empty if that didn't
+    /// find any frames either. Either way the `Debug` output of a backtrace
+    /// captured by this process notes that the unwinder came up empty.
+    ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
@@ -323,118 +753,927 @@ impl Backtrace {
         self.frames.as_slice()
     }
 
-    /// If this backtrace was created from `new_unresolved` then this function
-    /// will resolve all addresses in the backtrace to their symbolic names.
+    /// Consumes this backtrace, returning its frames with every symbol in a
+    /// frame of its own.
     ///
-    /// If this backtrace has been previously resolved or was created through
-    /// `new`, this function does nothing.
+    /// A frame whose code was inlined has a symbol for each inlined function
+    /// as well as one for the function it was inlined into. This lists those
+    /// as separate "virtual" frames, innermost first, which all share the
+    /// address of the frame they came from, the way debuggers like gdb and
+    /// lldb number inlined functions. Frames with at most one symbol, and
+    /// unresolved frames, are returned as they are.
+    ///
+    /// Note that calling `BacktraceFrame::resolve` on a virtual frame does
+    /// nothing, as it's already resolved to its one symbol.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// for (i, frame) in Backtrace::new().into_virtual_frames().iter().enumerate() {
+    ///     let name = frame.symbols().first().and_then(|symbol| symbol.name());
+    ///     println!("#{i} {:?} {name:?}", frame.ip());
+    /// }
+    /// ```
     ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
-    pub fn resolve(&mut self) {
-        self.frames.iter_mut().for_each(BacktraceFrame::resolve);
-    }
-}
-
-impl From<Vec<BacktraceFrame>> for Backtrace {
-    fn from(frames: Vec<BacktraceFrame>) -> Self {
-        Backtrace { frames }
-    }
-}
-
-impl From<crate::Frame> for BacktraceFrame {
-    fn from(frame: crate::Frame) -> Self {
-        BacktraceFrame {
-            frame: Frame::Raw(frame),
-            symbols: None,
+    pub fn into_virtual_frames(self) -> Vec<BacktraceFrame> {
+        let mut frames = Vec::with_capacity(self.frames.len());
+        for frame in self.frames {
+            match frame.symbols {
+                Some(symbols) if symbols.len() > 1 => {
+                    frames.extend(symbols.into_iter().map(|symbol| BacktraceFrame {
+                        frame: frame.frame.clone(),
+                        module_build_id: frame.module_build_id.clone(),
+                        module_path: frame.module_path.clone(),
+                        symbols: Some(vec![symbol]),
+                        region: None,
+                        names_only: frame.names_only,
+                    }));
+                }
+                symbols => frames.push(BacktraceFrame { symbols, ..frame }),
+            }
         }
+        frames
     }
-}
-
-// we don't want to implement `impl From<Backtrace> for Vec<BacktraceFrame>` on purpose,
-// because "... additional directions for Vec<T> can weaken type inference ..."
-// more information on https://github.com/rust-lang/backtrace-rs/pull/526
-impl Into<Vec<BacktraceFrame>> for Backtrace {
-    fn into(self) -> Vec<BacktraceFrame> {
-        self.frames
-    }
-}
 
-impl BacktraceFrame {
-    /// Same as `Frame::ip`
+    /// Returns the CPU architecture of the process this backtrace was
+    /// captured in, using the same names as `cfg!(target_arch = "...")`, for
+    /// example `"x86_64"` or `"aarch64"`.
+    ///
+    /// This is recorded so that a backtrace which has been serialized and
+    /// sent elsewhere, for example in a crash report, can be symbolicated
+    /// against binaries for the right architecture. It's `None` only for
+    /// backtraces deserialized from data which didn't record it.
     ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
-    pub fn ip(&self) -> *mut c_void {
-        self.frame.ip()
+    pub fn target_arch(&self) -> Option<&str> {
+        self.target_arch.as_deref()
     }
 
-    /// Same as `Frame::symbol_address`
+    /// Returns the pointer width in bits of the process this backtrace was
+    /// captured in, as in `cfg!(target_pointer_width = "...")`.
+    ///
+    /// Like `target_arch`, this is `None` only for backtraces deserialized
+    /// from data which didn't record it.
     ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
-    pub fn symbol_address(&self) -> *mut c_void {
-        self.frame.symbol_address()
+    pub fn pointer_width(&self) -> Option<u32> {
+        self.pointer_width
     }
 
-    /// Same as `Frame::module_base_address`
+    /// Returns the last OS error of the thread this backtrace was captured on,
+    /// as it was right before capturing it, if it was captured with
+    /// `new_with_os_error`.
+    ///
+    /// Pass this to `std::io::Error::from_raw_os_error` for a description of
+    /// the error.
     ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
-    pub fn module_base_address(&self) -> Option<*mut c_void> {
-        self.frame.module_base_address()
+    pub fn os_error(&self) -> Option<i32> {
+        self.os_error
     }
 
-    /// Returns the list of symbols that this frame corresponds to.
+    /// If this backtrace was created from `new_unresolved` then this function
+    /// will resolve all addresses in the backtrace to their symbolic names.
     ///
-    /// Normally there is only one symbol per frame, but sometimes if a number
-    /// of functions are inlined into one frame then multiple symbols will be
-    /// returned. The first symbol listed is the "innermost function", whereas
-    /// the last symbol is the outermost (last caller).
+    /// If this backtrace has been previously resolved or was created through
+    /// `new`, this function does nothing.
     ///
-    /// Note that if this frame came from an unresolved backtrace then this will
-    /// return an empty list.
+    /// Symbol names are stored as they appear in the binary, and are only
+    /// demangled when they're displayed, so resolving doesn't pay for
+    /// demangling up front.
+    ///
+    /// At most 65536 symbols are recorded for the whole backtrace, far more
+    /// than any reasonable backtrace has, see `resolve_with_symbol_limit`.
     ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
-    pub fn symbols(&self) -> &[BacktraceSymbol] {
-        self.symbols.as_ref().map(|s| &s[..]).unwrap_or(&[])
+    pub fn resolve(&mut self) {
+        self.resolve_with_symbol_limit(DEFAULT_SYMBOL_LIMIT);
     }
 
-    /// Resolve all addresses in this frame to their symbolic names.
+    /// Same as `resolve`, except that at most `limit` symbols are recorded
+    /// for the whole backtrace, counting the symbols of frames which were
+    /// already resolved.
     ///
-    /// If this frame has been previously resolved, this function does nothing.
+    /// Each frame resolves to a symbol for every function inlined into it, so
+    /// deep recursion through heavily inlined code can make for an enormous
+    /// number of symbols. Once a frame's symbols would go over the limit, it
+    /// and all frames after it only get the name of the function they're in,
+    /// without inlined functions or source locations, as if resolved with
+    /// `resolve_name_only`, and `BacktraceFrame::is_names_only` returns `true`
+    /// for them. Frames which can't be resolved that way are left without
+    /// symbols.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// let mut bt = Backtrace::new_unresolved();
+    /// bt.resolve_with_symbol_limit(1000);
+    /// let total: usize = bt.frames().iter().map(|f| f.symbols().len()).sum();
+    /// assert!(total <= 1000.max(bt.frames().len()));
+    /// ```
     ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
-    pub fn resolve(&mut self) {
-        if self.symbols.is_none() {
-            self.symbols = Some(self.frame.resolve_symbols());
+    pub fn resolve_with_symbol_limit(&mut self, limit: usize) {
+        let mut remaining = limit;
+        let mut regions = None;
+        for frame in self.frames.iter_mut() {
+            if frame.symbols.is_none() {
+                let mut symbols = None;
+                if remaining > 0 {
+                    symbols = frame.frame.resolve_symbols_limited(remaining);
+                }
+                frame.symbols = Some(symbols.unwrap_or_else(|| {
+                    remaining = 0;
+                    frame.names_only = true;
+                    frame.frame.resolve_function_name()
+                }));
+                frame.label_region(&mut regions);
+            }
+            remaining = remaining.saturating_sub(frame.symbols().len());
         }
     }
-}
 
-impl BacktraceSymbol {
-    /// Same as `Symbol::name`
+    /// Resolves the symbols of just the frame at `index`, see
+    /// `BacktraceFrame::resolve`.
+    ///
+    /// This is meant for showing a backtrace a few frames at a time, such as
+    /// only the top frames until the rest are asked for, without paying for
+    /// resolving frames which are never looked at. Frames which were already
+    /// resolved are left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds of `frames`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// let mut bt = Backtrace::new_unresolved();
+    /// for i in 0..bt.frames().len().min(3) {
+    ///     bt.resolve_frame(i);
+    /// }
+    /// println!("{bt:?}"); // names of the top three frames only
+    /// ```
     ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
-    pub fn name(&self) -> Option<SymbolName<'_>> {
-        self.name.as_ref().map(|s| SymbolName::new(s))
+    pub fn resolve_frame(&mut self, index: usize) {
+        self.frames[index].resolve();
+    }
+
+    /// Like `resolve`, but only records the names and addresses of symbols,
+    /// leaving their filenames, line numbers and column numbers as `None`.
+    ///
+    /// This is meant for backtraces which are kept around for a long time,
+    /// such as ones stored in error values or logs, where the names are all
+    /// that's wanted and keeping a path for every symbol would be wasteful.
+    /// Note that resolution still has to consult the same debug information,
+    /// so this isn't significantly faster than `resolve`.
+    ///
+    /// Frames resolved this way count as resolved, so a later call to
+    /// `resolve` won't fill in their source locations, but they can be told
+    /// apart from fully resolved frames with `BacktraceFrame::is_names_only`.
+    /// Frames which were already resolved are left untouched.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn resolve_names_only(&mut self) {
+        let mut regions = None;
+        for frame in self.frames.iter_mut() {
+            if frame.symbols.is_none() {
+                frame.symbols = Some(frame.frame.resolve_symbols(false));
+                frame.names_only = true;
+                frame.label_region(&mut regions);
+            }
+        }
+    }
+
+    /// Demangles the C++ names of this backtrace ahead of time, spreading the
+    /// work over several threads, so that formatting the backtrace later on
+    /// doesn't demangle them one after the other.
+    ///
+    /// Demangling C++ names, especially of heavily templated code, takes
+    /// much longer than demangling Rust names, and can dominate the time it
+    /// takes to print backtraces with hundreds of them. The demangled names
+    /// are kept with the backtrace, so this only needs to be done once, after
+    /// the backtrace is resolved. Small backtraces are demangled on the
+    /// current thread.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `parallel-demangle` feature of the
+    /// `backtrace` crate to be enabled.
+    #[cfg(feature = "parallel-demangle")]
+    pub fn demangle_names(&mut self) {
+        // How many names are worth the cost of spawning a thread.
+        const NAMES_PER_THREAD: usize = 32;
+
+        fn demangle(symbols: &mut [&mut BacktraceSymbol]) {
+            for symbol in symbols {
+                if let Some(ref name) = symbol.name {
+                    symbol.demangled = SymbolName::new(name).render_cpp();
+                }
+            }
+        }
+
+        let mut pending: Vec<&mut BacktraceSymbol> = self
+            .frames
+            .iter_mut()
+            .filter_map(|frame| frame.symbols.as_mut())
+            .flatten()
+            .filter(|symbol| symbol.demangled.is_none() && symbol.name.is_some())
+            .collect();
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(pending.len() / NAMES_PER_THREAD);
+        if threads <= 1 {
+            return demangle(&mut pending);
+        }
+        let chunk = pending.len().div_ceil(threads);
+        std::thread::scope(|s| {
+            for symbols in pending.chunks_mut(chunk) {
+                s.spawn(|| demangle(symbols));
+            }
+        });
+    }
+
+    /// Returns whether the frames of this backtrace are likely to come from
+    /// optimized code, where functions may have been inlined into their
+    /// callers or turned into tail calls and so be missing from the trace.
+    ///
+    /// This is a best-effort guess from the resolved symbols, see
+    /// `BacktraceSymbol::is_optimized`. It's `Some(true)` if any frame is
+    /// known to be optimized, which includes frames resolving to several
+    /// symbols because of inlining, `Some(false)` if some frames are known to
+    /// be unoptimized and none to be optimized, and `None` if nothing is known
+    /// either way. Frames which haven't been resolved yet are ignored.
+    ///
+    /// Whether a symbol is optimized isn't looked up when resolving, as it
+    /// takes another walk over the debug information, so this looks it up
+    /// the first time for the resolved symbols which don't know yet.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn optimization_hint(&self) -> Option<bool> {
+        let mut hint = None;
+        for frame in self.frames.iter() {
+            frame.resolve_optimized();
+            let symbols = match frame.symbols.as_deref() {
+                Some(symbols) => symbols,
+                None => continue,
+            };
+            if symbols.len() > 1 {
+                hint = Some(true);
+            }
+            for symbol in symbols {
+                match symbol.is_optimized() {
+                    Some(true) => hint = Some(true),
+                    Some(false) => hint = hint.or(Some(false)),
+                    None => {}
+                }
+            }
+        }
+        hint
+    }
+
+    /// Finds the places in this backtrace where frames are likely missing
+    /// because a function ended in a tail call, returning the index of each
+    /// frame whose caller is missing along with the name of the function
+    /// which likely made the tail call.
+    ///
+    /// Optimized code often replaces a call at the end of a function with a
+    /// jump, reusing the frame of the function for the callee so that the
+    /// function no longer shows up in the backtrace. This compares the
+    /// function each frame was calling, according to the call site
+    /// information in the debug information (see `Symbol::call_target`),
+    /// with the function of the frame above it. If they differ, the named
+    /// function was called but tail-called its way to the frame above.
+    ///
+    /// This is a best-effort guess: compilers only emit call site information
+    /// for optimized code and only for direct calls, so most gaps can't be
+    /// found, and functions with several names could be mistaken for a gap.
+    /// The backtrace should be resolved first, and the call site information
+    /// is looked up again for each frame, so this is about as expensive as
+    /// resolving the backtrace.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn elided_tail_calls(&self) -> Vec<(usize, String)> {
+        self.elided_tail_calls_with(|caller| {
+            // Calls are made from the innermost function of the caller's
+            // frame, which is resolved first.
+            let mut target = None;
+            let mut first = true;
+            caller.frame.resolve_with(|symbol| {
+                if first {
+                    target = symbol
+                        .call_target()
+                        .map(|n| (n.as_bytes().to_vec(), format!("{n:#}")));
+                }
+                first = false;
+            });
+            target
+        })
+    }
+
+    /// Same as `elided_tail_calls`, with `call_target` looking up the raw and
+    /// demangled name of the function each frame was calling.
+    fn elided_tail_calls_with(
+        &self,
+        mut call_target: impl FnMut(&BacktraceFrame) -> Option<(Vec<u8>, String)>,
+    ) -> Vec<(usize, String)> {
+        let mut gaps = Vec::new();
+        for (i, pair) in self.frames.windows(2).enumerate() {
+            let (callee, caller) = (&pair[0], &pair[1]);
+            let Some(callee) = callee.symbols().last().and_then(|s| s.name()) else {
+                continue;
+            };
+            let Some((raw, demangled)) = call_target(caller) else {
+                continue;
+            };
+            if raw != callee.as_bytes() && demangled != format!("{callee:#}") {
+                gaps.push((i, demangled));
+            }
+        }
+        gaps
+    }
+
+    /// Compares this backtrace with `other`, finding where the two stacks
+    /// diverge.
+    ///
+    /// The returned `BacktraceDiff` holds the outermost frames both backtraces
+    /// share and the leading frames in which they differ, and its `Display`
+    /// implementation renders it like a unified diff. Frames are compared by
+    /// symbol name, so both backtraces should be resolved first, but they
+    /// don't need to come from the same process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// let expected = Backtrace::new();
+    /// let actual = Backtrace::new();
+    /// println!("{}", expected.diff(&actual));
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn diff<'a>(&'a self, other: &'a Backtrace) -> BacktraceDiff<'a> {
+        BacktraceDiff::new(self, other)
+    }
+
+    /// Encodes this backtrace into a compact binary format, for sending it
+    /// elsewhere to be resolved.
+    ///
+    /// Only what's needed to resolve the backtrace later is kept: the
+    /// instruction pointer and symbol address of each frame, and the base
+    /// address, build ID and path of the modules the frames are in, where
+    /// known. The paths of the modules of frames captured by this process are
+    /// looked up among the libraries loaded while encoding, and are available
+    /// from `BacktraceFrame::module_path` after decoding, unless they aren't
+    /// valid UTF-8. Modules are listed
+    /// once rather than for every frame. Any symbols which were already
+    /// resolved are left out, which keeps the result a fraction of the size
+    /// of a serialized resolved backtrace.
+    ///
+    /// The format is versioned, and backtraces encoded by one version of
+    /// this crate can be decoded with `from_compact_bytes` by later versions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// let bytes = Backtrace::new_unresolved().to_compact_bytes();
+    /// // ... send `bytes` off somewhere ...
+    /// let mut bt = Backtrace::from_compact_bytes(&bytes).unwrap();
+    /// bt.resolve();
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        compact::encode(self)
+    }
+
+    /// Decodes a backtrace encoded with `to_compact_bytes`.
+    ///
+    /// The returned backtrace is unresolved. Returns `None` if `bytes` isn't a
+    /// valid encoding, or holds addresses which don't fit in a pointer of the
+    /// current platform.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Option<Backtrace> {
+        compact::decode(bytes)
+    }
+
+    /// Creates an unresolved backtrace out of the instruction pointers of its
+    /// frames, innermost first, such as ones logged by a crash handler.
+    ///
+    /// Each frame's `symbol_address` is the same as its `ip`, and the module
+    /// of each frame is unknown. Call `resolve` to resolve the frames, which
+    /// only gives meaningful results for addresses from this same process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// let ips: Vec<usize> = Backtrace::new_unresolved()
+    ///     .frames()
+    ///     .iter()
+    ///     .map(|frame| frame.ip() as usize)
+    ///     .collect();
+    /// // ... write `ips` to a log and read them back ...
+    /// let mut bt = Backtrace::from_ips(&ips);
+    /// bt.resolve();
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn from_ips(ips: &[usize]) -> Backtrace {
+        let frames = ips
+            .iter()
+            .map(|&ip| BacktraceFrame {
+                frame: Frame::Deserialized {
+                    ip: TracePtr::from_addr(ip),
+                    symbol_address: TracePtr::from_addr(ip),
+                    module_base_address: None,
+                },
+                module_build_id: None,
+                module_path: None,
+                symbols: None,
+                region: None,
+                names_only: false,
+            })
+            .collect::<Vec<_>>();
+        Backtrace::from(frames)
+    }
+
+    /// Encodes this backtrace as JSON, for tools which want to pass it on
+    /// without depending on serde.
+    ///
+    /// Unlike the serde support, the format is documented and versioned, so it
+    /// stays stable across versions of this crate. The result is an object
+    /// like
+    ///
+    /// ```json
+    /// {"version":1,"frames":[
+    ///   {"ip":"0x55d0c0a1b2c3","symbol_address":"0x55d0c0a1b200","symbols":[
+    ///     {"name":"my_crate::main","filename":"src/main.rs","lineno":4}
+    ///   ]}
+    /// ]}
+    /// ```
+    ///
+    /// without the whitespace. Frames are listed innermost first.
+    /// Instruction pointers and symbol addresses are hexadecimal strings.
+    /// `symbols` is `null` if the backtrace hasn't been resolved. A symbol's
+    /// `name`, `filename` and `lineno` are `null` when unknown. Names and
+    /// paths which aren't valid UTF-8 are converted lossily. Later versions
+    /// of the format only add fields, and bump `version` if they change the
+    /// meaning of existing ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// let json = Backtrace::new().to_json();
+    /// assert!(json.starts_with(r#"{"version":1,"#));
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `json` feature of the `backtrace` crate to
+    /// be enabled.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        json::encode(self)
+    }
+
+    /// Returns a hash of the symbol names in this backtrace, suitable for
+    /// grouping together reports of the same crash.
+    ///
+    /// This is `top_signature_hash` over all frames of the backtrace.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn signature_hash(&self) -> u64 {
+        self.top_signature_hash(usize::MAX)
+    }
+
+    /// Returns a hash of the symbol names in the innermost `frames` frames of
+    /// this backtrace, suitable for grouping together reports of the same
+    /// crash.
+    ///
+    /// Only the demangled names of symbols, without their trailing hashes, are
+    /// hashed. Instruction pointers and source locations are left out, so the
+    /// same crash hashes to the same value across runs with address space
+    /// layout randomization and across rebuilds of the same code. The hash
+    /// function is fixed, and hashes can be stored and compared between
+    /// processes and versions of this crate.
+    ///
+    /// Frames which are the same for every crash are skipped before counting
+    /// `frames`: those of this crate capturing the backtrace, of std's panic
+    /// machinery leading up to the panic hook, and of the C runtime calling
+    /// `main`. So from a panic hook, the top frames are those of the code that
+    /// panicked.
+    ///
+    /// The backtrace should be resolved first. Frames which haven't been
+    /// resolved, and symbols without a name, all hash the same way, so they
+    /// only contribute to the hash through their position.
+    ///
+    /// Crash reporters commonly only consider the top few frames, so that a
+    /// crash reached through different callers is still grouped together.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn top_signature_hash(&self, frames: usize) -> u64 {
+        // 64-bit FNV-1a, which unlike `DefaultHasher` is guaranteed to never
+        // change.
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        let mut write = |bytes: &[u8]| {
+            for &b in bytes {
+                hash ^= u64::from(b);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        let mut name = String::new();
+        let same_for_every_crash = |frame: &&BacktraceFrame| {
+            let symbols = frame.symbols();
+            !symbols.is_empty()
+                && symbols.iter().all(|symbol| {
+                    symbol.name().is_some_and(|name| {
+                        is_panic_machinery(&name)
+                            || crate::symbolize::is_backtrace_internal(&name)
+                            || crate::print::is_runtime_entry_point(name.as_bytes())
+                    })
+                })
+        };
+        for frame in self
+            .frames
+            .iter()
+            .filter(|frame| !same_for_every_crash(frame))
+            .take(frames)
+        {
+            for symbol in frame.symbols() {
+                name.clear();
+                if let Some(n) = symbol.name() {
+                    let _ = write!(name, "{n:#}");
+                }
+                write(name.as_bytes());
+                // Bytes which never appear in UTF-8 separate symbols and
+                // frames.
+                write(&[0xff]);
+            }
+            write(&[0xfe]);
+        }
+        hash
+    }
+
+    /// Pins the panic site in this backtrace to the `location` std reported
+    /// for the panic, returning whether the panic site was found.
+    ///
+    /// This is meant to be called from a panic hook with the location from
+    /// `PanicHookInfo::location`. That location is recorded by the compiler
+    /// at the panic site, so it's exact even when the line numbers found in
+    /// debug information are imprecise, as they often are in optimized code.
+    ///
+    /// The backtrace is resolved if it wasn't already. The panic site is taken
+    /// to be the first symbol past the panic machinery of the standard
+    /// library whose filename ends with `location.file()`, and its line and
+    /// column numbers are replaced with those of `location`. If no such symbol
+    /// is found, for example because there's no debug information, the
+    /// backtrace is left as it is and `false` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// std::panic::set_hook(Box::new(|info| {
+    ///     let mut backtrace = Backtrace::new();
+    ///     if let Some(location) = info.location() {
+    ///         backtrace.apply_panic_location(location);
+    ///     }
+    ///     eprintln!("{info}\n{backtrace:?}");
+    /// }));
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn apply_panic_location(&mut self, location: &Location<'_>) -> bool {
+        self.resolve();
+
+        // Frames of the panic hook itself may well come from the same file as
+        // the panic, so only look beyond the point where std started
+        // panicking, if it can be told.
+        let in_machinery = |(_, symbol): &(usize, &BacktraceSymbol)| {
+            symbol.name().is_some_and(|n| is_panic_machinery(&n))
+        };
+        let mut symbols = self.frames.iter().flat_map(|f| f.symbols()).enumerate();
+        let start = symbols.find(in_machinery).map_or(0, |(i, _)| i + 1);
+        let start = start + symbols.take_while(in_machinery).count();
+
+        let file = Path::new(location.file());
+        let site = self
+            .frames
+            .iter_mut()
+            .flat_map(|f| f.symbols.iter_mut().flatten())
+            .skip(start)
+            .find(|symbol| symbol.filename.as_ref().is_some_and(|f| f.ends_with(file)));
+        match site {
+            Some(symbol) => {
+                symbol.lineno = Some(location.line());
+                symbol.colno = Some(location.column());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns whether `name` belongs to the functions std goes through between a
+/// panic being raised and the panic hook being called.
+fn is_panic_machinery(name: &SymbolName<'_>) -> bool {
+    let name = format!("{name:#}");
+    name.starts_with("std::panicking::")
+        || name.starts_with("core::panicking::")
+        || name.ends_with("rust_begin_unwind")
+        || name.contains("__rust_end_short_backtrace")
+}
+
+impl From<Vec<BacktraceFrame>> for Backtrace {
+    fn from(frames: Vec<BacktraceFrame>) -> Self {
+        Backtrace {
+            frames,
+            target_arch: Some(Cow::Borrowed(env::consts::ARCH)),
+            pointer_width: Some(usize::BITS),
+            spawn_points: Vec::new(),
+            os_error: None,
+            nothing_unwound: false,
+        }
+    }
+}
+
+impl From<crate::Frame> for BacktraceFrame {
+    fn from(frame: crate::Frame) -> Self {
+        BacktraceFrame {
+            module_build_id: frame.module_build_id().map(|id| id.to_vec()),
+            module_path: None,
+            frame: Frame::Raw(frame),
+            symbols: None,
+            region: None,
+            names_only: false,
+        }
+    }
+}
+
+// we don't want to implement `impl From<Backtrace> for Vec<BacktraceFrame>` on purpose,
+// because "... additional directions for Vec<T> can weaken type inference ..."
+// more information on https://github.com/rust-lang/backtrace-rs/pull/526
+impl Into<Vec<BacktraceFrame>> for Backtrace {
+    fn into(self) -> Vec<BacktraceFrame> {
+        self.frames
+    }
+}
+
+impl BacktraceFrame {
+    /// Same as `Frame::ip`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn ip(&self) -> *mut c_void {
+        self.frame.ip()
+    }
+
+    /// Same as `Frame::symbol_address`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn symbol_address(&self) -> *mut c_void {
+        self.frame.symbol_address()
+    }
+
+    /// Same as `Frame::module_base_address`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn module_base_address(&self) -> Option<*mut c_void> {
+        self.frame.module_base_address()
+    }
+
+    /// Same as `Frame::module_build_id`, as recorded when the backtrace was
+    /// captured.
+    ///
+    /// This is only available for frames captured through `Backtrace::new`
+    /// or `Backtrace::new_unresolved`, or converted from frames passed
+    /// directly to the callback of `trace`, since the build ID can't be
+    /// recovered from a cloned `Frame`. It's preserved when serializing.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn module_build_id(&self) -> Option<&[u8]> {
+        self.module_build_id.as_deref()
+    }
+
+    /// Returns the path of the module this frame was captured in, as
+    /// recorded by the process which captured it.
+    ///
+    /// Looking up a frame's module in the process it was captured in is
+    /// better done with `native_libraries`, so this is only known for frames
+    /// decoded with `Backtrace::from_compact_bytes` or deserialized, when the
+    /// encoding process knew the path. It's `None` for frames captured here.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn module_path(&self) -> Option<&Path> {
+        self.module_path.as_deref()
+    }
+
+    /// Returns the list of symbols that this frame corresponds to.
+    ///
+    /// Normally there is only one symbol per frame, but sometimes if a number
+    /// of functions are inlined into one frame then multiple symbols will be
+    /// returned. The first symbol listed is the "innermost function", whereas
+    /// the last symbol is the outermost (last caller).
+    ///
+    /// Note that if this frame came from an unresolved backtrace then this will
+    /// return an empty list.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn symbols(&self) -> &[BacktraceSymbol] {
+        self.symbols.as_ref().map(|s| &s[..]).unwrap_or(&[])
+    }
+
+    /// Returns whether this frame was resolved by name only, with
+    /// `Backtrace::resolve_names_only` or past the limit of
+    /// `Backtrace::resolve_with_symbol_limit`.
+    ///
+    /// The symbols of such frames have names and addresses but no filenames,
+    /// line numbers or column numbers, even if the debug information has
+    /// them, unlike frames which are fully resolved but have no source
+    /// locations to show. Unresolved frames return `false`.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn is_names_only(&self) -> bool {
+        self.names_only
+    }
+
+    /// Resolve all addresses in this frame to their symbolic names.
+    ///
+    /// If this frame has been previously resolved, this function does nothing.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn resolve(&mut self) {
+        if self.symbols.is_none() {
+            self.symbols = Some(self.frame.resolve_symbols(true));
+            self.label_region(&mut None);
+        }
+    }
+
+    /// Records what kind of region the frame is in, like the stack or the
+    /// heap, if it resolved to no symbols, for printing in place of a name.
+    /// `regions` are those of this process as listed by the first frame which
+    /// needs them, so only frames captured in this process are labeled.
+    fn label_region(&mut self, regions: &mut Option<Vec<(Range<usize>, &'static str)>>) {
+        let unresolved = self.symbols.as_ref().is_some_and(Vec::is_empty);
+        if !unresolved || !matches!(self.frame, Frame::Raw(_)) || self.ip().is_null() {
+            return;
+        }
+        let regions = regions.get_or_insert_with(crate::symbolize::special_regions);
+        let ip = self.ip() as usize;
+        self.region = regions
+            .iter()
+            .find(|(range, _)| range.contains(&ip))
+            .map(|&(_, label)| label);
+    }
+
+    /// The label recorded by `label_region`, if any.
+    pub(crate) fn region_label(&self) -> Option<&'static str> {
+        self.region
+    }
+
+    /// Looks up whether the resolved symbols are optimized, unless that's
+    /// known already, and records the answer in them.
+    ///
+    /// Only frames captured in this process can be looked up, and symbols
+    /// resolved differently this time around, say because they were resolved
+    /// by name only, are recorded as not knowing, so that each frame is
+    /// looked up at most once.
+    fn resolve_optimized(&self) {
+        let symbols = match self.symbols.as_deref() {
+            Some(symbols) if symbols.iter().any(|s| s.optimized.get().is_none()) => symbols,
+            _ => return,
+        };
+        let mut optimized = Vec::new();
+        if let Frame::Raw(ref frame) = self.frame {
+            resolve_frame(frame, |symbol| optimized.push(symbol.is_optimized()));
+        }
+        if optimized.len() != symbols.len() {
+            optimized = vec![None; symbols.len()];
+        }
+        for (symbol, optimized) in symbols.iter().zip(optimized) {
+            let _ = symbol.optimized.set(optimized);
+        }
+    }
+}
+
+impl BacktraceSymbol {
+    /// Copies `symbol`, leaving out its source location unless `locations`
+    /// is set.
+    fn new(symbol: &Symbol, locations: bool) -> BacktraceSymbol {
+        BacktraceSymbol {
+            name: symbol.name().map(|m| m.as_bytes().to_vec()),
+            addr: symbol.addr().map(TracePtr),
+            filename: symbol.filename().filter(|_| locations).map(remap_filename),
+            lineno: symbol.lineno().filter(|_| locations),
+            colno: symbol.colno().filter(|_| locations),
+            optimized: OnceLock::new(),
+            demangled: None,
+        }
+    }
+
+    /// Same as `Symbol::name`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn name(&self) -> Option<SymbolName<'_>> {
+        let name = self.name.as_ref()?;
+        Some(match self.demangled {
+            Some(ref demangled) => SymbolName::with_rendered(name, demangled),
+            None => SymbolName::new(name),
+        })
     }
 
     /// Same as `Symbol::addr`
@@ -476,6 +1715,100 @@ impl BacktraceSymbol {
     pub fn colno(&self) -> Option<u32> {
         self.colno
     }
+
+    /// Same as `Symbol::is_optimized`
+    ///
+    /// This isn't looked up when resolving, and is `None` until
+    /// `Backtrace::optimization_hint` has been called on the backtrace.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn is_optimized(&self) -> Option<bool> {
+        self.optimized.get().copied().flatten()
+    }
+}
+
+type RemapFn = dyn Fn(&Path) -> Option<PathBuf> + Send + Sync;
+
+static FILENAME_REMAPPER: crate::hook::Hook<RemapFn> = crate::hook::Hook::new();
+
+/// Installs `remapper` to rewrite the source filenames recorded in
+/// `BacktraceSymbol`s, like the `set substitute-path` command of GDB.
+///
+/// This is meant for binaries built somewhere other than where their source
+/// is looked at, such as in CI, whose debug information has paths which
+/// don't exist on the machine the backtraces are looked at on. `remapper` is
+/// given the filename of every symbol resolved into a `BacktraceSymbol`,
+/// which happens when a `Backtrace` is resolved and in `resolve_owned`, and
+/// returns the filename to record instead, or `None` to keep it. The
+/// filenames `Symbol::filename` passes to the closures of `resolve` and
+/// friends are left alone.
+///
+/// Installing another remapper replaces this one, which is dropped once no
+/// filename is being remapped with it anymore.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// use std::path::Path;
+///
+/// backtrace::set_filename_remapper(|path| {
+///     let relative = path.strip_prefix("/build/ci/checkout").ok()?;
+///     Some(Path::new("/home/me/project").join(relative))
+/// });
+/// ```
+pub fn set_filename_remapper<F>(remapper: F)
+where
+    F: Fn(&Path) -> Option<PathBuf> + Send + Sync + 'static,
+{
+    FILENAME_REMAPPER.set(std::sync::Arc::new(remapper));
+}
+
+/// Returns the filename to record for a symbol at `path`, after running it
+/// through the remapper installed with `set_filename_remapper`, if any.
+fn remap_filename(path: &Path) -> PathBuf {
+    FILENAME_REMAPPER
+        .get()
+        .and_then(|remap| remap(path))
+        .unwrap_or_else(|| path.to_owned())
+}
+
+/// Resolves an address to its symbols, returning owned copies of them rather
+/// than passing them to a closure like `resolve` does.
+///
+/// The symbols are listed just like `resolve` passes them on, innermost
+/// function first, with one symbol for every function inlined at `addr`. An
+/// empty list is returned if nothing was found. Like `resolve`, `addr` is
+/// assumed to be a return address.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// backtrace::trace(|frame| {
+///     for symbol in backtrace::resolve_owned(frame.ip()) {
+///         println!("{:?}", symbol.name());
+///     }
+///     false // only look at the top frame
+/// });
+/// ```
+pub fn resolve_owned(addr: *mut c_void) -> Vec<BacktraceSymbol> {
+    let mut symbols = Vec::new();
+    resolve(addr, |symbol| {
+        symbols.push(BacktraceSymbol::new(symbol, true))
+    });
+    symbols
 }
 
 impl fmt::Debug for Backtrace {
@@ -494,6 +1827,8 @@ impl fmt::Debug for Backtrace {
         let mut print_path =
             move |fmt: &mut fmt::Formatter<'_>, path: crate::BytesOrWideString<'_>| {
                 let path = path.into_path_buf();
+                #[cfg(windows)]
+                let path = crate::types::path_without_verbatim_prefix(path);
                 if style == PrintFmt::Full {
                     if let Ok(cwd) = &cwd {
                         if let Ok(suffix) = path.strip_prefix(cwd) {
@@ -504,9 +1839,33 @@ impl fmt::Debug for Backtrace {
                 fmt::Display::fmt(&path.display(), fmt)
             };
 
+        let context_lines = fmt.precision();
         let mut f = BacktraceFmt::new(fmt, style, &mut print_path);
+        if let Some(context_lines) = context_lines {
+            f.enable_source_snippets(context_lines);
+        }
         f.add_context()?;
-        for frame in &self.frames {
+        if let Some(code) = self.os_error {
+            let error = std::io::Error::from_raw_os_error(code);
+            f.message(&format!("last OS error: {error}\n"))?;
+        }
+        if self.nothing_unwound {
+            // The unwinder gives no indication of why it came up empty, but
+            // the usual culprit is unwind information which was stripped from
+            // the binary or never generated for it.
+            f.message(if self.frames.is_empty() {
+                "<no frames were captured, is unwind information (such as \
+                 `.eh_frame`) missing from the binary?>\n"
+            } else {
+                "<no frames were unwound, is unwind information (such as \
+                 `.eh_frame`) missing from the binary? the frames below were \
+                 found by following frame pointers>\n"
+            })?;
+        }
+        for (i, frame) in self.frames.iter().enumerate() {
+            if self.spawn_points.contains(&i) {
+                f.message("      --- task spawned from ---\n")?;
+            }
             f.frame().backtrace_frame(frame)?;
         }
         f.finish()?;
@@ -553,7 +1912,13 @@ mod serde_impls {
         ip: usize,
         symbol_address: usize,
         module_base_address: Option<usize>,
+        #[serde(default)]
+        module_build_id: Option<Vec<u8>>,
+        #[serde(default)]
+        module_path: Option<PathBuf>,
         symbols: Option<Vec<BacktraceSymbol>>,
+        #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+        names_only: bool,
     }
 
     impl Serialize for BacktraceFrame {
@@ -561,17 +1926,47 @@ mod serde_impls {
         where
             S: Serializer,
         {
-            let BacktraceFrame { frame, symbols } = self;
+            let BacktraceFrame {
+                frame,
+                module_build_id,
+                module_path,
+                symbols,
+                region: _,
+                names_only,
+            } = self;
             SerializedFrame {
                 ip: frame.ip() as usize,
                 symbol_address: frame.symbol_address() as usize,
                 module_base_address: frame.module_base_address().map(|sym_a| sym_a as usize),
+                module_build_id: module_build_id.clone(),
+                module_path: module_path.clone(),
                 symbols: symbols.clone(),
+                names_only: *names_only,
             }
             .serialize(s)
         }
     }
 
+    /// `BacktraceSymbol::optimized` as it was before being cached, which is
+    /// known once deserialized, even if not known to be either.
+    pub(super) mod optimized {
+        use super::*;
+
+        pub fn serialize<S>(optimized: &OnceLock<Option<bool>>, s: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            optimized.get().copied().flatten().serialize(s)
+        }
+
+        pub fn deserialize<'a, D>(d: D) -> Result<OnceLock<Option<bool>>, D::Error>
+        where
+            D: Deserializer<'a>,
+        {
+            Option::<bool>::deserialize(d).map(OnceLock::from)
+        }
+    }
+
     impl<'a> Deserialize<'a> for BacktraceFrame {
         fn deserialize<D>(d: D) -> Result<Self, D::Error>
         where
@@ -584,7 +1979,11 @@ mod serde_impls {
                     symbol_address: TracePtr::from_addr(frame.symbol_address),
                     module_base_address: frame.module_base_address.map(TracePtr::from_addr),
                 },
+                module_build_id: frame.module_build_id,
+                module_path: frame.module_path,
                 symbols: frame.symbols,
+                region: None,
+                names_only: frame.names_only,
             })
         }
     }
@@ -594,6 +1993,631 @@ mod serde_impls {
 mod tests {
     use super::*;
 
+    /// A resolved frame at `ip`, as if deserialized, with a symbol for each
+    /// of `names` and nothing else known.
+    fn fake_frame(ip: usize, names: &[&str]) -> BacktraceFrame {
+        BacktraceFrame {
+            frame: Frame::Deserialized {
+                ip: TracePtr::from_addr(ip),
+                symbol_address: TracePtr::from_addr(ip),
+                module_base_address: None,
+            },
+            module_build_id: None,
+            module_path: None,
+            region: None,
+            names_only: false,
+            symbols: Some(
+                names
+                    .iter()
+                    .map(|name| BacktraceSymbol {
+                        name: Some(name.as_bytes().to_vec()),
+                        addr: None,
+                        filename: None,
+                        lineno: None,
+                        colno: None,
+                        optimized: OnceLock::from(None),
+                        demangled: None,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_resolve_frame() {
+        let mut bt = Backtrace::new_unresolved();
+        assert!(bt.frames().len() > 1);
+        bt.resolve_frame(0);
+        assert!(bt.frames[0].symbols.is_some());
+        assert!(bt.frames[1..].iter().all(|frame| frame.symbols.is_none()));
+
+        let symbols = bt.frames()[0].symbols().len();
+        bt.resolve();
+        assert_eq!(bt.frames()[0].symbols().len(), symbols);
+        assert!(bt.frames().iter().all(|frame| frame.symbols.is_some()));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_to_json() {
+        let mut named = fake_frame(0x1234, &["weird \"name\"\n"]);
+        let symbol = &mut named.symbols.as_mut().unwrap()[0];
+        symbol.filename = Some(PathBuf::from("src\\lib.rs"));
+        symbol.lineno = Some(7);
+        let bt = Backtrace::from(vec![
+            named,
+            fake_frame(0x5678, &[]),
+            BacktraceFrame {
+                symbols: None,
+                ..fake_frame(0x9abc, &[])
+            },
+        ]);
+        assert_eq!(
+            bt.to_json(),
+            concat!(
+                r#"{"version":1,"frames":["#,
+                r#"{"ip":"0x1234","symbol_address":"0x1234","symbols":["#,
+                r#"{"name":"weird \"name\"\n","filename":"src\\lib.rs","lineno":7}]},"#,
+                r#"{"ip":"0x5678","symbol_address":"0x5678","symbols":[]},"#,
+                r#"{"ip":"0x9abc","symbol_address":"0x9abc","symbols":null}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_from_ips() {
+        let mut captured = Backtrace::new_unresolved();
+        let ips = captured
+            .frames
+            .iter()
+            .map(|frame| frame.ip() as usize)
+            .collect::<Vec<_>>();
+        let mut bt = Backtrace::from_ips(&ips);
+        assert_eq!(bt.frames.len(), ips.len());
+        for (frame, &ip) in bt.frames.iter().zip(&ips) {
+            assert_eq!(frame.ip() as usize, ip);
+            assert_eq!(frame.symbol_address() as usize, ip);
+            assert!(frame.symbols.is_none());
+        }
+
+        captured.resolve();
+        bt.resolve();
+        let names = |bt: &Backtrace| -> Vec<_> {
+            bt.frames
+                .iter()
+                .flat_map(|f| f.symbols())
+                .map(|s| s.name().map(|n| n.to_string()))
+                .collect()
+        };
+        assert_eq!(names(&bt), names(&captured));
+    }
+
+    #[test]
+    fn test_resolve_with_symbol_limit() {
+        let mut full = Backtrace::new_unresolved();
+        let mut limited = full.clone();
+        full.resolve();
+        let first = full.frames[0].symbols().len();
+        limited.resolve_with_symbol_limit(first);
+
+        assert_eq!(limited.frames[0].symbols().len(), first);
+        assert!(!limited.frames[0].is_names_only());
+        for frame in &limited.frames[1..] {
+            assert!(frame.symbols.is_some());
+            assert!(frame.is_names_only());
+            assert!(frame.symbols().len() <= 1);
+            assert!(frame.symbols().iter().all(|s| s.filename().is_none()));
+        }
+        if cfg!(all(target_os = "linux", debug_assertions)) {
+            // Only inlined functions are missing.
+            let names = |bt: &Backtrace| -> Vec<_> {
+                bt.frames[1..]
+                    .iter()
+                    .filter_map(|f| f.symbols().last()?.name().map(|n| n.to_string()))
+                    .collect()
+            };
+            assert_eq!(names(&limited), names(&full));
+        }
+
+        // Frames over the limit are given up on without copying them whole.
+        let frame = &full.frames[0].frame;
+        assert_eq!(
+            frame.resolve_symbols_limited(first).map(|s| s.len()),
+            Some(first)
+        );
+        if first > 0 {
+            assert!(frame.resolve_symbols_limited(first - 1).is_none());
+        }
+    }
+
+    #[test]
+    fn test_new_with_os_error() {
+        let err = std::fs::File::open("/does/not/exist").unwrap_err();
+        let bt = Backtrace::new_with_os_error();
+        assert_eq!(bt.os_error(), err.raw_os_error());
+        assert!(!bt.frames().is_empty());
+        let expected = format!("last OS error: {err}\n");
+        assert!(format!("{bt:?}").starts_with(&expected));
+        assert_eq!(Backtrace::new().os_error(), None);
+    }
+
+    #[test]
+    fn test_into_virtual_frames() {
+        let bt = Backtrace::new();
+        let frames = bt.clone().into_virtual_frames();
+        let symbols = bt.frames().iter().map(|frame| frame.symbols().len().max(1));
+        assert_eq!(frames.len(), symbols.sum::<usize>());
+        assert!(frames.iter().all(|frame| frame.symbols().len() <= 1));
+
+        let mut virtual_frames = frames.iter();
+        for frame in bt.frames() {
+            if frame.symbols().is_empty() {
+                let virtual_frame = virtual_frames.next().unwrap();
+                assert!(virtual_frame.symbols().is_empty());
+            }
+            for symbol in frame.symbols() {
+                let virtual_frame = virtual_frames.next().unwrap();
+                assert_eq!(virtual_frame.ip(), frame.ip());
+                assert_eq!(virtual_frame.symbols()[0].addr(), symbol.addr());
+                assert_eq!(virtual_frame.symbols()[0].lineno(), symbol.lineno());
+            }
+        }
+
+        let unresolved = Backtrace::new_unresolved().into_virtual_frames();
+        assert!(unresolved.iter().all(|frame| frame.symbols.is_none()));
+    }
+
+    #[test]
+    fn test_compact_bytes_round_trip() {
+        let mut bt = Backtrace::new_unresolved();
+        let bytes = bt.to_compact_bytes();
+        let mut decoded = Backtrace::from_compact_bytes(&bytes).unwrap();
+        assert_eq!(decoded.frames().len(), bt.frames().len());
+        for (a, b) in decoded.frames().iter().zip(bt.frames()) {
+            assert_eq!(a.ip(), b.ip());
+            assert_eq!(a.symbol_address(), b.symbol_address());
+            assert_eq!(a.module_base_address(), b.module_base_address());
+            assert_eq!(a.module_build_id(), b.module_build_id());
+            assert!(b.module_path().is_none());
+        }
+        assert_eq!(decoded.target_arch(), bt.target_arch());
+
+        // Module paths are looked up while encoding, and kept when encoding
+        // decoded frames again.
+        if cfg!(target_os = "linux") {
+            let exe = std::env::current_exe().unwrap();
+            let path = decoded.frames()[0].module_path().unwrap();
+            assert_eq!(path.canonicalize().unwrap(), exe.canonicalize().unwrap());
+        }
+        assert_eq!(decoded.to_compact_bytes(), bytes);
+        assert_eq!(decoded.pointer_width(), bt.pointer_width());
+
+        bt.resolve();
+        decoded.resolve();
+        assert_eq!(decoded.signature_hash(), bt.signature_hash());
+
+        assert!(Backtrace::from_compact_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(Backtrace::from_compact_bytes(b"not a backtrace").is_none());
+        let empty = Backtrace::from(Vec::new()).to_compact_bytes();
+        assert!(Backtrace::from_compact_bytes(&empty)
+            .unwrap()
+            .frames()
+            .is_empty());
+
+        // A frame at 0x20 in a module based at 0x10, with the given version
+        // and module flags.
+        let encoded = |version: u8, flags: u8| {
+            let mut bytes = b"BTC".to_vec();
+            bytes.extend_from_slice(&[version, 64, 0, 1, flags, 0x10]);
+            if flags & 4 != 0 {
+                bytes.extend_from_slice(b"\x01a");
+            }
+            bytes.extend_from_slice(&[1, 0x40, 0, 1]);
+            bytes
+        };
+        // Version 1 had no paths, which is still understood.
+        let decoded = Backtrace::from_compact_bytes(&encoded(1, 1)).unwrap();
+        assert_eq!(decoded.frames()[0].ip() as usize, 0x20);
+        assert_eq!(
+            decoded.frames()[0].module_base_address(),
+            Some(0x10 as *mut c_void)
+        );
+        assert!(Backtrace::from_compact_bytes(&encoded(1, 1 | 4)).is_none());
+        let decoded = Backtrace::from_compact_bytes(&encoded(2, 1 | 4)).unwrap();
+        assert_eq!(decoded.frames()[0].module_path(), Some(Path::new("a")));
+        // Unknown flags and versions are rejected rather than misread.
+        assert!(Backtrace::from_compact_bytes(&encoded(2, 1 | 8)).is_none());
+        assert!(Backtrace::from_compact_bytes(&encoded(0, 1)).is_none());
+        assert!(Backtrace::from_compact_bytes(&encoded(3, 1)).is_none());
+    }
+
+    #[test]
+    fn test_with_spawn_context() {
+        let spawned_at = Backtrace::new();
+        let polled = Backtrace::new();
+        let bt = polled.with_spawn_context(&spawned_at);
+        assert_eq!(
+            bt.frames().len(),
+            polled.frames().len() + spawned_at.frames().len()
+        );
+        assert_eq!(bt.spawn_points, [polled.frames().len()]);
+        assert_eq!(format!("{bt:?}").matches("task spawned from").count(), 1);
+
+        let nested = polled.with_spawn_context(&bt);
+        assert_eq!(
+            nested.spawn_points,
+            [polled.frames().len(), 2 * polled.frames().len()]
+        );
+        assert_eq!(
+            format!("{nested:?}").matches("task spawned from").count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_new_unfiltered() {
+        let is_internal = |bt: &Backtrace| {
+            bt.frames()
+                .iter()
+                .flat_map(BacktraceFrame::symbols)
+                .any(|symbol| {
+                    symbol.name().is_some_and(|name| {
+                        format!("{name:#}").starts_with("backtrace::backtrace::")
+                    })
+                })
+        };
+        let (filtered, unfiltered) = (Backtrace::new(), Backtrace::new_unfiltered());
+        if cfg!(all(target_os = "linux", debug_assertions)) {
+            assert!(!is_internal(&filtered));
+            assert!(is_internal(&unfiltered));
+            assert!(unfiltered.frames().len() > filtered.frames().len());
+        }
+    }
+
+    #[test]
+    fn test_fold_stacks() {
+        let frame = |names| fake_frame(0x1000, names);
+
+        let sample = |frames| Backtrace::from(frames);
+        let samples = [
+            sample(vec![frame(&["parse"]), frame(&["main"])]),
+            sample(vec![frame(&["inlined", "run"]), frame(&["main"])]),
+            sample(vec![frame(&["parse"]), frame(&["main"])]),
+            sample(vec![frame(&[]), frame(&["a;b"])]),
+        ];
+        assert_eq!(
+            fold_stacks(&samples),
+            "a:b;0x1000 1\nmain;parse 2\nmain;run;inlined 1\n"
+        );
+        assert_eq!(fold_stacks(&[]), "");
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    fn test_from_fp() {
+        // A fake stack of three frames, each a saved frame pointer followed
+        // by a return address.
+        let mut stack = [0usize; 6];
+        let base = stack.as_mut_ptr() as usize;
+        let word = core::mem::size_of::<usize>();
+        stack[..4].copy_from_slice(&[base + 2 * word, 0x1000, base + 4 * word, 0x2000]);
+
+        let bt = unsafe { Backtrace::from_fp(stack.as_mut_ptr().cast()) };
+        let ips: Vec<usize> = bt.frames().iter().map(|f| f.ip() as usize).collect();
+        assert_eq!(ips, [0x1000, 0x2000]);
+
+        // A chain that doesn't move up the stack ends the walk.
+        stack[2] = base;
+        let bt = unsafe { Backtrace::from_fp(stack.as_mut_ptr().cast()) };
+        assert_eq!(bt.frames().len(), 2);
+
+        assert!(unsafe { Backtrace::from_fp(core::ptr::null_mut()) }
+            .frames()
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(all(
+        any(target_os = "linux", target_os = "android"),
+        any(target_arch = "x86_64", target_arch = "aarch64"),
+    ))]
+    fn test_trace_current() {
+        // How far the walk gets depends on which code keeps frame pointers,
+        // but it only ever moves up this thread's stack.
+        let local = 0u8;
+        let here = &local as *const u8 as usize;
+        let mut sps = Vec::new();
+        crate::backtrace::trace_current(|frame| {
+            assert!(!frame.ip().is_null());
+            sps.push(frame.sp() as usize);
+            true
+        });
+        assert!(sps.iter().all(|&sp| sp > here));
+        assert!(sps.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_elided_tail_calls() {
+        let frame = |ip, name| fake_frame(ip, &[name]);
+
+        // `main` called `run`, which tail-called `parse`, and `parse` called
+        // `_ZN3foo3barE` by its mangled name.
+        let bt = Backtrace::from(vec![
+            frame(0x1000, "foo::bar"),
+            frame(0x2000, "parse"),
+            frame(0x3000, "main"),
+        ]);
+        let gaps = bt.elided_tail_calls_with(|caller| {
+            let target = match caller.ip() as usize {
+                0x2000 => "_ZN3foo3barE",
+                0x3000 => "run",
+                _ => return None,
+            };
+            let demangled = format!("{:#}", SymbolName::new(target.as_bytes()));
+            Some((target.as_bytes().to_vec(), demangled))
+        });
+        assert_eq!(gaps, [(1, "run".to_string())]);
+        assert!(bt.elided_tail_calls_with(|_| None).is_empty());
+
+        let bt = Backtrace::new();
+        for (i, _) in bt.elided_tail_calls() {
+            assert!(i + 1 < bt.frames().len());
+        }
+        assert!(Backtrace::from(Vec::new()).elided_tail_calls().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel-demangle")]
+    fn test_demangle_names() {
+        let frame = |name| fake_frame(0x1000, &[name]);
+
+        for count in [1, 1000] {
+            let mut frames = Vec::new();
+            for _ in 0..count {
+                frames.push(frame("_ZN3foo3barEv.llvm.123"));
+                frames.push(frame("_ZN4core3ptr13drop_in_place17h0123456789abcdefE"));
+            }
+            let mut bt = Backtrace::from(frames);
+            let before = format!("{bt:?}");
+            bt.demangle_names();
+            assert_eq!(format!("{bt:?}"), before);
+
+            let names: Vec<String> = bt.frames()[..2]
+                .iter()
+                .map(|f| format!("{:#}", f.symbols()[0].name().unwrap()))
+                .collect();
+            assert_eq!(names, ["foo::bar()", "core::ptr::drop_in_place"]);
+            assert!(bt.frames()[0].symbols()[0].demangled.is_some());
+            assert!(bt.frames()[1].symbols()[0].demangled.is_none());
+        }
+    }
+
+    #[test]
+    fn test_signature_hash() {
+        #[inline(never)]
+        fn capture() -> Backtrace {
+            Backtrace::new()
+        }
+
+        let (a, b) = (capture(), capture());
+        assert_eq!(a.signature_hash(), b.signature_hash());
+        assert_eq!(a.top_signature_hash(3), b.top_signature_hash(3));
+        assert_eq!(
+            a.top_signature_hash(0),
+            Backtrace::from(Vec::new()).signature_hash()
+        );
+        if cfg!(all(target_os = "linux", debug_assertions)) {
+            // The frames of this crate, and so of these tests, are skipped.
+            assert_eq!(a.signature_hash(), Backtrace::new().signature_hash());
+            let other = std::thread::spawn(Backtrace::new).join().unwrap();
+            assert_ne!(a.signature_hash(), other.signature_hash());
+            assert_ne!(a.top_signature_hash(1), a.top_signature_hash(2));
+        }
+    }
+
+    #[test]
+    fn test_optimization_hint() {
+        fn frame(optimized: &[Option<bool>]) -> BacktraceFrame {
+            let mut frame = fake_frame(0x1000, &vec![""; optimized.len()]);
+            for (symbol, optimized) in frame.symbols.iter_mut().flatten().zip(optimized) {
+                symbol.optimized = OnceLock::from(*optimized);
+            }
+            frame
+        }
+
+        let hint = |frames| Backtrace::from(frames).optimization_hint();
+        assert_eq!(hint(vec![]), None);
+        assert_eq!(hint(vec![frame(&[None])]), None);
+        assert_eq!(
+            hint(vec![frame(&[None]), frame(&[Some(false)])]),
+            Some(false)
+        );
+        assert_eq!(
+            hint(vec![frame(&[Some(false)]), frame(&[Some(true)])]),
+            Some(true)
+        );
+        assert_eq!(hint(vec![frame(&[None, None])]), Some(true));
+
+        // Only looked up when asked for, and only once.
+        let bt = Backtrace::new();
+        let optimized = |bt: &Backtrace| {
+            bt.frames()
+                .iter()
+                .flat_map(|f| f.symbols())
+                .any(|s| s.is_optimized().is_some())
+        };
+        assert!(!optimized(&bt));
+        let hint = bt.optimization_hint();
+        if hint == Some(false) {
+            assert!(optimized(&bt));
+        }
+        let symbols = || bt.frames().iter().flat_map(|f| f.symbols());
+        assert!(symbols().all(|s| s.optimized.get().is_some()));
+        assert_eq!(bt.optimization_hint(), hint);
+    }
+
+    #[test]
+    fn test_empty_debug_explains() {
+        let mut bt = Backtrace::from(Vec::new());
+        assert!(!format!("{bt:?}").contains("no frames were captured"));
+
+        // As if the unwinder had come up empty.
+        bt.nothing_unwound = true;
+        assert!(format!("{bt:?}").contains("no frames were captured"));
+        assert!(format!("{bt:#?}").contains("no frames were captured"));
+
+        // Or had found frames only by following frame pointers.
+        bt.frames.push(fake_frame(0x1000, &[]));
+        assert!(format!("{bt:?}").contains("found by following frame pointers"));
+
+        // Backtraces which were asked for no frames aren't empty by accident.
+        let (bt, _) = Backtrace::create_with(None, None, None, 0);
+        assert!(!format!("{bt:?}").contains("no frames were captured"));
+    }
+
+    #[test]
+    fn test_diff() {
+        #[inline(never)]
+        fn old() -> Backtrace {
+            Backtrace::new()
+        }
+        #[inline(never)]
+        fn new() -> Backtrace {
+            Backtrace::new()
+        }
+
+        let (a, b) = (old(), new());
+        let diff = a.diff(&b);
+        assert!(!diff.is_identical());
+        assert!(diff.shared_tail_len() > 0);
+        assert_eq!(
+            diff.old_frames().len() + diff.shared_tail_len(),
+            a.frames().len()
+        );
+        assert_eq!(
+            diff.new_frames().len() + diff.shared_tail_len(),
+            b.frames().len()
+        );
+        if cfg!(all(target_os = "linux", debug_assertions)) {
+            let rendered = diff.to_string();
+            assert!(rendered.contains("- backtrace::capture::tests::test_diff::old\n"));
+            assert!(rendered.contains("+ backtrace::capture::tests::test_diff::new\n"));
+            assert!(rendered.contains("  backtrace::capture::tests::test_diff\n"));
+        }
+
+        assert!(a.diff(&a).is_identical());
+    }
+
+    #[test]
+    fn test_diff_unnamed_frames() {
+        // Frames whose symbols have no names are compared by address, like
+        // frames which haven't been resolved.
+        let unnamed = |ip| {
+            let mut frame = fake_frame(ip, &["unnamed"]);
+            frame.symbols.as_mut().unwrap()[0].name = None;
+            frame
+        };
+        let a = Backtrace::from(vec![unnamed(0x10), fake_frame(0x100, &["main"])]);
+        let b = Backtrace::from(vec![unnamed(0x20), fake_frame(0x200, &["main"])]);
+        let diff = a.diff(&b);
+        assert_eq!(diff.shared_tail_len(), 1);
+        assert_eq!(diff.old_frames().len(), 1);
+        assert_eq!(diff.new_frames().len(), 1);
+
+        let c = Backtrace::from(vec![unnamed(0x10), fake_frame(0x200, &["main"])]);
+        assert!(a.diff(&c).is_identical());
+        assert!(!a.diff(&Backtrace::from(vec![unnamed(0x10)])).is_identical());
+    }
+
+    #[test]
+    fn test_ring() {
+        let mut ring = BacktraceRing::new(3);
+        assert!(ring.is_empty());
+        for _ in 0..3 {
+            ring.capture();
+        }
+        assert_eq!(ring.len(), 3);
+
+        let marker = Backtrace::from(Vec::new());
+        let evicted = ring.push(marker);
+        assert!(evicted.is_some_and(|bt| !bt.frames().is_empty()));
+        assert_eq!(ring.len(), 3);
+        assert!(ring.iter().last().unwrap().frames().is_empty());
+
+        let mut out = Vec::new();
+        ring.dump(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("backtrace 1 of 3"));
+        assert!(out.contains("backtrace 3 of 3"));
+        if cfg!(all(target_os = "linux", debug_assertions)) {
+            assert!(out.contains("test_ring"));
+        }
+
+        let mut empty = BacktraceRing::new(0);
+        assert!(empty.push(Backtrace::from(Vec::new())).is_some());
+        assert!(empty.is_empty());
+        ring.clear();
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_new_until() {
+        #[inline(never)]
+        fn boundary(f: &dyn Fn() -> Backtrace) -> Backtrace {
+            f()
+        }
+
+        let address = boundary as fn(&dyn Fn() -> Backtrace) -> Backtrace as *mut c_void;
+        let bt = boundary(&|| Backtrace::new_until(address));
+        let full = boundary(&Backtrace::new);
+        assert!(bt.frames().len() <= full.frames().len());
+        assert!(!bt.frames().is_empty());
+
+        if cfg!(all(target_os = "linux", debug_assertions)) {
+            assert!(bt.frames().len() < full.frames().len());
+            assert!(bt.frames().iter().all(|f| f.symbol_address() != address));
+            assert!(full.frames().iter().any(|f| f.symbol_address() == address));
+        }
+    }
+
+    #[test]
+    fn test_resolve_names_only() {
+        let mut bt = Backtrace::new_unresolved();
+        bt.resolve_names_only();
+        let symbols = bt.frames().iter().flat_map(|f| f.symbols());
+        let mut named = 0;
+        for symbol in symbols {
+            assert!(symbol.filename().is_none());
+            assert!(symbol.lineno().is_none());
+            assert!(symbol.colno().is_none());
+            named += symbol.name().is_some() as usize;
+        }
+        if cfg!(all(target_os = "linux", debug_assertions)) {
+            assert!(named > 0);
+        }
+
+        assert!(bt.frames().iter().all(|f| f.is_names_only()));
+
+        // the frames now count as resolved, so locations stay unknown
+        bt.resolve();
+        assert!(bt
+            .frames()
+            .iter()
+            .flat_map(|f| f.symbols())
+            .all(|s| s.filename().is_none()));
+    }
+
+    #[test]
+    fn test_target_info() {
+        let bt = Backtrace::new_unresolved();
+        assert_eq!(bt.target_arch(), Some(std::env::consts::ARCH));
+        assert_eq!(bt.pointer_width(), Some(usize::BITS));
+        assert_eq!(
+            bt.pointer_width(),
+            Some(std::mem::size_of::<*const ()>() as u32 * 8)
+        );
+    }
+
     #[test]
     fn test_frame_conversion() {
         let mut frames = vec![];
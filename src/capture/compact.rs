@@ -0,0 +1,268 @@
+//! The compact binary encoding of backtraces, see
+//! `Backtrace::to_compact_bytes`.
+//!
+//! The encoding starts with a header:
+//!
+//! * the magic bytes `BTC`, followed by a format version byte
+//! * the pointer width of the capturing process in bits, as a byte, or 0
+//! * the target architecture, as a length byte followed by UTF-8
+//!
+//! Next comes the table of modules frames were captured in, as a count
+//! followed by that many entries of
+//!
+//! * a flags byte, any of `MODULE_HAS_BASE`, `MODULE_HAS_BUILD_ID` and, as of
+//!   version 2, `MODULE_HAS_PATH`
+//! * the base address, if present
+//! * the build ID, as a length followed by its bytes, if present
+//! * the path of the module's file, as a length followed by UTF-8, if present
+//!   (paths which aren't valid UTF-8 are left out)
+//!
+//! and finally the frames, as a count followed by that many entries of
+//!
+//! * the instruction pointer, as a delta from the previous frame's
+//! * the distance from the symbol address back to the instruction pointer
+//! * the index of the frame's module plus one, or 0 for no module
+//!
+//! All counts, lengths, addresses and indices are LEB128 varints, with
+//! signed values zigzag encoded.
+
+use super::{Backtrace, BacktraceFrame, Frame, TracePtr};
+use crate::Library;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+
+const MAGIC: &[u8] = b"BTC";
+const VERSION: u8 = 2;
+
+const MODULE_HAS_BASE: u8 = 1 << 0;
+const MODULE_HAS_BUILD_ID: u8 = 1 << 1;
+const MODULE_HAS_PATH: u8 = 1 << 2;
+
+/// The module flags each version of the format knows about, starting with
+/// version 1.
+const MODULE_FLAGS: [u8; VERSION as usize] = [
+    MODULE_HAS_BASE | MODULE_HAS_BUILD_ID,
+    MODULE_HAS_BASE | MODULE_HAS_BUILD_ID | MODULE_HAS_PATH,
+];
+
+type Module<'a> = (Option<usize>, Option<&'a [u8]>, Option<&'a str>);
+
+pub(super) fn encode(bt: &Backtrace) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.push(bt.pointer_width.map_or(0, |bits| bits as u8));
+    let arch = bt.target_arch.as_deref().unwrap_or("");
+    let arch = &arch.as_bytes()[..arch.len().min(u8::MAX.into())];
+    out.push(arch.len() as u8);
+    out.extend_from_slice(arch);
+
+    // The paths of modules of frames captured here are looked up among the
+    // libraries loaded right now, which is only worth it if there are any.
+    let libraries = if bt.frames.iter().any(|f| matches!(f.frame, Frame::Raw(_))) {
+        crate::native_libraries()
+    } else {
+        Vec::new()
+    };
+    let mut modules = Vec::new();
+    for frame in &bt.frames {
+        let module = module(&libraries, frame);
+        if module != (None, None, None) && !modules.contains(&module) {
+            modules.push(module);
+        }
+    }
+    write_varint(&mut out, modules.len() as u64);
+    for &(base, build_id, path) in &modules {
+        let mut flags = 0;
+        if base.is_some() {
+            flags |= MODULE_HAS_BASE;
+        }
+        if build_id.is_some() {
+            flags |= MODULE_HAS_BUILD_ID;
+        }
+        if path.is_some() {
+            flags |= MODULE_HAS_PATH;
+        }
+        out.push(flags);
+        if let Some(base) = base {
+            write_varint(&mut out, base as u64);
+        }
+        if let Some(build_id) = build_id {
+            write_varint(&mut out, build_id.len() as u64);
+            out.extend_from_slice(build_id);
+        }
+        if let Some(path) = path {
+            write_varint(&mut out, path.len() as u64);
+            out.extend_from_slice(path.as_bytes());
+        }
+    }
+
+    write_varint(&mut out, bt.frames.len() as u64);
+    let mut prev_ip = 0;
+    for frame in &bt.frames {
+        let ip = frame.ip() as usize as u64;
+        let symbol_address = frame.symbol_address() as usize as u64;
+        write_varint(&mut out, zigzag(ip.wrapping_sub(prev_ip) as i64));
+        write_varint(&mut out, zigzag(ip.wrapping_sub(symbol_address) as i64));
+        let module = module(&libraries, frame);
+        let index = modules
+            .iter()
+            .position(|m| *m == module)
+            .map_or(0, |i| i + 1);
+        write_varint(&mut out, index as u64);
+        prev_ip = ip;
+    }
+    out
+}
+
+fn module<'a>(libraries: &'a [Library], frame: &'a BacktraceFrame) -> Module<'a> {
+    let base = frame.module_base_address().map(|base| base as usize);
+    let path = match frame.frame {
+        Frame::Raw(_) => loaded_library_path(libraries, frame),
+        Frame::Deserialized { .. } => frame.module_path(),
+    };
+    (base, frame.module_build_id(), path.and_then(Path::to_str))
+}
+
+/// Finds the path of the library among `libraries` which `frame` is in.
+fn loaded_library_path<'a>(libraries: &'a [Library], frame: &BacktraceFrame) -> Option<&'a Path> {
+    // The instruction before a return address is the one certain to be in
+    // the same library, see `adjust_ip` in `symbolize`.
+    let addr = (frame.ip() as usize).wrapping_sub(1);
+    let library = libraries.iter().find(|library| {
+        library.segments().iter().any(|segment| {
+            let start = segment
+                .stated_virtual_memory_address()
+                .wrapping_add(library.bias());
+            addr.wrapping_sub(start) < segment.size()
+        })
+    })?;
+    Some(library.name())
+}
+
+pub(super) fn decode(bytes: &[u8]) -> Option<Backtrace> {
+    let mut input = Input(bytes);
+    if input.bytes(MAGIC.len())? != MAGIC {
+        return None;
+    }
+    let known_flags = *MODULE_FLAGS.get(usize::from(input.byte()?).checked_sub(1)?)?;
+    let pointer_width = match input.byte()? {
+        0 => None,
+        bits => Some(u32::from(bits)),
+    };
+    let arch_len = input.byte()?.into();
+    let arch = std::str::from_utf8(input.bytes(arch_len)?).ok()?;
+    let target_arch = if arch.is_empty() {
+        None
+    } else {
+        Some(Cow::Owned(arch.to_owned()))
+    };
+
+    let mut modules = Vec::new();
+    for _ in 0..input.varint()? {
+        let flags = input.byte()?;
+        if flags & !known_flags != 0 {
+            return None;
+        }
+        let base = if flags & MODULE_HAS_BASE != 0 {
+            Some(usize::try_from(input.varint()?).ok()?)
+        } else {
+            None
+        };
+        let build_id = if flags & MODULE_HAS_BUILD_ID != 0 {
+            let len = usize::try_from(input.varint()?).ok()?;
+            Some(input.bytes(len)?.to_vec())
+        } else {
+            None
+        };
+        let path = if flags & MODULE_HAS_PATH != 0 {
+            let len = usize::try_from(input.varint()?).ok()?;
+            let path = std::str::from_utf8(input.bytes(len)?).ok()?;
+            Some(PathBuf::from(path))
+        } else {
+            None
+        };
+        modules.push((base, build_id, path));
+    }
+
+    // Don't trust the count for the allocation, a corrupt one could be huge.
+    let mut frames = Vec::new();
+    let mut prev_ip = 0u64;
+    for _ in 0..input.varint()? {
+        let ip = prev_ip.wrapping_add(unzigzag(input.varint()?) as u64);
+        let symbol_address = ip.wrapping_sub(unzigzag(input.varint()?) as u64);
+        let (base, build_id, path) = match input.varint()? {
+            0 => (None, None, None),
+            i => modules.get(usize::try_from(i - 1).ok()?)?.clone(),
+        };
+        frames.push(BacktraceFrame {
+            frame: Frame::Deserialized {
+                ip: TracePtr::from_addr(usize::try_from(ip).ok()?),
+                symbol_address: TracePtr::from_addr(usize::try_from(symbol_address).ok()?),
+                module_base_address: base.map(TracePtr::from_addr),
+            },
+            module_build_id: build_id,
+            module_path: path,
+            symbols: None,
+            region: None,
+            names_only: false,
+        });
+        prev_ip = ip;
+    }
+    if !input.0.is_empty() {
+        return None;
+    }
+
+    let mut bt = Backtrace::from(frames);
+    bt.target_arch = target_arch;
+    bt.pointer_width = pointer_width;
+    Some(bt)
+}
+
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+fn unzigzag(n: u64) -> i64 {
+    ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let (&first, rest) = self.0.split_first()?;
+        self.0 = rest;
+        Some(first)
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.0.len() {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut n = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(n);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,126 @@
+use super::{Backtrace, BacktraceFrame};
+use std::fmt;
+
+/// A comparison of two backtraces, returned from `Backtrace::diff`.
+///
+/// Two stacks that diverge typically share their outermost frames (`main`,
+/// thread startup, a test harness, ...) and differ in their innermost ones.
+/// This type splits both backtraces into the differing leading frames and the
+/// tail of frames which they have in common.
+///
+/// Frames are compared by the names of their symbols so that backtraces
+/// captured in different processes, where address space layout randomization
+/// will have moved code around, can still be compared. Frames which haven't
+/// been resolved, or whose symbols aren't all named, are compared by
+/// instruction pointer instead.
+///
+/// The `Display` implementation renders a view in the style of a unified diff,
+/// with frames only in the first backtrace prefixed by `-`, frames only in the
+/// second prefixed by `+`, and the shared tail prefixed by a space.
+///
+/// # Required features
+///
+/// This type requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+pub struct BacktraceDiff<'a> {
+    old: &'a [BacktraceFrame],
+    new: &'a [BacktraceFrame],
+    shared_tail: usize,
+}
+
+impl<'a> BacktraceDiff<'a> {
+    pub(super) fn new(old: &'a Backtrace, new: &'a Backtrace) -> BacktraceDiff<'a> {
+        let shared_tail = old
+            .frames
+            .iter()
+            .rev()
+            .zip(new.frames.iter().rev())
+            .take_while(|(a, b)| same_frame(a, b))
+            .count();
+        BacktraceDiff {
+            old: &old.frames,
+            new: &new.frames,
+            shared_tail,
+        }
+    }
+
+    /// Returns the number of outermost frames the two backtraces have in
+    /// common.
+    pub fn shared_tail_len(&self) -> usize {
+        self.shared_tail
+    }
+
+    /// Returns whether both backtraces consist of the same frames.
+    pub fn is_identical(&self) -> bool {
+        self.old.len() == self.shared_tail && self.new.len() == self.shared_tail
+    }
+
+    /// Returns the leading frames of the backtrace `diff` was called on which
+    /// aren't part of the shared tail.
+    pub fn old_frames(&self) -> &'a [BacktraceFrame] {
+        &self.old[..self.old.len() - self.shared_tail]
+    }
+
+    /// Returns the leading frames of the backtrace passed to `diff` which
+    /// aren't part of the shared tail.
+    pub fn new_frames(&self) -> &'a [BacktraceFrame] {
+        &self.new[..self.new.len() - self.shared_tail]
+    }
+
+    /// Returns the frames both backtraces have in common, as they appear in
+    /// the backtrace `diff` was called on.
+    pub fn shared_frames(&self) -> &'a [BacktraceFrame] {
+        &self.old[self.old.len() - self.shared_tail..]
+    }
+}
+
+fn same_frame(a: &BacktraceFrame, b: &BacktraceFrame) -> bool {
+    let named = |frame: &BacktraceFrame| {
+        let symbols = frame.symbols();
+        !symbols.is_empty() && symbols.iter().all(|sym| sym.name.is_some())
+    };
+    if !named(a) || !named(b) {
+        return a.ip() == b.ip();
+    }
+    let names = a.symbols().iter().map(|sym| &sym.name);
+    names.eq(b.symbols().iter().map(|sym| &sym.name))
+}
+
+fn write_frame(fmt: &mut fmt::Formatter<'_>, prefix: char, frame: &BacktraceFrame) -> fmt::Result {
+    let symbols = frame.symbols();
+    if symbols.is_empty() {
+        return writeln!(fmt, "{prefix} {:?}", frame.ip());
+    }
+    for symbol in symbols {
+        match symbol.name() {
+            Some(name) => writeln!(fmt, "{prefix} {name:#}")?,
+            None => writeln!(fmt, "{prefix} <unknown>")?,
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for BacktraceDiff<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for frame in self.old_frames() {
+            write_frame(fmt, '-', frame)?;
+        }
+        for frame in self.new_frames() {
+            write_frame(fmt, '+', frame)?;
+        }
+        for frame in self.shared_frames() {
+            write_frame(fmt, ' ', frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BacktraceDiff<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("BacktraceDiff")
+            .field("old_frames", &self.old_frames().len())
+            .field("new_frames", &self.new_frames().len())
+            .field("shared_tail", &self.shared_tail)
+            .finish()
+    }
+}
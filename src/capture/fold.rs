@@ -0,0 +1,64 @@
+use super::Backtrace;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::prelude::v1::*;
+
+/// Folds `samples` into the collapsed stack format of the FlameGraph tools,
+/// for making flame graphs out of backtraces sampled from a program.
+///
+/// Each distinct stack becomes a line of the names of its functions,
+/// outermost first and separated by `;`, followed by a space and the number
+/// of samples with that stack, for example `main;run;parse 3`. Lines are
+/// sorted by stack. The output can be fed to `flamegraph.pl` or `inferno`.
+///
+/// Names are those of the resolved symbols without their hashes, with every
+/// function inlined into a frame listed separately, so the backtraces should
+/// be resolved first. Frames without any symbol names are written as their
+/// instruction pointer instead. Any `;` in a name is replaced with `:`.
+///
+/// # Examples
+///
+/// ```
+/// use backtrace::Backtrace;
+///
+/// let samples = (0..10).map(|_| Backtrace::new()).collect::<Vec<_>>();
+/// let folded = backtrace::fold_stacks(&samples);
+/// assert!(folded.ends_with(" 10\n"));
+/// ```
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+pub fn fold_stacks(samples: &[Backtrace]) -> String {
+    let mut counts = BTreeMap::new();
+    let mut stack = String::new();
+    for sample in samples {
+        stack.clear();
+        for frame in sample.frames.iter().rev() {
+            let mut names = frame.symbols().iter().filter_map(|s| s.name()).peekable();
+            if names.peek().is_none() {
+                push_name(&mut stack, &format!("{:?}", frame.ip()));
+                continue;
+            }
+            // Symbols are listed innermost first.
+            for name in names.collect::<Vec<_>>().into_iter().rev() {
+                push_name(&mut stack, &format!("{name:#}"));
+            }
+        }
+        *counts.entry(stack.clone()).or_insert(0usize) += 1;
+    }
+
+    let mut out = String::new();
+    for (stack, count) in counts {
+        let _ = writeln!(out, "{stack} {count}");
+    }
+    out
+}
+
+fn push_name(stack: &mut String, name: &str) {
+    if !stack.is_empty() {
+        stack.push(';');
+    }
+    stack.extend(name.chars().map(|c| if c == ';' { ':' } else { c }));
+}
@@ -0,0 +1,105 @@
+//! The JSON encoding of backtraces, see `Backtrace::to_json`.
+//!
+//! A backtrace is encoded as an object with the fields
+//!
+//! * `"version"`: the version of this format, currently `1`
+//! * `"frames"`: the frames of the backtrace, innermost first
+//!
+//! and each frame as an object with the fields
+//!
+//! * `"ip"`: the instruction pointer, as a hexadecimal string like `"0x1a2b"`
+//! * `"symbol_address"`: the starting address of the frame's function, in the
+//!   same form as `"ip"`
+//! * `"symbols"`: the symbols the frame resolved to, or `null` if the
+//!   backtrace wasn't resolved
+//!
+//! and each symbol as an object with the fields
+//!
+//! * `"name"`: the demangled name, or `null`
+//! * `"filename"`: the path of the source file, or `null`
+//! * `"lineno"`: the line number as a number, or `null`
+//!
+//! Addresses are strings since JSON numbers aren't reliably precise beyond 53
+//! bits. Names and paths which aren't valid UTF-8 are converted lossily.
+//! Later versions may add fields, which readers should ignore.
+
+use super::{Backtrace, BacktraceFrame, BacktraceSymbol};
+use std::fmt::Write as _;
+use std::prelude::v1::*;
+
+const VERSION: u32 = 1;
+
+pub(super) fn encode(bt: &Backtrace) -> String {
+    let mut out = String::new();
+    let _ = write!(out, "{{\"version\":{VERSION},\"frames\":[");
+    for (i, frame) in bt.frames.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_frame(&mut out, frame);
+    }
+    out.push_str("]}");
+    out
+}
+
+fn write_frame(out: &mut String, frame: &BacktraceFrame) {
+    let _ = write!(
+        out,
+        "{{\"ip\":\"{:#x}\",\"symbol_address\":\"{:#x}\",\"symbols\":",
+        frame.ip() as usize,
+        frame.symbol_address() as usize,
+    );
+    match &frame.symbols {
+        Some(symbols) => {
+            out.push('[');
+            for (i, symbol) in symbols.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_symbol(out, symbol);
+            }
+            out.push(']');
+        }
+        None => out.push_str("null"),
+    }
+    out.push('}');
+}
+
+fn write_symbol(out: &mut String, symbol: &BacktraceSymbol) {
+    out.push_str("{\"name\":");
+    match symbol.name() {
+        Some(name) => write_string(out, &name.to_string()),
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"filename\":");
+    match symbol.filename() {
+        Some(filename) => write_string(out, &filename.to_string_lossy()),
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"lineno\":");
+    match symbol.lineno() {
+        Some(lineno) => {
+            let _ = write!(out, "{lineno}");
+        }
+        None => out.push_str("null"),
+    }
+    out.push('}');
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
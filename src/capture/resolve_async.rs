@@ -0,0 +1,184 @@
+use super::BacktraceSymbol;
+use core::ffi::c_void;
+use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::prelude::v1::*;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+/// Resolves an address to symbols like `resolve`, returning a `Future` which
+/// completes with owned copies of the symbols found.
+///
+/// Resolving symbols can block for a long time, especially the first time a
+/// library is looked at and its debug information has to be read and parsed.
+/// This function hands that work off to a background thread instead, so it
+/// can be used from async code without stalling the executor it runs on.
+/// No particular async runtime is required.
+///
+/// All addresses passed to this function are resolved one at a time on a
+/// single background thread, which is started on first use. Resolution is
+/// serialized by this crate's global lock anyway, so more threads wouldn't
+/// resolve any faster. If the thread can't be started, the address is
+/// resolved right away on the calling thread instead.
+///
+/// The background thread, named `backtrace-resolve`, is never stopped once
+/// started. It's idle while there's nothing to resolve, and lives until the
+/// process exits, at which point any addresses still queued are dropped
+/// without being resolved.
+///
+/// As with `resolve`, `addr` is taken to be a return address, and the
+/// symbols are listed innermost first.
+///
+/// # Required features
+///
+/// This function requires the `resolve-async` feature of the `backtrace`
+/// crate to be enabled.
+///
+/// # Example
+///
+/// ```
+/// # async fn log_symbols(addr: *mut std::ffi::c_void) {
+/// for symbol in backtrace::resolve_async(addr).await {
+///     println!("{:?}", symbol.name());
+/// }
+/// # }
+/// ```
+pub fn resolve_async(addr: *mut c_void) -> ResolveFuture {
+    let shared = Arc::new(Mutex::new(Shared {
+        symbols: None,
+        waker: None,
+    }));
+
+    let job = {
+        let shared = shared.clone();
+        // Addresses are passed as integers as raw pointers can't be sent to
+        // another thread.
+        let addr = addr as usize;
+        move || {
+            let mut symbols = Vec::new();
+            // Make sure the future still completes if resolution panics.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                crate::resolve(addr as *mut c_void, |symbol| {
+                    symbols.push(BacktraceSymbol::new(symbol, true));
+                })
+            }));
+            let mut shared = shared.lock().unwrap();
+            shared.symbols = Some(symbols);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    };
+    let job: Job = Box::new(job);
+    match worker() {
+        Some(worker) => {
+            if let Err(mpsc::SendError(job)) = worker.send(job) {
+                job();
+            }
+        }
+        None => job(),
+    }
+
+    ResolveFuture { shared }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Returns the channel to the thread resolving addresses, starting it if
+/// necessary.
+fn worker() -> Option<&'static Sender<Job>> {
+    static WORKER: OnceLock<Option<Sender<Job>>> = OnceLock::new();
+    WORKER
+        .get_or_init(|| {
+            let (tx, rx) = mpsc::channel::<Job>();
+            thread::Builder::new()
+                .name("backtrace-resolve".into())
+                .spawn(move || rx.into_iter().for_each(|job| job()))
+                .ok()?;
+            Some(tx)
+        })
+        .as_ref()
+}
+
+struct Shared {
+    symbols: Option<Vec<BacktraceSymbol>>,
+    waker: Option<Waker>,
+}
+
+/// A future resolving an address to symbols, returned from `resolve_async`.
+///
+/// # Required features
+///
+/// This type requires the `resolve-async` feature of the `backtrace` crate
+/// to be enabled.
+pub struct ResolveFuture {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Future for ResolveFuture {
+    type Output = Vec<BacktraceSymbol>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Vec<BacktraceSymbol>> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.symbols.take() {
+            Some(symbols) => Poll::Ready(symbols),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ResolveFuture {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ResolveFuture").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+
+    struct Unpark(thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn matches_resolve() {
+        let ip = crate::Backtrace::new_unresolved().frames()[0].ip();
+        let mut expected = Vec::new();
+        crate::resolve(ip, |symbol| {
+            expected.push(BacktraceSymbol::new(symbol, true))
+        });
+
+        let symbols = block_on(resolve_async(ip));
+        assert_eq!(symbols.len(), expected.len());
+        for (a, b) in symbols.iter().zip(&expected) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.filename, b.filename);
+            assert_eq!(a.lineno, b.lineno);
+        }
+    }
+}
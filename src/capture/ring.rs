@@ -0,0 +1,139 @@
+use super::Backtrace;
+use std::collections::vec_deque::{self, VecDeque};
+use std::fmt;
+use std::io;
+
+/// A bounded buffer holding the most recently captured backtraces.
+///
+/// Once `capacity` backtraces are held, pushing another one evicts the oldest.
+/// This is intended for always-on diagnostics, such as remembering where the
+/// last few errors of a long-running service were raised, without letting
+/// memory grow without bound.
+///
+/// Backtraces captured through `capture` are left unresolved, as resolving
+/// symbols is far more expensive than capturing and most backtraces in the
+/// ring will be evicted without ever being looked at. They're resolved when
+/// the ring is dumped, or explicitly through `resolve`.
+///
+/// # Examples
+///
+/// ```
+/// use backtrace::BacktraceRing;
+///
+/// let mut ring = BacktraceRing::new(10);
+/// for _ in 0..20 {
+///     ring.capture();
+/// }
+/// assert_eq!(ring.len(), 10);
+/// ring.dump(&mut std::io::stderr()).unwrap();
+/// ```
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone)]
+pub struct BacktraceRing {
+    capacity: usize,
+    // Oldest backtraces are at the front.
+    backtraces: VecDeque<Backtrace>,
+}
+
+impl BacktraceRing {
+    /// Creates an empty ring which holds at most `capacity` backtraces.
+    ///
+    /// Space for all of them is allocated up front. A ring with a capacity of
+    /// zero never holds any backtraces.
+    pub fn new(capacity: usize) -> BacktraceRing {
+        BacktraceRing {
+            capacity,
+            backtraces: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the maximum number of backtraces this ring holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of backtraces currently held.
+    pub fn len(&self) -> usize {
+        self.backtraces.len()
+    }
+
+    /// Returns whether no backtraces are currently held.
+    pub fn is_empty(&self) -> bool {
+        self.backtraces.is_empty()
+    }
+
+    /// Captures an unresolved backtrace of the caller and pushes it into the
+    /// ring, as if by `push(Backtrace::new_unresolved())`.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn capture(&mut self) {
+        let backtrace = Backtrace::create(Some(Self::capture as *const () as usize), None);
+        self.push(backtrace);
+    }
+
+    /// Pushes `backtrace` into the ring as the newest entry, returning the
+    /// oldest entry if it had to be evicted to make room.
+    ///
+    /// If the capacity is zero then `backtrace` itself is returned.
+    pub fn push(&mut self, backtrace: Backtrace) -> Option<Backtrace> {
+        if self.capacity == 0 {
+            return Some(backtrace);
+        }
+        let evicted = if self.backtraces.len() == self.capacity {
+            self.backtraces.pop_front()
+        } else {
+            None
+        };
+        self.backtraces.push_back(backtrace);
+        evicted
+    }
+
+    /// Returns an iterator over the held backtraces, from oldest to newest.
+    pub fn iter(&self) -> vec_deque::Iter<'_, Backtrace> {
+        self.backtraces.iter()
+    }
+
+    /// Removes all backtraces from the ring.
+    pub fn clear(&mut self) {
+        self.backtraces.clear();
+    }
+
+    /// Resolves the symbols of all backtraces in the ring, see
+    /// `Backtrace::resolve`.
+    pub fn resolve(&mut self) {
+        self.backtraces.iter_mut().for_each(Backtrace::resolve);
+    }
+
+    /// Resolves and writes out every backtrace in the ring, from oldest to
+    /// newest, each under a heading numbering it.
+    pub fn dump<W: io::Write + ?Sized>(&mut self, out: &mut W) -> io::Result<()> {
+        self.resolve();
+        let len = self.backtraces.len();
+        for (i, backtrace) in self.backtraces.iter().enumerate() {
+            writeln!(out, "backtrace {} of {} (oldest first):", i + 1, len)?;
+            writeln!(out, "{backtrace:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a BacktraceRing {
+    type Item = &'a Backtrace;
+    type IntoIter = vec_deque::Iter<'a, Backtrace>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for BacktraceRing {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("BacktraceRing")
+            .field("capacity", &self.capacity)
+            .field("backtraces", &self.backtraces)
+            .finish()
+    }
+}
@@ -0,0 +1,125 @@
+use super::Backtrace;
+use std::fmt;
+use std::prelude::v1::*;
+use std::thread;
+
+/// A backtrace along with the thread it was captured on.
+///
+/// Crash reporters dumping the stacks of all threads want to show which
+/// thread each backtrace belongs to and which thread crashed. This pairs a
+/// `Backtrace` with the name and ID of its thread and whether it's the
+/// crashing one, and its `Debug` output puts a header such as
+/// `Thread "worker-3" (id 12345):` above the backtrace.
+///
+/// # Examples
+///
+/// ```
+/// use backtrace::ThreadBacktrace;
+///
+/// let mut bt = ThreadBacktrace::current();
+/// bt.set_crashed(true);
+/// println!("{bt:?}");
+/// ```
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone)]
+pub struct ThreadBacktrace {
+    name: Option<String>,
+    id: Option<u64>,
+    crashed: bool,
+    backtrace: Backtrace,
+}
+
+impl ThreadBacktrace {
+    /// Pairs `backtrace` with the name and ID of the thread it was captured
+    /// on, for backtraces of other threads, which can't be captured by this
+    /// crate itself.
+    pub fn new(backtrace: Backtrace, name: Option<String>, id: Option<u64>) -> ThreadBacktrace {
+        ThreadBacktrace {
+            name,
+            id,
+            crashed: false,
+            backtrace,
+        }
+    }
+
+    /// Captures and resolves the backtrace of the current thread, like
+    /// `Backtrace::new`, along with the thread's name and ID.
+    ///
+    /// The ID is the one the OS knows the thread by, as shown by debuggers,
+    /// which is only found on Linux, Android and Apple platforms.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn current() -> ThreadBacktrace {
+        let mut backtrace = Backtrace::create(Some(Self::current as *const () as usize), None);
+        backtrace.resolve();
+        let name = thread::current().name().map(String::from);
+        ThreadBacktrace::new(backtrace, name, os_thread_id())
+    }
+
+    /// Returns the name of the thread, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the ID of the thread, if it's known.
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    /// Returns whether the thread is the one which crashed.
+    pub fn is_crashed(&self) -> bool {
+        self.crashed
+    }
+
+    /// Marks the thread as the one which crashed, or not.
+    pub fn set_crashed(&mut self, crashed: bool) {
+        self.crashed = crashed;
+    }
+
+    /// Returns the backtrace of the thread.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+
+    /// Returns the backtrace of the thread mutably, for example to resolve
+    /// it.
+    pub fn backtrace_mut(&mut self) -> &mut Backtrace {
+        &mut self.backtrace
+    }
+}
+
+/// Returns the ID the OS knows the current thread by.
+fn os_thread_id() -> Option<u64> {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_os = "linux", target_os = "android"))] {
+            let id = unsafe { libc::syscall(libc::SYS_gettid) };
+            u64::try_from(id).ok()
+        } else if #[cfg(target_vendor = "apple")] {
+            let mut id = 0;
+            let ret = unsafe { libc::pthread_threadid_np(libc::pthread_self(), &mut id) };
+            (ret == 0).then_some(id)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Debug for ThreadBacktrace {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(fmt, "Thread {name:?}")?,
+            None => fmt.write_str("Thread <unnamed>")?,
+        }
+        match (self.id, self.crashed) {
+            (Some(id), false) => write!(fmt, " (id {id})")?,
+            (Some(id), true) => write!(fmt, " (id {id}, crashed)")?,
+            (None, true) => fmt.write_str(" (crashed)")?,
+            (None, false) => {}
+        }
+        fmt.write_str(":\n")?;
+        fmt::Debug::fmt(&self.backtrace, fmt)
+    }
+}
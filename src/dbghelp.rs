@@ -145,6 +145,16 @@ dbghelp! {
             hprocess: HANDLE,
             searchpatha: PCWSTR
         ) -> BOOL;
+        fn SymLoadModuleExW(
+            hProcess: HANDLE,
+            hFile: HANDLE,
+            ImageName: PCWSTR,
+            ModuleName: PCWSTR,
+            BaseOfDll: u64,
+            DllSize: u32,
+            Data: *const MODLOAD_DATA,
+            Flags: SYM_LOAD_FLAGS
+        ) -> u64;
         fn EnumerateLoadedModulesW64(
             hprocess: HANDLE,
             enumloadedmodulescallback: PENUMLOADED_MODULES_CALLBACKW64,
@@ -324,7 +334,6 @@ pub fn init() -> Result<Init, ()> {
         #[allow(static_mut_refs)]
         DBGHELP.ensure_open()?;
 
-        static mut INITIALIZED: bool = false;
         if !INITIALIZED {
             set_optional_options(ret.dbghelp());
             INITIALIZED = true;
@@ -332,6 +341,22 @@ pub fn init() -> Result<Init, ()> {
         Ok(ret)
     }
 }
+
+/// Whether `init` has set the options and search path of dbghelp.
+static mut INITIALIZED: bool = false;
+
+/// Makes the next call to `init` set the options and search path of dbghelp
+/// again, as if it was the first one, so that the directories of modules
+/// loaded since then are searched for symbols too.
+pub fn reset() {
+    // Hold the lock while the flag is changed.
+    if let Ok(_init) = init() {
+        unsafe {
+            INITIALIZED = false;
+        }
+    }
+}
+
 unsafe fn set_optional_options(dbghelp: *mut Dbghelp) -> Option<()> {
     unsafe {
         let orig = (*dbghelp).SymGetOptions()?();
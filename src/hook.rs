@@ -0,0 +1,73 @@
+//! Storage for the functions users of this crate install to customize it,
+//! such as with `set_unwinder`.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, TryLockError};
+
+/// A function installed through one of the `set_*` functions of this crate.
+///
+/// Installing another function replaces the current one, which is dropped
+/// once no call to it is in progress anymore.
+pub(crate) struct Hook<F: ?Sized> {
+    /// Whether a function is installed, so that looking for one doesn't take
+    /// the lock in the common case of there being none.
+    installed: AtomicBool,
+    f: RwLock<Option<Arc<F>>>,
+}
+
+impl<F: ?Sized> Hook<F> {
+    pub(crate) const fn new() -> Hook<F> {
+        Hook {
+            installed: AtomicBool::new(false),
+            f: RwLock::new(None),
+        }
+    }
+
+    pub(crate) fn set(&self, f: Arc<F>) {
+        self.replace(Some(f));
+    }
+
+    /// Uninstalls the installed function, if any.
+    pub(crate) fn clear(&self) {
+        self.replace(None);
+    }
+
+    fn replace(&self, f: Option<Arc<F>>) {
+        let mut current = self.f.write().unwrap_or_else(PoisonError::into_inner);
+        self.installed.store(f.is_some(), Ordering::Release);
+        let old = core::mem::replace(&mut *current, f);
+        // The old function is dropped once the lock is released.
+        drop(current);
+        drop(old);
+    }
+
+    /// Returns the installed function, if any.
+    ///
+    /// The lock is only held while cloning the `Arc`, so the function can
+    /// install another one, or be replaced by another thread, while it runs.
+    pub(crate) fn get(&self) -> Option<Arc<F>> {
+        if !self.installed.load(Ordering::Acquire) {
+            return None;
+        }
+        self.f
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Same as `get`, except that this never waits for the lock, returning
+    /// `Err` if it's held by a thread installing a function.
+    ///
+    /// That thread may be the current one, interrupted by a signal handler
+    /// which calls this.
+    pub(crate) fn try_get(&self) -> Result<Option<Arc<F>>, ()> {
+        if !self.installed.load(Ordering::Acquire) {
+            return Ok(None);
+        }
+        match self.f.try_read() {
+            Ok(f) => Ok(f.clone()),
+            Err(TryLockError::Poisoned(f)) => Ok(f.into_inner().clone()),
+            Err(TryLockError::WouldBlock) => Err(()),
+        }
+    }
+}
@@ -70,6 +70,16 @@
 //!   because debug information wasn't generated by the compiler, or it's just
 //!   missing on the filesystem.
 //!
+//! * Code generated at runtime by a JIT compiler isn't part of any library, so
+//!   it can't be symbolicated the usual way. After `enable_gdb_jit`, symbols
+//!   are also looked up in the objects JITs register for debuggers through
+//!   the GDB JIT interface on Linux and Android.
+//!
+//! * Optimized code may omit frames entirely. Besides functions being inlined,
+//!   a function ending in a call to another one may jump to it instead (a tail
+//!   call), leaving no frame behind. `Symbol::call_target` can help spot where
+//!   this happened if the compiler emitted call site information.
+//!
 //! * Not all platforms are supported. For example there's no way to get a
 //!   backtrace on WebAssembly at the moment.
 //!
@@ -83,6 +93,26 @@
 //! need to worry about these caveats. We'll try to fix ones where we can over
 //! time, but otherwise it's important to be aware of the limitations of
 //! unwinding-based backtraces!
+//!
+//! # Memory allocation
+//!
+//! Capturing a backtrace with `trace` doesn't allocate, but symbolication and
+//! the `Backtrace` type do. Everything this crate allocates itself, including
+//! the caches of parsed debug information kept between calls to `resolve`,
+//! the list of loaded libraries and sorted symbol tables, goes through the
+//! `alloc` crate's `Vec` and `Box`. A custom `#[global_allocator]` therefore
+//! sees all of those allocations, and `clear_symbol_cache` hands the cached
+//! ones back to it.
+//!
+//! There are a few exceptions which are outside of this crate's control:
+//!
+//! * Object files and debug information are memory-mapped with `mmap` (or
+//!   `MapViewOfFile` on Windows) rather than read onto the heap, on platforms
+//!   that support it.
+//!
+//! * The platform unwinder (e.g. libgcc's or LLVM's libunwind), `dbghelp.dll`
+//!   on Windows, and the system's dynamic loader when enumerating libraries
+//!   may allocate internally using the C allocator.
 
 #![deny(missing_docs)]
 #![no_std]
@@ -105,16 +135,19 @@ extern crate std;
 #[allow(unused_extern_crates)]
 extern crate alloc;
 
-pub use self::backtrace::{trace_unsynchronized, Frame};
+pub use self::backtrace::{trace_fp, trace_unsynchronized, Frame};
 mod backtrace;
 
 pub use self::symbolize::resolve_frame_unsynchronized;
-pub use self::symbolize::{resolve_unsynchronized, Symbol, SymbolName};
+pub use self::symbolize::{resolve_unsynchronized, Symbol, SymbolName, SymbolizeBackend};
 mod symbolize;
 
 pub use self::types::BytesOrWideString;
 mod types;
 
+pub use self::backend::{backend_info, BackendInfo};
+mod backend;
+
 #[cfg(feature = "std")]
 pub use self::symbolize::clear_symbol_cache;
 
@@ -123,9 +156,21 @@ pub use print::{BacktraceFmt, BacktraceFrameFmt, PrintFmt};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
-        pub use self::backtrace::trace;
-        pub use self::symbolize::{resolve, resolve_frame};
-        pub use self::capture::{Backtrace, BacktraceFrame, BacktraceSymbol};
+        pub use self::backtrace::{clear_unwinder, set_unwinder, trace, trace_lazy, trace_validated, LazyFrames, TraceResult};
+        pub use self::symbolize::set_custom_demangler;
+        pub use self::backtrace::{frames, trace_with_timeout, FrameIter};
+        pub use self::symbolize::{resolve, resolve_batch, resolve_candidates, resolve_frame, resolve_name_only};
+        pub use self::symbolize::{symbolization_status, SymbolizationStatus};
+        pub use self::symbolize::{cache_stats, set_mapping_cache_size, CacheStats};
+        pub use self::symbolize::{resolve_in_archive, resolve_location, CoreSymbolizer, MappedFile};
+        pub use self::symbolize::{native_libraries, Library, LibrarySegment, Symbolizer};
+        pub use self::symbolize::{enable_gdb_jit, preload_library, register_module, reset};
+        pub use self::capture::{
+            fold_stacks, resolve_owned, set_filename_remapper, Backtrace, BacktraceDiff, BacktraceFrame, BacktraceRing, BacktraceSymbol,
+            ThreadBacktrace,
+        };
+        #[cfg(feature = "resolve-async")]
+        pub use self::capture::{resolve_async, ResolveFuture};
         mod capture;
     }
 }
@@ -136,6 +181,9 @@ cfg_if::cfg_if! {
     }
 }
 
+#[cfg(feature = "std")]
+mod hook;
+
 #[cfg(feature = "std")]
 mod lock {
     use std::boxed::Box;
@@ -167,6 +215,32 @@ mod lock {
         }
     }
 
+    /// Marks a thread as holding the lock on behalf of another thread which
+    /// actually holds it, see `borrow`.
+    pub struct BorrowedLock(());
+
+    impl Drop for BorrowedLock {
+        fn drop(&mut self) {
+            LOCK_HELD.with(|slot| slot.set(false));
+        }
+    }
+
+    /// Lets the current thread use what the lock protects as if it held the
+    /// lock, without taking it, until the returned `BorrowedLock` is dropped.
+    ///
+    /// # Safety
+    ///
+    /// Another thread must hold the lock for as long as the `BorrowedLock`
+    /// lives, and the two threads must take turns using what the lock
+    /// protects, as if they were a single thread.
+    pub unsafe fn borrow() -> BorrowedLock {
+        LOCK_HELD.with(|slot| {
+            assert!(!slot.get());
+            slot.set(true);
+        });
+        BorrowedLock(())
+    }
+
     /// Acquire a partially unsound(!!!) global re-entrant lock over
     /// backtrace's internals.
     ///
@@ -1,6 +1,9 @@
 #[cfg(feature = "std")]
 use super::{BacktraceFrame, BacktraceSymbol};
 use super::{BytesOrWideString, Frame, SymbolName};
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
 use core::ffi::c_void;
 use core::fmt;
 
@@ -8,6 +11,8 @@ const HEX_WIDTH: usize = 2 + 2 * core::mem::size_of::<usize>();
 
 #[cfg(target_os = "fuchsia")]
 mod fuchsia;
+#[cfg(feature = "std")]
+mod source;
 
 /// A formatter for backtraces.
 ///
@@ -20,8 +25,24 @@ pub struct BacktraceFmt<'a, 'b> {
     format: PrintFmt,
     print_path:
         &'a mut (dyn FnMut(&mut fmt::Formatter<'_>, BytesOrWideString<'_>) -> fmt::Result + 'b),
+    #[cfg(feature = "std")]
+    source_snippets: Option<source::SourceSnippets>,
+    #[cfg(feature = "std")]
+    rust_src: Option<std::path::PathBuf>,
+    #[cfg(feature = "std")]
+    module_relative: bool,
+    /// The libraries to print addresses relative to, listed when they're
+    /// first needed.
+    #[cfg(feature = "std")]
+    libraries: Option<Vec<crate::Library>>,
+    trim_runtime_frames: bool,
+    entry_point_frame: Option<usize>,
+    frame_filter: Option<FrameFilter<'a>>,
 }
 
+/// A filter installed with `BacktraceFmt::set_frame_filter`.
+type FrameFilter<'a> = Box<dyn Fn(&SymbolName<'_>) -> bool + 'a>;
+
 /// The styles of printing that we can print
 #[derive(Copy, Clone, Eq, PartialEq)]
 #[non_exhaustive]
@@ -51,9 +72,188 @@ impl<'a, 'b> BacktraceFmt<'a, 'b> {
             frame_index: 0,
             format,
             print_path,
+            #[cfg(feature = "std")]
+            source_snippets: None,
+            #[cfg(feature = "std")]
+            rust_src: None,
+            #[cfg(feature = "std")]
+            module_relative: false,
+            #[cfg(feature = "std")]
+            libraries: None,
+            trim_runtime_frames: false,
+            entry_point_frame: None,
+            frame_filter: None,
         }
     }
 
+    /// Configures whether frames of the C runtime which call into `main` are
+    /// left out of `PrintFmt::Short` backtraces.
+    ///
+    /// This is disabled by default. When enabled, nothing is printed for frames
+    /// after the one for `main` itself, and frames for the known program entry
+    /// points of glibc and musl (such as `_start` and `__libc_start_main`) are
+    /// skipped wherever they appear. These frames show up at the bottom of
+    /// nearly every backtrace captured on the main thread but are rarely of
+    /// interest. The same goes for the frames of glibc's dynamic linker binding
+    /// a symbol on its first call (`_dl_runtime_resolve` and `_dl_fixup`),
+    /// which show up when a backtrace is captured during that binding. Frames
+    /// are still counted, so the indices of the frames that are printed don't
+    /// change. `PrintFmt::Full` always prints every frame.
+    pub fn trim_runtime_frames(&mut self, trim: bool) {
+        self.trim_runtime_frames = trim;
+    }
+
+    /// Installs a filter deciding which symbols get printed, by their names.
+    ///
+    /// `filter` is called with the name of every symbol about to be printed,
+    /// and the symbol is left out if it returns `false`. This is meant for
+    /// user-facing backtraces which only show the frames of the application
+    /// itself, without the frames of the standard library which show up in
+    /// every backtrace, like `std::rt::lang_start` or
+    /// `core::ops::function::FnOnce::call_once`. Symbols without a name are
+    /// always printed.
+    ///
+    /// A frame with several symbols, one for each function inlined into it,
+    /// is printed with just the symbols the filter keeps, and isn't printed at
+    /// all if it keeps none of them. Frames are still counted, so the indices
+    /// of the frames that are printed don't change. This applies to both
+    /// `PrintFmt::Short` and `PrintFmt::Full`. Without a filter every symbol
+    /// is printed, which is the default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use backtrace::{BacktraceFmt, BytesOrWideString, PrintFmt};
+    /// use std::fmt;
+    ///
+    /// struct AppFrames;
+    ///
+    /// impl fmt::Display for AppFrames {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         let mut print_path =
+    ///             |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| path.fmt(f);
+    ///         let mut bt = BacktraceFmt::new(f, PrintFmt::Short, &mut print_path);
+    ///         bt.set_frame_filter(|name| {
+    ///             let name = format!("{name:#}");
+    ///             !["std::", "core::", "alloc::"].iter().any(|p| name.starts_with(p))
+    ///         });
+    ///         backtrace::trace(|frame| {
+    ///             let mut result = Ok(());
+    ///             backtrace::resolve_frame(frame, |symbol| {
+    ///                 result = bt.frame().symbol(frame, symbol);
+    ///             });
+    ///             result.is_ok()
+    ///         });
+    ///         bt.finish()
+    ///     }
+    /// }
+    ///
+    /// println!("{}", AppFrames);
+    /// ```
+    pub fn set_frame_filter(&mut self, filter: impl Fn(&SymbolName<'_>) -> bool + 'a) {
+        self.frame_filter = Some(Box::new(filter));
+    }
+
+    /// Enables printing a snippet of source code under each frame's filename
+    /// and line number.
+    ///
+    /// When enabled, the line being executed is printed along with
+    /// `context_lines` lines on either side of it, and a caret is placed under
+    /// the column if it's known. Source files are read from the local
+    /// filesystem as they're encountered and cached for the lifetime of this
+    /// formatter. Files which can't be read, for example because the backtrace
+    /// is printed on a different machine than it was built on, simply don't
+    /// get a snippet.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[cfg(feature = "std")]
+    pub fn enable_source_snippets(&mut self, context_lines: usize) {
+        self.source_snippets = Some(source::SourceSnippets::new(context_lines));
+    }
+
+    /// Sets where the source code of the standard library is found, for the
+    /// snippets printed by `enable_source_snippets`.
+    ///
+    /// The standard library is built with its source paths remapped to
+    /// `/rustc/<commit hash>/`, which doesn't exist locally, so snippets
+    /// can't be printed for its frames by default. With this set, snippets
+    /// are read from `path` instead, which should be the `rust` directory of
+    /// the `rust-src` component, found under
+    /// `$(rustc --print sysroot)/lib/rustlib/src/rust`. Note that this should
+    /// be the source of the same Rust version the program was built with, or
+    /// the snippets won't match up with the line numbers. The filenames
+    /// printed for these frames are left as they are.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[cfg(feature = "std")]
+    pub fn rust_src_path(&mut self, path: impl Into<std::path::PathBuf>) {
+        self.rust_src = Some(path.into());
+    }
+
+    /// Configures whether `PrintFmt::Full` backtraces print the address of each
+    /// frame relative to the library it's in, as `<library>+0x<offset>`,
+    /// rather than as an absolute address.
+    ///
+    /// Absolute addresses depend on where libraries happened to be loaded,
+    /// which differs between runs of a program with address space layout
+    /// randomization, and logging them gives that layout away. Offsets don't,
+    /// so backtraces from different runs of the same build can be compared.
+    /// The offset is the address the code was linked at, which tools like
+    /// `addr2line` can look up in the library. Only the file name of the
+    /// library is printed, not its directory, and addresses which aren't in
+    /// any library are printed as `<unknown>`.
+    ///
+    /// This is disabled by default. The libraries are the ones loaded into
+    /// the current process when the first frame is printed, see
+    /// `native_libraries`, so this is only meaningful for backtraces captured
+    /// by this process.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[cfg(feature = "std")]
+    pub fn module_relative_addresses(&mut self, relative: bool) {
+        self.module_relative = relative;
+    }
+
+    /// Formats `ip` relative to the library it's in, or returns `None` if
+    /// `module_relative_addresses` isn't enabled.
+    #[cfg(feature = "std")]
+    fn module_relative_address(&mut self, ip: *mut c_void) -> Option<std::string::String> {
+        if !self.module_relative {
+            return None;
+        }
+        let libraries = self.libraries.get_or_insert_with(crate::native_libraries);
+        let ip = ip as usize;
+        let library = libraries.iter().find(|library| {
+            library.segments().iter().any(|segment| {
+                let start = segment
+                    .stated_virtual_memory_address()
+                    .wrapping_add(library.bias());
+                ip.wrapping_sub(start) < segment.size()
+            })
+        });
+        Some(match library {
+            Some(library) => {
+                let name = library.name();
+                let name = name.file_name().unwrap_or(name.as_os_str());
+                std::format!(
+                    "{}+{:#x}",
+                    name.to_string_lossy(),
+                    ip.wrapping_sub(library.bias())
+                )
+            }
+            None => "<unknown>".into(),
+        })
+    }
+
     /// Prints a preamble for the backtrace about to be printed.
     ///
     /// This is required on some platforms for backtraces to be fully
@@ -74,6 +274,8 @@ impl<'a, 'b> BacktraceFmt<'a, 'b> {
         BacktraceFrameFmt {
             fmt: self,
             symbol_index: 0,
+            last_location: None,
+            address_width: HEX_WIDTH,
         }
     }
 
@@ -111,6 +313,44 @@ impl<'a, 'b> BacktraceFmt<'a, 'b> {
 pub struct BacktraceFrameFmt<'fmt, 'a, 'b> {
     fmt: &'fmt mut BacktraceFmt<'a, 'b>,
     symbol_index: usize,
+    /// The location last printed for this frame in `PrintFmt::Short`, so that
+    /// symbols inlined at the same location only print it once.
+    last_location: Option<(FilenameKey, u32, Option<u32>)>,
+    /// How wide the address printed for this frame in `PrintFmt::Full` is,
+    /// to line up the lines which follow it.
+    address_width: usize,
+}
+
+/// What's kept of a `BytesOrWideString` to compare later filenames to, which
+/// is its length and a hash rather than a copy, so that printing doesn't
+/// allocate.
+#[derive(PartialEq, Eq)]
+struct FilenameKey {
+    wide: bool,
+    len: usize,
+    hash: u64,
+}
+
+impl FilenameKey {
+    fn new(file: &BytesOrWideString<'_>) -> FilenameKey {
+        // 64-bit FNV-1a
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        let mut write = |b: u8| {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        };
+        let (wide, len) = match file {
+            BytesOrWideString::Bytes(b) => {
+                b.iter().for_each(|&b| write(b));
+                (false, b.len())
+            }
+            BytesOrWideString::Wide(w) => {
+                w.iter().flat_map(|w| w.to_le_bytes()).for_each(write);
+                (true, w.len())
+            }
+        };
+        FilenameKey { wide, len, hash }
+    }
 }
 
 impl BacktraceFrameFmt<'_, '_, '_> {
@@ -130,7 +370,8 @@ impl BacktraceFrameFmt<'_, '_, '_> {
             self.backtrace_symbol(frame, symbol)?;
         }
         if symbols.is_empty() {
-            self.print_raw(frame.ip(), None, None, None)?;
+            let label = frame.region_label().unwrap_or("<unknown>");
+            self.print_labeled(frame.ip(), None, None, None, None, label)?;
         }
         Ok(())
     }
@@ -147,7 +388,7 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         frame: &BacktraceFrame,
         symbol: &BacktraceSymbol,
     ) -> fmt::Result {
-        self.print_raw_with_column(
+        self.print_labeled(
             frame.ip(),
             symbol.name(),
             // TODO: this isn't great that we don't end up printing anything
@@ -158,6 +399,7 @@ impl BacktraceFrameFmt<'_, '_, '_> {
                 .and_then(|p| Some(BytesOrWideString::Bytes(p.to_str()?.as_bytes()))),
             symbol.lineno(),
             symbol.colno(),
+            frame.region_label().unwrap_or("<unknown>"),
         )?;
         Ok(())
     }
@@ -203,13 +445,42 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         lineno: Option<u32>,
         colno: Option<u32>,
     ) -> fmt::Result {
+        self.print_labeled(frame_ip, symbol_name, filename, lineno, colno, "<unknown>")
+    }
+
+    /// Same as `print_raw_with_column`, printing `unknown_label` when there's
+    /// no symbol name.
+    fn print_labeled(
+        &mut self,
+        frame_ip: *mut c_void,
+        symbol_name: Option<SymbolName<'_>>,
+        filename: Option<BytesOrWideString<'_>>,
+        lineno: Option<u32>,
+        colno: Option<u32>,
+        unknown_label: &'static str,
+    ) -> fmt::Result {
+        // Symbols left out by the filter don't count towards the symbols of
+        // the frame, so the first one printed still gets the frame's index.
+        if let (Some(filter), Some(name)) = (&self.fmt.frame_filter, &symbol_name) {
+            if !filter(name) {
+                return Ok(());
+            }
+        }
+
         // Fuchsia is unable to symbolize within a process so it has a special
         // format which can be used to symbolize later. Print that instead of
         // printing addresses in our own format here.
         if cfg!(target_os = "fuchsia") {
             self.print_raw_fuchsia(frame_ip)?;
         } else {
-            self.print_raw_generic(frame_ip, symbol_name, filename, lineno, colno)?;
+            self.print_raw_generic(
+                frame_ip,
+                symbol_name,
+                filename,
+                lineno,
+                colno,
+                unknown_label,
+            )?;
         }
         self.symbol_index += 1;
         Ok(())
@@ -223,6 +494,7 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         filename: Option<BytesOrWideString<'_>>,
         lineno: Option<u32>,
         colno: Option<u32>,
+        unknown_label: &'static str,
     ) -> fmt::Result {
         // No need to print "null" frames, it basically just means that the
         // system backtrace was a bit eager to trace back super far.
@@ -230,6 +502,21 @@ impl BacktraceFrameFmt<'_, '_, '_> {
             if frame_ip.is_null() {
                 return Ok(());
             }
+            if self.fmt.trim_runtime_frames {
+                if let Some(entry) = self.fmt.entry_point_frame {
+                    if self.fmt.frame_index > entry {
+                        return Ok(());
+                    }
+                }
+                match &symbol_name {
+                    Some(name) if is_runtime_entry_point(name.as_bytes()) => return Ok(()),
+                    Some(name) if is_lazy_binding(name.as_bytes()) => return Ok(()),
+                    Some(name) if name.as_bytes() == b"main" => {
+                        self.fmt.entry_point_frame = Some(self.fmt.frame_index);
+                    }
+                    _ => {}
+                }
+            }
         }
 
         // Print the index of the frame as well as the optional instruction
@@ -238,12 +525,13 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         if self.symbol_index == 0 {
             write!(self.fmt.fmt, "{:4}: ", self.fmt.frame_index)?;
             if let PrintFmt::Full = self.fmt.format {
-                write!(self.fmt.fmt, "{frame_ip:HEX_WIDTH$?} - ")?;
+                self.print_address(frame_ip)?;
+                self.fmt.fmt.write_str(" - ")?;
             }
         } else {
             write!(self.fmt.fmt, "      ")?;
             if let PrintFmt::Full = self.fmt.format {
-                write!(self.fmt.fmt, "{:1$}", "", HEX_WIDTH + 3)?;
+                write!(self.fmt.fmt, "{:1$}", "", self.address_width + 3)?;
             }
         }
 
@@ -253,18 +541,41 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         match (symbol_name, &self.fmt.format) {
             (Some(name), PrintFmt::Short) => write!(self.fmt.fmt, "{name:#}")?,
             (Some(name), PrintFmt::Full) => write!(self.fmt.fmt, "{name}")?,
-            (None, _) => write!(self.fmt.fmt, "<unknown>")?,
+            (None, _) => self.fmt.fmt.write_str(unknown_label)?,
         }
         self.fmt.fmt.write_str("\n")?;
 
         // And last up, print out the filename/line number if they're available.
+        // A chain of inlined symbols often all point at the same location, so
+        // in short backtraces repeats of the previous one are left out.
         if let (Some(file), Some(line)) = (filename, lineno) {
+            if let PrintFmt::Short = self.fmt.format {
+                let key = FilenameKey::new(&file);
+                let repeated = self
+                    .last_location
+                    .as_ref()
+                    .is_some_and(|(f, l, c)| *f == key && *l == line && *c == colno);
+                if repeated {
+                    return Ok(());
+                }
+                self.last_location = Some((key, line, colno));
+            }
             self.print_fileline(file, line, colno)?;
         }
 
         Ok(())
     }
 
+    /// Prints the address of the frame, recording how wide it is.
+    fn print_address(&mut self, frame_ip: *mut c_void) -> fmt::Result {
+        #[cfg(feature = "std")]
+        if let Some(address) = self.fmt.module_relative_address(frame_ip) {
+            self.address_width = address.chars().count();
+            return self.fmt.fmt.write_str(&address);
+        }
+        write!(self.fmt.fmt, "{frame_ip:HEX_WIDTH$?}")
+    }
+
     fn print_fileline(
         &mut self,
         file: BytesOrWideString<'_>,
@@ -273,10 +584,19 @@ impl BacktraceFrameFmt<'_, '_, '_> {
     ) -> fmt::Result {
         // Filename/line are printed on lines under the symbol name, so print
         // some appropriate whitespace to sort of right-align ourselves.
+        let mut indent = 13;
         if let PrintFmt::Full = self.fmt.format {
-            write!(self.fmt.fmt, "{:1$}", "", HEX_WIDTH)?;
+            indent += self.address_width;
         }
-        write!(self.fmt.fmt, "             at ")?;
+        write!(self.fmt.fmt, "{:indent$}at ", "")?;
+
+        // Hang on to the path for the source snippet below, if any, since the
+        // callback consumes the filename.
+        #[cfg(feature = "std")]
+        let snippet_path = self.fmt.source_snippets.as_ref().map(|_| match file {
+            BytesOrWideString::Bytes(b) => BytesOrWideString::Bytes(b),
+            BytesOrWideString::Wide(w) => BytesOrWideString::Wide(w),
+        });
 
         // Delegate to our internal callback to print the filename and then
         // print out the line number.
@@ -289,6 +609,15 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         }
 
         writeln!(self.fmt.fmt)?;
+
+        #[cfg(feature = "std")]
+        if let (Some(snippets), Some(path)) = (&mut self.fmt.source_snippets, snippet_path) {
+            let mut path = path.into_path_buf();
+            if let Some(rust_src) = &self.fmt.rust_src {
+                path = source::remap_rust_src(path, rust_src);
+            }
+            snippets.print(self.fmt.fmt, indent + 3, path, line, colno)?;
+        }
         Ok(())
     }
 
@@ -303,6 +632,32 @@ impl BacktraceFrameFmt<'_, '_, '_> {
     }
 }
 
+/// Returns whether `name` is one of the functions a C runtime uses to get from
+/// the process entry point to `main`.
+pub(crate) fn is_runtime_entry_point(name: &[u8]) -> bool {
+    match name {
+        // Common to glibc and musl.
+        b"_start" | b"__libc_start_main" => true,
+        // glibc, where `__libc_start_main` is an alias of
+        // `__libc_start_main_impl` as of 2.34.
+        b"__libc_start_call_main" | b"__libc_start_main_impl" => true,
+        // musl
+        b"_start_c" | b"libc_start_main_stage2" => true,
+        _ => false,
+    }
+}
+
+/// Returns whether `name` is one of the functions glibc's dynamic linker uses
+/// to bind a symbol the first time it's called through the PLT.
+fn is_lazy_binding(name: &[u8]) -> bool {
+    // The trampoline has variants for saving different register sets, such as
+    // `_dl_runtime_resolve_xsavec`, and for `LD_AUDIT` there's `_profile`.
+    name.starts_with(b"_dl_runtime_resolve")
+        || name.starts_with(b"_dl_runtime_profile")
+        || name == b"_dl_fixup"
+        || name == b"_dl_profile_fixup"
+}
+
 impl Drop for BacktraceFrameFmt<'_, '_, '_> {
     fn drop(&mut self) {
         self.fmt.frame_index += 1;
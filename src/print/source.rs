@@ -0,0 +1,130 @@
+//! Printing of source code snippets next to frames in a backtrace.
+//!
+//! Source files are read lazily as frames referencing them are printed, and
+//! are cached for the lifetime of the `BacktraceFmt` so a file which shows up
+//! in many frames is only read once.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+
+pub(super) struct SourceSnippets {
+    /// Number of lines to print on either side of the line being executed.
+    context_lines: usize,
+    /// Lines of each source file read so far, or `None` if it couldn't be read.
+    files: HashMap<PathBuf, Option<Vec<String>>>,
+}
+
+impl SourceSnippets {
+    pub(super) fn new(context_lines: usize) -> SourceSnippets {
+        SourceSnippets {
+            context_lines,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Prints the lines surrounding `line` in `path`, with a caret under
+    /// `colno` if it's known.
+    ///
+    /// Nothing is printed if the file can't be read or doesn't have `line` in
+    /// it, snippets are purely a best-effort addition to the backtrace.
+    pub(super) fn print(
+        &mut self,
+        fmt: &mut fmt::Formatter<'_>,
+        indent: usize,
+        path: PathBuf,
+        line: u32,
+        colno: Option<u32>,
+    ) -> fmt::Result {
+        let lines = self.files.entry(path).or_insert_with_key(|path| {
+            let contents = fs::read_to_string(path).ok()?;
+            Some(contents.lines().map(String::from).collect())
+        });
+        let lines = match lines {
+            Some(lines) => lines,
+            None => return Ok(()),
+        };
+
+        // Line numbers are 1-based, and a line of 0 means "unknown".
+        let idx = match (line as usize).checked_sub(1) {
+            Some(idx) if idx < lines.len() => idx,
+            _ => return Ok(()),
+        };
+        let start = idx.saturating_sub(self.context_lines);
+        let end = (idx + self.context_lines + 1).min(lines.len());
+        let width = end.to_string().len();
+
+        for (i, text) in lines[start..end].iter().enumerate() {
+            let lineno = start + i + 1;
+            writeln!(fmt, "{:indent$}{lineno:>width$} | {text}", "")?;
+
+            if start + i != idx {
+                continue;
+            }
+            if let Some(colno) = colno.and_then(|c| (c as usize).checked_sub(1)) {
+                // Columns count bytes, so pad with one space per character
+                // before the column, keeping tabs so the caret lines up with
+                // the text above it.
+                let mut end = colno.min(text.len());
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let pad: String = text[..end]
+                    .chars()
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                writeln!(fmt, "{:indent$}{:width$} | {pad}^", "", "")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Maps `path` into `rust_src` if it's a path into the source of the standard
+/// library, which starts with `/rustc/<commit hash>/`.
+pub(super) fn remap_rust_src(path: PathBuf, rust_src: &Path) -> PathBuf {
+    let rest = path.to_str().and_then(|path| {
+        let path = path.strip_prefix("/rustc/")?;
+        let (hash, rest) = path.split_once(['/', '\\'])?;
+        if hash.is_empty() || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        // Windows builds record paths like `/rustc/<hash>\library\std\...`.
+        Some(rest.split(['/', '\\']).collect::<PathBuf>())
+    });
+    match rest {
+        Some(rest) => rust_src.join(rest),
+        None => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_src_paths() {
+        let rust_src = Path::new("/sysroot/lib/rustlib/src/rust");
+        let remap = |path: &str| remap_rust_src(PathBuf::from(path), rust_src);
+        let expected = rust_src
+            .join("library")
+            .join("std")
+            .join("src")
+            .join("rt.rs");
+        assert_eq!(
+            remap("/rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/std/src/rt.rs"),
+            expected
+        );
+        assert_eq!(
+            remap("/rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf\\library\\std\\src\\rt.rs"),
+            expected
+        );
+        assert_eq!(
+            remap("/rustc/not-a-hash/library/std/src/rt.rs"),
+            PathBuf::from("/rustc/not-a-hash/library/std/src/rt.rs")
+        );
+        assert_eq!(remap("/src/main.rs"), PathBuf::from("/src/main.rs"));
+    }
+}
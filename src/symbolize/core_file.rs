@@ -0,0 +1,168 @@
+//! Symbolication of addresses from a core dump rather than the current
+//! process, see `CoreSymbolizer`.
+
+use super::{imp, Symbol};
+use core::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+
+/// A file which was mapped into the address space of a process, as listed in
+/// the `NT_FILE` note of an ELF core dump.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone, Debug)]
+pub struct MappedFile {
+    path: PathBuf,
+    start: u64,
+    end: u64,
+    file_offset: u64,
+}
+
+impl MappedFile {
+    /// Creates a record of `path` being mapped at the addresses
+    /// `start..end`, starting `file_offset` bytes into the file.
+    ///
+    /// Note that the `NT_FILE` note stores file offsets in units of its page
+    /// size, so they need to be multiplied by that page size first.
+    pub fn new(path: impl Into<PathBuf>, start: u64, end: u64, file_offset: u64) -> MappedFile {
+        MappedFile {
+            path: path.into(),
+            start,
+            end,
+            file_offset,
+        }
+    }
+
+    /// Returns the path of the file which was mapped.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the address the mapping starts at.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the address just past the end of the mapping.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns how many bytes into the file the mapping starts.
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+
+    /// Parses the text of a process's `/proc/<pid>/maps` file into the files
+    /// mapped into it, in the order they're listed.
+    ///
+    /// Anonymous mappings, pseudo-paths like `[heap]` or `[vdso]` and lines
+    /// which can't be parsed are skipped. Note that `/proc/<pid>/maps` lists
+    /// file offsets in bytes, unlike the `NT_FILE` note of a core dump.
+    ///
+    /// Like `CoreSymbolizer`, this is only supported on platforms using ELF
+    /// with the default symbolication backend, and nothing is returned
+    /// elsewhere.
+    pub fn parse_maps(text: &str) -> Vec<MappedFile> {
+        imp::parse_maps_text(text)
+    }
+}
+
+/// Resolves addresses from the stack of another process, described by the
+/// files which were mapped into it.
+///
+/// This is intended for post-mortem debugging tools, which can read the list
+/// of mapped files out of a core dump's `NT_FILE` note along with the stack
+/// addresses to symbolicate. The files are opened by path from the local
+/// filesystem, so they need to be the same builds the crashed process used,
+/// and the core dump needs to come from a process of the same architecture as
+/// the current one.
+///
+/// Each `CoreSymbolizer` keeps its own cache of parsed debug information,
+/// separate from the one used by `resolve` and unaffected by
+/// `clear_symbol_cache`. Dropping it releases that cache.
+///
+/// Currently this is only supported on platforms using ELF with the default
+/// symbolication backend. Elsewhere no addresses will be resolved.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+pub struct CoreSymbolizer {
+    inner: imp::CoreSymbolizer,
+}
+
+impl CoreSymbolizer {
+    /// Creates a symbolizer for a process which had `files` mapped into its
+    /// address space.
+    ///
+    /// Every file is inspected as part of this call to find out where its
+    /// segments were loaded. Files which can't be opened or parsed are
+    /// skipped, and addresses within them won't be resolved.
+    pub fn from_nt_file<I>(files: I) -> CoreSymbolizer
+    where
+        I: IntoIterator<Item = MappedFile>,
+    {
+        let files = files.into_iter().collect::<Vec<_>>();
+        CoreSymbolizer {
+            inner: imp::CoreSymbolizer::new(&files),
+        }
+    }
+
+    /// Creates a symbolizer for a process from the text of its
+    /// `/proc/<pid>/maps` file, reading the files it lists with
+    /// `file_opener`.
+    ///
+    /// This is meant for tools which snapshot another process, such as
+    /// profilers, which can save the text of its maps file and the files
+    /// mapped into it along with the addresses they sampled. `file_opener` is
+    /// given the path of each file as it's listed in `maps`, and returns the
+    /// file's contents, or `None` if it isn't available. The contents can be
+    /// anything which dereferences to bytes, such as a `Vec<u8>` or a memory
+    /// map of the file.
+    ///
+    /// The files are parsed as in `from_nt_file`, except that their debug
+    /// information is only looked for in the files themselves rather than
+    /// also in separate debug files on the local filesystem.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use backtrace::CoreSymbolizer;
+    /// use std::path::Path;
+    ///
+    /// // Read the files through the process's view of the filesystem.
+    /// let root = Path::new("/proc/1234/root");
+    /// let maps = std::fs::read_to_string("/proc/1234/maps").unwrap();
+    /// let mut symbolizer = CoreSymbolizer::from_maps_text(&maps, move |path| {
+    ///     std::fs::read(root.join(path.strip_prefix("/").ok()?)).ok()
+    /// });
+    /// symbolizer.resolve(0x7f12_3456_789a, |symbol| {
+    ///     println!("{:?}", symbol.name());
+    /// });
+    /// ```
+    pub fn from_maps_text<F, M>(maps: &str, file_opener: F) -> CoreSymbolizer
+    where
+        F: Fn(&Path) -> Option<M> + 'static,
+        M: Deref<Target = [u8]> + 'static,
+    {
+        let files = MappedFile::parse_maps(maps);
+        CoreSymbolizer {
+            inner: imp::CoreSymbolizer::with_opener(&files, file_opener),
+        }
+    }
+
+    /// Resolves an address from the process this symbolizer describes,
+    /// passing symbols to the specified closure like `resolve` does.
+    ///
+    /// Just like with `resolve`, `addr` is assumed to be a return address
+    /// taken from a stack frame, so the instruction before it is what gets
+    /// resolved.
+    pub fn resolve<F: FnMut(&Symbol)>(&mut self, addr: u64, mut cb: F) {
+        self.inner.resolve(addr.saturating_sub(1), &mut cb)
+    }
+}
@@ -19,6 +19,8 @@
 
 use super::super::{dbghelp, windows_sys::*};
 use super::{BytesOrWideString, ResolveWhat, SymbolName};
+use alloc::vec;
+use alloc::vec::Vec;
 use core::ffi::c_void;
 use core::marker;
 use core::mem;
@@ -50,6 +52,22 @@ impl Symbol<'_> {
         Some(SymbolName::new(unsafe { &*self.name }))
     }
 
+    pub fn linkage_name(&self) -> Option<SymbolName<'_>> {
+        None
+    }
+
+    pub fn source_name(&self) -> Option<SymbolName<'_>> {
+        None
+    }
+
+    pub fn call_target(&self) -> Option<SymbolName<'_>> {
+        None
+    }
+
+    pub fn is_optimized(&self) -> Option<bool> {
+        None
+    }
+
     pub fn addr(&self) -> Option<*mut c_void> {
         Some(self.addr)
     }
@@ -73,6 +91,11 @@ impl Symbol<'_> {
 
         self._filename_cache.as_ref().map(Path::new)
     }
+
+    #[cfg(feature = "std")]
+    pub fn module(&self) -> Option<&::std::path::Path> {
+        None
+    }
 }
 
 #[repr(C, align(8))]
@@ -201,18 +224,18 @@ unsafe fn resolve_with_inline(
 }
 
 unsafe fn do_resolve(
-    sym_from_addr: impl FnOnce(*mut SYMBOL_INFOW) -> BOOL,
+    mut sym_from_addr: impl FnMut(*mut SYMBOL_INFOW) -> BOOL,
     get_line_from_addr: impl FnOnce(&mut IMAGEHLP_LINEW64) -> BOOL,
     cb: &mut dyn FnMut(&super::Symbol),
 ) {
     const SIZE: usize = 2 * MAX_SYM_NAME as usize + mem::size_of::<SYMBOL_INFOW>();
     let mut data = Aligned8([0u8; SIZE]);
-    let info = &mut *data.0.as_mut_ptr().cast::<SYMBOL_INFOW>();
-    info.MaxNameLen = MAX_SYM_NAME as u32;
+    let mut info = data.0.as_mut_ptr().cast::<SYMBOL_INFOW>();
+    (*info).MaxNameLen = MAX_SYM_NAME;
     // the struct size in C.  the value is different to
     // `size_of::<SYMBOL_INFOW>() - MAX_SYM_NAME + 1` (== 81)
     // due to struct alignment.
-    info.SizeOfStruct = 88;
+    (*info).SizeOfStruct = 88;
 
     if sym_from_addr(info) != TRUE {
         return;
@@ -220,32 +243,50 @@ unsafe fn do_resolve(
 
     // If the symbol name is greater than MaxNameLen, SymFromAddrW will
     // give a buffer of (MaxNameLen - 1) characters and set NameLen to
-    // the real value.
+    // the real value. Heavily generic Rust and C++ names can get that long,
+    // so ask again with a buffer large enough for all of it.
+    let mut long_data: Vec<u64>;
+    if (*info).NameLen >= (*info).MaxNameLen {
+        let max_name_len = (*info).NameLen + 1;
+        let size = 2 * max_name_len as usize + mem::size_of::<SYMBOL_INFOW>();
+        long_data = vec![0; size.div_ceil(mem::size_of::<u64>())];
+        info = long_data.as_mut_ptr().cast::<SYMBOL_INFOW>();
+        (*info).MaxNameLen = max_name_len;
+        (*info).SizeOfStruct = 88;
+        if sym_from_addr(info) != TRUE {
+            return;
+        }
+    }
+    let info = &*info;
+
     let name_len = ::core::cmp::min(info.NameLen as usize, info.MaxNameLen as usize - 1);
     let name_ptr = info.Name.as_ptr().cast::<u16>();
 
     // Reencode the utf-16 symbol to utf-8 so we can use `SymbolName::new` like
-    // all other platforms
-    let mut name_buffer = [0_u8; 256];
-    let mut name_len = WideCharToMultiByte(
+    // all other platforms, first asking how much space that takes.
+    let utf8_len = WideCharToMultiByte(
+        CP_UTF8,
+        0,
+        name_ptr,
+        name_len as i32,
+        ptr::null_mut(),
+        0,
+        ptr::null_mut(),
+        ptr::null_mut(),
+    );
+    let mut name_buffer = vec![0_u8; utf8_len.max(0) as usize];
+    let written = WideCharToMultiByte(
         CP_UTF8,
         0,
         name_ptr,
         name_len as i32,
         name_buffer.as_mut_ptr(),
-        name_buffer.len() as i32,
-        core::ptr::null_mut(),
-        core::ptr::null_mut(),
-    ) as usize;
-    if name_len == 0 {
-        // If the returned length is zero that means the buffer wasn't big enough.
-        // However, the buffer will be filled with as much as will fit.
-        name_len = name_buffer.len();
-    } else if name_len > name_buffer.len() {
-        // This can't happen.
-        return;
-    }
-    let name = ptr::addr_of!(name_buffer[..name_len]);
+        utf8_len,
+        ptr::null_mut(),
+        ptr::null_mut(),
+    );
+    name_buffer.truncate(written.max(0) as usize);
+    let name = ptr::addr_of!(name_buffer[..]);
 
     let mut line = mem::zeroed::<IMAGEHLP_LINEW64>();
     line.SizeOfStruct = mem::size_of::<IMAGEHLP_LINEW64>() as u32;
@@ -288,3 +329,186 @@ unsafe fn cache(filename: Option<*const [u16]>) -> Option<::std::ffi::OsString>
 unsafe fn cache(_filename: Option<*const [u16]>) {}
 
 pub unsafe fn clear_symbol_cache() {}
+
+pub unsafe fn set_mapping_cache_size(_size: usize) {}
+
+pub unsafe fn reset() {
+    dbghelp::reset();
+}
+
+#[cfg(feature = "std")]
+pub fn resolve_in_archive(
+    _path: &std::path::Path,
+    _member: &[u8],
+    _svma: u64,
+    _cb: &mut dyn FnMut(&super::Symbol),
+) {
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_location(
+    what: ResolveWhat<'_>,
+) -> Option<(std::path::PathBuf, u32, Option<u32>)> {
+    super::location_of_first_symbol(what)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_candidates(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+    resolve(what, cb)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_batch(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &super::Symbol)) {
+    super::resolve_each(addrs, cb)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_name_only(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+    resolve(what, cb)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn preload_library(_path: &std::path::Path) -> bool {
+    false
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn register_module(
+    base: *mut c_void,
+    size: usize,
+    path: Option<&std::path::Path>,
+) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+
+    let Ok(size) = u32::try_from(size) else {
+        return false;
+    };
+    let Ok(dbghelp) = dbghelp::init() else {
+        return false;
+    };
+    let path = path.map(|path| {
+        let mut path = path.as_os_str().encode_wide().collect::<Vec<_>>();
+        path.push(0);
+        path
+    });
+    let image_name = path.as_ref().map_or(ptr::null(), |path| path.as_ptr());
+    let base = dbghelp.SymLoadModuleExW()(
+        GetCurrentProcess(),
+        ptr::null_mut(),
+        image_name,
+        ptr::null(),
+        base as u64,
+        size,
+        ptr::null(),
+        0,
+    );
+    base != 0
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn enable_gdb_jit() {}
+
+pub fn debuginfo_mismatch() -> bool {
+    false
+}
+
+pub unsafe fn library_replaced() -> bool {
+    false
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn mapped_ranges() -> Option<Vec<core::ops::Range<usize>>> {
+    None
+}
+
+#[cfg(feature = "std")]
+pub fn special_regions() -> Vec<(core::ops::Range<usize>, &'static str)> {
+    Vec::new()
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn cache_stats() -> super::CacheStats {
+    super::CacheStats::default()
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn loaded_libraries() -> Vec<super::Library> {
+    extern "system" fn callback(
+        module_name: PCWSTR,
+        module_base: u64,
+        module_size: u32,
+        user_context: *const c_void,
+    ) -> BOOL {
+        use std::os::windows::ffi::OsStringExt;
+
+        let libraries = unsafe { &mut *(user_context as *mut Vec<super::Library>) };
+        let len = unsafe { lstrlenW(module_name) };
+        let name = unsafe { slice::from_raw_parts(module_name, len.max(0) as usize) };
+        // Only the extent of the whole image is known.
+        if let Ok(base) = usize::try_from(module_base) {
+            let segment = super::LibrarySegment::new(0, module_size as usize);
+            let name = std::ffi::OsString::from_wide(name).into();
+            libraries.push(super::Library::new(name, vec![segment], base));
+        }
+        TRUE
+    }
+
+    let mut libraries = Vec::new();
+    let Ok(dbghelp) = dbghelp::init() else {
+        return libraries;
+    };
+    dbghelp.EnumerateLoadedModulesW64()(
+        GetCurrentProcess(),
+        Some(callback),
+        ptr::addr_of_mut!(libraries).cast(),
+    );
+    libraries
+}
+
+// Core dumps can't be symbolicated with this backend.
+#[cfg(feature = "std")]
+pub struct CoreSymbolizer;
+
+#[cfg(feature = "std")]
+impl CoreSymbolizer {
+    pub fn new(_files: &[super::MappedFile]) -> CoreSymbolizer {
+        CoreSymbolizer
+    }
+
+    pub fn with_opener<F, M>(_files: &[super::MappedFile], _open: F) -> CoreSymbolizer
+    where
+        F: Fn(&std::path::Path) -> Option<M> + 'static,
+        M: core::ops::Deref<Target = [u8]> + 'static,
+    {
+        CoreSymbolizer
+    }
+
+    pub fn resolve(&mut self, _addr: u64, _cb: &mut dyn FnMut(&super::Symbol)) {}
+}
+
+// DbgHelp keeps its state per process, so there's nothing to own here and
+// lookups go through the global state like `resolve`.
+#[cfg(feature = "std")]
+pub struct Symbolizer;
+
+#[cfg(feature = "std")]
+impl Symbolizer {
+    pub fn new() -> Symbolizer {
+        Symbolizer
+    }
+
+    pub fn preload(&mut self, _addr: *mut c_void) -> bool {
+        false
+    }
+
+    pub fn resolve(&mut self, what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+        let _guard = crate::lock::lock();
+        unsafe { resolve(what, cb) }
+    }
+}
+
+#[cfg(feature = "std")]
+pub fn parse_maps_text(_text: &str) -> Vec<super::MappedFile> {
+    Vec::new()
+}
@@ -3,7 +3,7 @@
 //! This is the default symbolication implementation for Rust.
 
 use self::gimli::read::EndianSlice;
-use self::gimli::NativeEndian as Endian;
+use self::gimli::RunTimeEndian as Endian;
 use self::mmap::Mmap;
 use self::stash::Stash;
 use super::BytesOrWideString;
@@ -12,6 +12,7 @@ use super::SymbolName;
 use addr2line::gimli;
 use core::convert::TryInto;
 use core::mem;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use libc::c_void;
 use mystd::ffi::OsString;
 use mystd::fs::File;
@@ -57,15 +58,57 @@ mod stash;
 
 use lru::Lru;
 
-const MAPPINGS_CACHE_SIZE: usize = 4;
+/// How many libraries have their debug information kept loaded at a time
+/// unless configured otherwise, see `set_mapping_cache_size`.
+const DEFAULT_MAPPINGS_CACHE_SIZE: usize = 4;
+
+/// How many libraries have their debug information kept loaded at a time.
+static MAPPINGS_CACHE_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_MAPPINGS_CACHE_SIZE);
+
+/// How many addresses outside of any library are remembered for every library
+/// whose debug information is kept loaded, see `Cache::unmapped`. Processes
+/// which symbolicate many libraries tend to run JIT-compiled code too, so this
+/// grows along with `MAPPINGS_CACHE_SIZE`.
+const UNMAPPED_PER_MAPPING: usize = 16;
 
 struct Mapping {
     // 'static lifetime is a lie to hack around lack of support for self-referential structs.
     cx: Context<'static>,
-    _map: Mmap,
+    // `None` for mappings of data copied into the stash, see `mk_copy`.
+    _map: Option<FileData>,
     stash: Stash,
 }
 
+/// The contents of a file a `Mapping` is created from.
+enum FileData {
+    Mmap(Mmap),
+    /// Contents read by a `CoreSymbolizer`'s file opener.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    Opened(Box<dyn core::ops::Deref<Target = [u8]>>),
+}
+
+impl core::ops::Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileData::Mmap(map) => map,
+            FileData::Opened(data) => data,
+        }
+    }
+}
+
+impl From<Mmap> for FileData {
+    fn from(map: Mmap) -> FileData {
+        FileData::Mmap(map)
+    }
+}
+
+/// Reads the files of the libraries of a `CoreSymbolizer`, see
+/// `super::CoreSymbolizer::from_maps_text`.
+#[cfg(feature = "std")]
+type FileOpener = Box<dyn Fn(&Path) -> Option<FileData>>;
+
 enum Either<A, B> {
     #[allow(dead_code)]
     A(A),
@@ -76,7 +119,7 @@ impl Mapping {
     /// Creates a `Mapping` by ensuring that the `data` specified is used to
     /// create a `Context` and it can only borrow from that or the `Stash` of
     /// decompressed sections or auxiliary data.
-    fn mk<F>(data: Mmap, mk: F) -> Option<Mapping>
+    fn mk<F>(data: impl Into<FileData>, mk: F) -> Option<Mapping>
     where
         F: for<'a> FnOnce(&'a [u8], &'a Stash) -> Option<Context<'a>>,
     {
@@ -88,10 +131,11 @@ impl Mapping {
 
     /// Creates a `Mapping` from `data`, or if the closure decides to, returns a
     /// different mapping.
-    fn mk_or_other<F>(data: Mmap, mk: F) -> Option<Mapping>
+    fn mk_or_other<F>(data: impl Into<FileData>, mk: F) -> Option<Mapping>
     where
         F: for<'a> FnOnce(&'a [u8], &'a Stash) -> Option<Either<Mapping, Context<'a>>>,
     {
+        let data = data.into();
         let stash = Stash::new();
         let cx = match mk(&data, &stash)? {
             Either::A(mapping) => return Some(mapping),
@@ -101,7 +145,26 @@ impl Mapping {
             // Convert to 'static lifetimes since the symbols should
             // only borrow `map` and `stash` and we're preserving them below.
             cx: unsafe { core::mem::transmute::<Context<'_>, Context<'static>>(cx) },
-            _map: data,
+            _map: Some(data),
+            stash,
+        })
+    }
+
+    /// Creates a `Mapping` from a copy of `data`, for data which may not
+    /// outlive the mapping, such as objects in memory owned by someone else.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn mk_copy<F>(data: &[u8], mk: F) -> Option<Mapping>
+    where
+        F: for<'a> FnOnce(&'a [u8], &'a Stash) -> Option<Context<'a>>,
+    {
+        let stash = Stash::new();
+        let copy = stash.allocate(data.len());
+        copy.copy_from_slice(data);
+        let cx = mk(copy, &stash)?;
+        Some(Mapping {
+            // Same as in `mk_or_other`, the copy is owned by `stash`.
+            cx: unsafe { core::mem::transmute::<Context<'_>, Context<'static>>(cx) },
+            _map: None,
             stash,
         })
     }
@@ -111,6 +174,9 @@ struct Context<'a> {
     dwarf: addr2line::Context<EndianSlice<'a, Endian>>,
     object: Object<'a>,
     package: Option<gimli::DwarfPackage<EndianSlice<'a, Endian>>>,
+    /// Whether there's any `.debug_info` or `.debug_line` at all.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    has_debug_info: bool,
 }
 
 impl<'data> Context<'data> {
@@ -120,15 +186,18 @@ impl<'data> Context<'data> {
         sup: Option<Object<'data>>,
         dwp: Option<Object<'data>>,
     ) -> Option<Context<'data>> {
+        // All of the files need to have the same endianness, which is that of
+        // the object itself rather than necessarily the native one.
+        let endian = object.dwarf_endian();
         let mut sections = gimli::Dwarf::load(|id| -> Result<_, ()> {
             if cfg!(not(target_os = "aix")) {
                 let data = object.section(stash, id.name()).unwrap_or(&[]);
-                Ok(EndianSlice::new(data, Endian))
+                Ok(EndianSlice::new(data, endian))
             } else if let Some(name) = id.xcoff_name() {
                 let data = object.section(stash, name).unwrap_or(&[]);
-                Ok(EndianSlice::new(data, Endian))
+                Ok(EndianSlice::new(data, endian))
             } else {
-                Ok(EndianSlice::new(&[], Endian))
+                Ok(EndianSlice::new(&[], endian))
             }
         })
         .ok()?;
@@ -137,10 +206,13 @@ impl<'data> Context<'data> {
             sections
                 .load_sup(|id| -> Result<_, ()> {
                     let data = sup.section(stash, id.name()).unwrap_or(&[]);
-                    Ok(EndianSlice::new(data, Endian))
+                    Ok(EndianSlice::new(data, endian))
                 })
                 .ok()?;
         }
+        use gimli::Section;
+        let has_debug_info =
+            !sections.debug_info.reader().is_empty() || !sections.debug_line.reader().is_empty();
         let dwarf = addr2line::Context::from_dwarf(sections).ok()?;
 
         let mut package = None;
@@ -152,9 +224,9 @@ impl<'data> Context<'data> {
                             .dwo_name()
                             .and_then(|name| dwp.section(stash, name))
                             .unwrap_or(&[]);
-                        Ok(EndianSlice::new(data, Endian))
+                        Ok(EndianSlice::new(data, endian))
                     },
-                    EndianSlice::new(&[], Endian),
+                    EndianSlice::new(&[], endian),
                 )
                 .ok()?,
             );
@@ -164,6 +236,7 @@ impl<'data> Context<'data> {
             dwarf,
             object,
             package,
+            has_debug_info,
         })
     }
 
@@ -184,6 +257,181 @@ impl<'data> Context<'data> {
             l = continuation.resume(handle_split_dwarf(self.package.as_ref(), stash, load));
         }
     }
+
+    fn find_unit(
+        &'_ self,
+        stash: &'data Stash,
+        probe: u64,
+    ) -> Option<gimli::UnitRef<'_, EndianSlice<'data, Endian>>> {
+        use addr2line::{LookupContinuation, LookupResult};
+
+        let mut l = self.dwarf.find_dwarf_and_unit(probe);
+        loop {
+            let (load, continuation) = match l {
+                LookupResult::Output(output) => break output,
+                LookupResult::Load { load, continuation } => (load, continuation),
+            };
+
+            l = continuation.resume(handle_split_dwarf(self.package.as_ref(), stash, load));
+        }
+    }
+}
+
+/// A reference to the debugging information entry of a frame's function, used
+/// to lazily look up its names when asked for.
+#[derive(Clone, Copy)]
+pub struct FrameDie<'a> {
+    cx: &'a Context<'a>,
+    stash: &'a Stash,
+    probe: u64,
+    exact: bool,
+    offset: gimli::UnitOffset,
+}
+
+impl<'a> FrameDie<'a> {
+    fn new(
+        cx: &'a Context<'a>,
+        stash: &'a Stash,
+        probe: u64,
+        offset: Option<gimli::UnitOffset>,
+    ) -> Option<FrameDie<'a>> {
+        Some(FrameDie {
+            cx,
+            stash,
+            probe,
+            exact: false,
+            offset: offset?,
+        })
+    }
+
+    /// Returns the first of the `names` attributes on this entry, following
+    /// `DW_AT_abstract_origin` and `DW_AT_specification` to find it since
+    /// inlined and out-of-line instances of a function usually only point at
+    /// the declaration which holds the names.
+    fn attr_string(&self, names: &[gimli::DwAt]) -> Option<&'a [u8]> {
+        let unit = self.cx.find_unit(self.stash, self.probe)?;
+        let mut offset = self.offset;
+        // Same recursion limit as `addr2line` uses for these chains.
+        for _ in 0..16 {
+            let entry = unit.unit.entry(offset).ok()?;
+            for name in names {
+                if let Some(value) = entry.attr_value(*name).ok()? {
+                    return Some(unit.attr_string(value).ok()?.slice());
+                }
+            }
+            let next = match entry.attr_value(gimli::DW_AT_abstract_origin).ok()? {
+                Some(value) => Some(value),
+                None => entry.attr_value(gimli::DW_AT_specification).ok()?,
+            };
+            match next {
+                Some(gimli::AttributeValue::UnitRef(next)) => offset = next,
+                // References into other units aren't followed.
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Returns whether this function is an inlined instance or, failing that,
+    /// whether its compilation unit says it was compiled with optimizations.
+    fn is_optimized(&self) -> Option<bool> {
+        let unit = self.cx.find_unit(self.stash, self.probe)?;
+        if unit.unit.entry(self.offset).ok()?.tag() == gimli::DW_TAG_inlined_subroutine {
+            return Some(true);
+        }
+        let mut entries = unit.unit.entries();
+        let (_, root) = entries.next_dfs().ok()??;
+        if let Some(gimli::AttributeValue::Flag(true)) =
+            root.attr_value(gimli::DW_AT_APPLE_optimized).ok()?
+        {
+            return Some(true);
+        }
+        let producer = unit.attr_string(root.attr_value(gimli::DW_AT_producer).ok()??);
+        producer_optimization(producer.ok()?.slice())
+    }
+
+    fn linkage_name(&self) -> Option<&'a [u8]> {
+        self.attr_string(&[gimli::DW_AT_linkage_name, gimli::DW_AT_MIPS_linkage_name])
+    }
+
+    fn source_name(&self) -> Option<&'a [u8]> {
+        self.attr_string(&[gimli::DW_AT_name])
+    }
+
+    /// Returns the name of the function called from this frame, found through
+    /// the call site entries compilers emit for optimized code.
+    fn call_target(&self) -> Option<&'a [u8]> {
+        // An exact `probe` is the instruction this frame was interrupted at,
+        // so it isn't in the middle of calling anything.
+        if self.exact {
+            return None;
+        }
+        let unit = self.cx.find_unit(self.stash, self.probe)?;
+        let mut tree = unit.entries_tree(Some(self.offset)).ok()?;
+        // Otherwise `probe` has been adjusted to point into the call
+        // instruction, but call sites are recorded by the address that's
+        // returned to.
+        let offset = find_call_site(&unit, tree.root().ok()?, self.probe.wrapping_add(1))?;
+        let target = FrameDie { offset, ..*self };
+        target.attr_string(&[
+            gimli::DW_AT_linkage_name,
+            gimli::DW_AT_MIPS_linkage_name,
+            gimli::DW_AT_name,
+        ])
+    }
+}
+
+/// Returns whether the `-O` flags in a `DW_AT_producer` string, such as
+/// `GNU C17 11.4.0 -mtune=generic -O2`, enable optimizations.
+fn producer_optimization(producer: &[u8]) -> Option<bool> {
+    // As on the command line, the last flag wins.
+    let level = producer
+        .split(|b| b.is_ascii_whitespace())
+        .filter_map(|flag| flag.strip_prefix(b"-O"))
+        .next_back()?;
+    Some(level != b"0")
+}
+
+/// Searches the children of a function's entry for the call site returning to
+/// `return_pc`, and returns the entry of the function it calls.
+fn find_call_site<'data>(
+    unit: &gimli::UnitRef<'_, EndianSlice<'data, Endian>>,
+    node: gimli::EntriesTreeNode<'_, '_, '_, EndianSlice<'data, Endian>>,
+    return_pc: u64,
+) -> Option<gimli::UnitOffset> {
+    let mut children = node.children();
+    while let Ok(Some(child)) = children.next() {
+        let entry = child.entry();
+        match entry.tag() {
+            // DWARF 5 call sites, and the GNU extension they were based on
+            // which uses the attributes of an inlined subroutine instead.
+            gimli::DW_TAG_call_site | gimli::DW_TAG_GNU_call_site => {
+                let pc = match entry.attr_value(gimli::DW_AT_call_return_pc).ok()? {
+                    Some(pc) => pc,
+                    None => entry.attr_value(gimli::DW_AT_low_pc).ok()??,
+                };
+                if unit.attr_address(pc).ok()? != Some(return_pc) {
+                    continue;
+                }
+                let origin = match entry.attr_value(gimli::DW_AT_call_origin).ok()? {
+                    Some(origin) => origin,
+                    None => entry.attr_value(gimli::DW_AT_abstract_origin).ok()??,
+                };
+                return match origin {
+                    gimli::AttributeValue::UnitRef(offset) => Some(offset),
+                    _ => None,
+                };
+            }
+            // Calls made from inlined functions belong to the inlined frame.
+            gimli::DW_TAG_inlined_subroutine => {}
+            _ => {
+                if let Some(offset) = find_call_site(unit, child, return_pc) {
+                    return Some(offset);
+                }
+            }
+        }
+    }
+    None
 }
 
 fn mmap(path: &Path) -> Option<Mmap> {
@@ -233,8 +481,6 @@ cfg_if::cfg_if! {
     ))] {
         mod libs_dl_iterate_phdr;
         use libs_dl_iterate_phdr::native_libraries;
-        #[path = "gimli/parse_running_mmaps_unix.rs"]
-        mod parse_running_mmaps;
     } else if #[cfg(target_env = "libnx")] {
         mod libs_libnx;
         use libs_libnx::native_libraries;
@@ -252,21 +498,220 @@ cfg_if::cfg_if! {
     }
 }
 
-#[derive(Default)]
+cfg_if::cfg_if! {
+    if #[cfg(all(
+        feature = "std",
+        not(any(windows, target_vendor = "apple", target_os = "aix")),
+    ))] {
+        mod libs_core_file;
+        pub use libs_core_file::parse_maps_text;
+        use libs_core_file::core_file_libraries;
+    } else if #[cfg(feature = "std")] {
+        // Only core dumps in the ELF format are supported so far.
+        fn core_file_libraries(
+            _files: &[super::MappedFile],
+            _open: &dyn Fn(&Path) -> Option<FileData>,
+        ) -> Vec<Library> {
+            Vec::new()
+        }
+
+        impl Mapping {
+            fn new_opened(_data: FileData) -> Option<Mapping> {
+                None
+            }
+        }
+
+        pub fn parse_maps_text(_text: &str) -> Vec<super::MappedFile> {
+            Vec::new()
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(any(target_os = "linux", target_os = "android"))] {
+        mod gdb_jit;
+        use gdb_jit::JitCache;
+        #[cfg(feature = "std")]
+        pub use gdb_jit::enable_gdb_jit;
+    } else {
+        /// Code registered through the GDB JIT interface is only looked up
+        /// on Linux and Android.
+        #[cfg(feature = "std")]
+        pub unsafe fn enable_gdb_jit() {}
+
+        #[derive(Default)]
+        struct JitCache;
+
+        impl JitCache {
+            fn resolve(
+                &mut self,
+                _addr: *mut c_void,
+                _exact: bool,
+                _cb: &mut dyn FnMut(&super::Symbol),
+            ) {
+            }
+
+            fn clear(&mut self) {}
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(
+        feature = "std",
+        not(any(windows, target_vendor = "apple", target_os = "aix")),
+    ))] {
+        use self::elf::NameTable;
+    } else if #[cfg(feature = "std")] {
+        /// Symbol tables are only read on their own for ELF files, elsewhere
+        /// `resolve_name_only` maps the whole file like `resolve` does.
+        struct NameTable;
+
+        impl NameTable {
+            fn new(_path: &Path) -> Option<NameTable> {
+                None
+            }
+
+            fn search(&self, _addr: u64) -> Option<&[u8]> {
+                None
+            }
+        }
+    } else {
+        struct NameTable;
+    }
+}
+
+// `/proc/self/maps` is read when finding libraries with `dl_iterate_phdr`, and
+// the maps of other processes are parsed for `CoreSymbolizer`. Not every part
+// of the parser is used by both.
+#[cfg(any(
+    all(
+        any(
+            target_os = "linux",
+            target_os = "fuchsia",
+            target_os = "freebsd",
+            target_os = "hurd",
+            target_os = "openbsd",
+            target_os = "netbsd",
+            target_os = "nto",
+            target_os = "android",
+        ),
+        not(target_env = "uclibc"),
+    ),
+    all(
+        feature = "std",
+        not(any(windows, target_vendor = "apple", target_os = "aix")),
+    ),
+))]
+#[path = "gimli/parse_running_mmaps_unix.rs"]
+mod parse_running_mmaps;
+
+/// Returns the path of the file mapped at `addr` and the address its start
+/// would be mapped at if it was mapped in one piece, according to
+/// `/proc/self/maps`.
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(target_env = "uclibc")
+))]
+fn mapped_file_at(addr: usize) -> Option<(OsString, usize)> {
+    let maps = parse_running_mmaps::parse_maps().ok()?;
+    let entry = maps.into_iter().find(|e| e.ip_matches(addr))?;
+    let file_start = entry.address().0.wrapping_sub(entry.offset() as usize);
+    Some((entry.pathname().clone(), file_start))
+}
+
+#[cfg(not(all(
+    any(target_os = "linux", target_os = "android"),
+    not(target_env = "uclibc")
+)))]
+fn mapped_file_at(_addr: usize) -> Option<(OsString, usize)> {
+    None
+}
+
+#[cfg(all(
+    feature = "std",
+    any(target_os = "linux", target_os = "android"),
+    not(target_env = "uclibc")
+))]
+pub fn special_regions() -> Vec<(core::ops::Range<usize>, &'static str)> {
+    let Ok(maps) = parse_running_mmaps::parse_maps() else {
+        return Vec::new();
+    };
+    maps.iter()
+        .filter_map(|entry| {
+            let (start, end) = entry.address();
+            Some((start..end, entry.special_label()?))
+        })
+        .collect()
+}
+
+#[cfg(all(
+    feature = "std",
+    not(all(
+        any(target_os = "linux", target_os = "android"),
+        not(target_env = "uclibc")
+    ))
+))]
+pub fn special_regions() -> Vec<(core::ops::Range<usize>, &'static str)> {
+    Vec::new()
+}
+
+/// Global symbolication state.
+///
+/// Note that everything in here is allocated through `alloc` (or mmap'd) so
+/// that a `#[global_allocator]` accounts for all of it, please keep it that way
+/// and avoid reaching for `libc::malloc` or similar in this backend.
 struct Cache {
     /// All known shared libraries that have been loaded.
     libraries: Vec<Library>,
 
     /// Mappings cache where we retain parsed dwarf information.
     ///
-    /// This list has a fixed capacity for its entire lifetime which never
-    /// increases. The `usize` element of each pair is an index into `libraries`
-    /// above where `usize::max_value()` represents the current executable. The
-    /// `Mapping` is corresponding parsed dwarf information.
+    /// The capacity of this list is `MAPPINGS_CACHE_SIZE`, and only changes
+    /// through `set_mapping_cache_size`. The `usize` element of each pair is
+    /// an index into `libraries` above where `usize::max_value()` represents
+    /// the current executable. The `Mapping` is corresponding parsed dwarf
+    /// information.
     ///
     /// Note that this is basically an LRU cache and we'll be shifting things
     /// around in here as we symbolize addresses.
-    mappings: Lru<(usize, Mapping), MAPPINGS_CACHE_SIZE>,
+    mappings: Lru<(usize, Mapping)>,
+
+    /// Addresses which weren't in any library even after rescanning the loaded
+    /// libraries for them.
+    ///
+    /// Libraries loaded after this cache was created are picked up by
+    /// rescanning when an address can't be found in any library, but addresses
+    /// which don't belong to a library at all (such as JIT-compiled code) would
+    /// then trigger a rescan every time. Remembering them avoids that, until
+    /// a rescan finds a newly loaded library.
+    unmapped: Lru<usize>,
+
+    /// Objects registered by JIT compilers, for addresses outside of any
+    /// library. Only used for the current process.
+    jit: JitCache,
+
+    /// Symbol tables read for `resolve_name_only`, keyed by index into
+    /// `libraries` like `mappings`.
+    name_tables: Lru<(usize, NameTable)>,
+
+    /// Reads the files of `libraries` instead of mapping them, for the
+    /// libraries of a `CoreSymbolizer` which were given a file opener.
+    #[cfg(feature = "std")]
+    opener: Option<FileOpener>,
+
+    /// How lookups through this cache fared, see `cache_stats`.
+    stats: Stats,
+}
+
+/// How `Cache::mapping_for_lib` fared, see `cache_stats`, and whether it came
+/// across a replaced library, see `library_replaced`.
+#[derive(Copy, Clone, Default)]
+struct Stats {
+    hits: usize,
+    misses: usize,
+    evictions: usize,
+    library_replaced: bool,
 }
 
 struct Library {
@@ -293,6 +738,15 @@ struct Library {
     /// to use the `libbar.so` library. In this case, only `libbar.so` is
     /// mmapped, not the whole `libfoo.a`.
     member_name: OsString,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    /// The inode of the file which was mapped, according to
+    /// `/proc/self/maps`, if known.
+    ///
+    /// Deploys often install a new build of a library or executable in place
+    /// of the one a running process has mapped. Its debug information would
+    /// then describe different code, so the file found at `name` is only used
+    /// if it's still the one which was mapped.
+    inode: Option<u64>,
     /// Segments of this library loaded into memory, and where they're loaded.
     segments: Vec<LibrarySegment>,
     /// The "bias" of this library, typically where it's loaded into memory.
@@ -324,6 +778,29 @@ fn create_mapping(lib: &Library) -> Option<Mapping> {
     }
 }
 
+/// Returns whether the file at the path of `lib` has been replaced since it
+/// was mapped, so that it's no use for symbolicating the mapped code.
+///
+/// Only the inode is compared, the device numbers listed in the maps of a
+/// process differ from the ones `stat` reports on some filesystems, such as
+/// btrfs and overlayfs. A file which was overwritten in place keeps its inode,
+/// but then the code which was mapped has changed too.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn is_replaced(lib: &Library) -> bool {
+    use mystd::os::unix::fs::MetadataExt;
+
+    let Some(inode) = lib.inode else {
+        return false;
+    };
+    // Files which can't be found at all are left to fail to open.
+    mystd::fs::metadata(&lib.name).is_ok_and(|metadata| metadata.ino() != inode)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn is_replaced(_lib: &Library) -> bool {
+    false
+}
+
 /// Try to extract the archive path from an "embedded" library path
 /// (e.g. `/path/to/my.apk` from `/path/to/my.apk!/mylib.so`).
 ///
@@ -341,151 +818,735 @@ fn extract_zip_path_android(path: &mystd::ffi::OsStr) -> Option<&mystd::ffi::OsS
 
 // unsafe because this is required to be externally synchronized
 pub unsafe fn clear_symbol_cache() {
-    Cache::with_global(|cache| cache.mappings.clear());
+    Cache::with_global(|cache| {
+        cache.mappings.clear();
+        cache.unmapped.clear();
+        cache.jit.clear();
+        cache.name_tables.clear();
+        cache.stats = Stats::default();
+    });
+    DEBUGINFO_MISMATCH.store(false, Ordering::Relaxed);
+}
+
+fn mappings_cache_size() -> usize {
+    MAPPINGS_CACHE_SIZE.load(Ordering::Relaxed)
 }
 
+fn unmapped_cache_size() -> usize {
+    mappings_cache_size() * UNMAPPED_PER_MAPPING
+}
+
+// unsafe because this is required to be externally synchronized
+pub unsafe fn set_mapping_cache_size(size: usize) {
+    // Symbolicating needs the mapping it's working with to stay cached.
+    let size = size.max(1);
+    MAPPINGS_CACHE_SIZE.store(size, Ordering::Relaxed);
+    Cache::with_global(|cache| {
+        cache.stats.evictions += cache.mappings.len().saturating_sub(size);
+        cache.mappings.set_capacity(size);
+        cache.name_tables.set_capacity(size);
+        cache.unmapped.set_capacity(unmapped_cache_size());
+    });
+}
+
+// unsafe because this is required to be externally synchronized
+pub unsafe fn reset() {
+    DEBUGINFO_MISMATCH.store(false, Ordering::Relaxed);
+    // Start over with a fresh list of libraries too, rather than waiting for
+    // an address outside of the known ones to rescan them.
+    Cache::reset_global();
+}
+
+/// Set once a separate debug info file was found for a library but rejected
+/// because it belongs to a different build.
+static DEBUGINFO_MISMATCH: AtomicBool = AtomicBool::new(false);
+
+// Separate debug info files are only looked up for ELF files.
+#[cfg(not(any(windows, target_vendor = "apple", target_os = "aix")))]
+fn record_debuginfo_mismatch() {
+    DEBUGINFO_MISMATCH.store(true, Ordering::Relaxed);
+}
+
+pub fn debuginfo_mismatch() -> bool {
+    DEBUGINFO_MISMATCH.load(Ordering::Relaxed)
+}
+
+// unsafe because this is required to be externally synchronized
+pub unsafe fn library_replaced() -> bool {
+    Cache::global_stats().library_replaced
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn mapped_ranges() -> Option<Vec<core::ops::Range<usize>>> {
+    let mut ranges = None;
+    Cache::with_global(|cache| {
+        cache.rescan_libraries();
+        // Libraries can't be found on some platforms at all.
+        if !cache.libraries.is_empty() {
+            let segments = cache.libraries.iter().flat_map(|lib| {
+                lib.segments.iter().map(move |s| {
+                    let start = s.stated_virtual_memory_address.wrapping_add(lib.bias);
+                    start..start.wrapping_add(s.len)
+                })
+            });
+            ranges = Some(segments.collect());
+        }
+    });
+    ranges
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn loaded_libraries() -> Vec<super::Library> {
+    native_libraries()
+        .into_iter()
+        .map(|lib| {
+            let segments = lib
+                .segments
+                .iter()
+                .map(|s| super::LibrarySegment::new(s.stated_virtual_memory_address, s.len))
+                .collect();
+            super::Library::new(lib.name.into(), segments, lib.bias)
+        })
+        .collect()
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn cache_stats() -> super::CacheStats {
+    let stats = Cache::global_stats();
+    super::CacheStats {
+        mapping_hits: stats.hits as u64,
+        mapping_misses: stats.misses as u64,
+        evictions: stats.evictions as u64,
+    }
+}
+
+// A very small, very simple LRU cache for debug info mappings.
+//
+// The hit rate should be very high, since the typical stack doesn't cross
+// between many shared libraries.
+//
+// The `addr2line::Context` structures are pretty expensive to create. Its
+// cost is expected to be amortized by subsequent `locate` queries, which
+// leverage the structures built when constructing `addr2line::Context`s to
+// get nice speedups. If we didn't have this cache, that amortization would
+// never happen, and symbolicating backtraces would be ssssllllooooowwww.
+static mut MAPPINGS_CACHE: Option<Cache> = None;
+
 impl Cache {
     fn new() -> Cache {
+        Cache::with_libraries(native_libraries())
+    }
+
+    fn with_libraries(libraries: Vec<Library>) -> Cache {
         Cache {
-            mappings: Lru::default(),
-            libraries: native_libraries(),
+            mappings: Lru::new(mappings_cache_size()),
+            libraries,
+            unmapped: Lru::new(unmapped_cache_size()),
+            jit: Default::default(),
+            name_tables: Lru::new(mappings_cache_size()),
+            #[cfg(feature = "std")]
+            opener: None,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Refreshes the list of loaded libraries if `addr` isn't in any of them,
+    /// in case it's in a library which has been loaded since.
+    fn rescan_libraries_for(&mut self, addr: *const u8) {
+        if self.avma_to_svma(addr).is_some() || self.unmapped.iter().any(|a| *a == addr as usize) {
+            return;
+        }
+
+        self.rescan_libraries();
+        if self.avma_to_svma(addr).is_none() {
+            self.unmapped.push_front(addr as usize);
+        }
+    }
+
+    /// Refreshes the list of loaded libraries, keeping what's cached for the
+    /// libraries which are still loaded.
+    fn rescan_libraries(&mut self) {
+        let old = mem::replace(&mut self.libraries, native_libraries());
+        // Cached mappings are keyed by index into `libraries`, so point them at
+        // the new indices of their libraries. Should any of them have been
+        // unloaded it's simplest to start from scratch.
+        let libraries = &self.libraries;
+        let remapped = self.mappings.iter_mut().all(|(lib, _)| {
+            let old = &old[*lib];
+            match libraries
+                .iter()
+                .position(|new| new.name == old.name && new.bias == old.bias)
+            {
+                Some(i) => {
+                    *lib = i;
+                    true
+                }
+                None => false,
+            }
+        });
+        if !remapped {
+            self.mappings.clear();
+        }
+        // These are cheap enough to read again.
+        self.name_tables.clear();
+
+        // Addresses which weren't in any library can only be in one now if a
+        // library has been loaded since.
+        let loaded = libraries.iter().any(|new| {
+            !old.iter()
+                .any(|old| new.name == old.name && new.bias == old.bias)
+        });
+        if loaded {
+            self.unmapped.clear();
         }
     }
 
+    /// Finds the loaded library whose file is at `path`.
+    #[cfg(feature = "std")]
+    fn library_by_path(&self, path: &Path) -> Option<usize> {
+        let name_is = |lib: &Library, path: &Path| Path::new(&lib.name) == path;
+        if let Some(i) = self.libraries.iter().position(|lib| name_is(lib, path)) {
+            return Some(i);
+        }
+        // The path may be spelled differently from how the library was
+        // loaded, for example through a symlink.
+        let path = mystd::fs::canonicalize(path).ok()?;
+        self.libraries
+            .iter()
+            .position(|lib| mystd::fs::canonicalize(&lib.name).is_ok_and(|name| name == path))
+    }
+
     // unsafe because this is required to be externally synchronized
     unsafe fn with_global(f: impl FnOnce(&mut Self)) {
-        // A very small, very simple LRU cache for debug info mappings.
-        //
-        // The hit rate should be very high, since the typical stack doesn't cross
-        // between many shared libraries.
-        //
-        // The `addr2line::Context` structures are pretty expensive to create. Its
-        // cost is expected to be amortized by subsequent `locate` queries, which
-        // leverage the structures built when constructing `addr2line::Context`s to
-        // get nice speedups. If we didn't have this cache, that amortization would
-        // never happen, and symbolicating backtraces would be ssssllllooooowwww.
-        static mut MAPPINGS_CACHE: Option<Cache> = None;
-
         // FIXME: https://github.com/rust-lang/backtrace-rs/issues/678
         #[allow(static_mut_refs)]
         f(MAPPINGS_CACHE.get_or_insert_with(Cache::new))
     }
 
+    // unsafe because this is required to be externally synchronized
+    unsafe fn global_stats() -> Stats {
+        // Only the global cache reports to `cache_stats`, without creating it
+        // for that.
+        #[allow(static_mut_refs)]
+        MAPPINGS_CACHE
+            .as_ref()
+            .map_or_else(Stats::default, |cache| cache.stats)
+    }
+
+    // unsafe because this is required to be externally synchronized
+    unsafe fn reset_global() {
+        // Replaced rather than cleared, so the libraries are only scanned once
+        // even if there's no cache yet.
+        MAPPINGS_CACHE = Some(Cache::new());
+    }
+
     fn avma_to_svma(&self, addr: *const u8) -> Option<(usize, *const u8)> {
-        self.libraries
+        let address = addr as usize;
+        // Each library with a segment containing `addr` (handling
+        // relocation), along with the size of that segment.
+        let mut candidates = self.libraries.iter().enumerate().filter_map(|(i, lib)| {
+            // Note that we're using `wrapping_add` here to avoid overflow
+            // checks. It's been seen in the wild that the SVMA + bias
+            // computation overflows. It seems a bit odd that would happen
+            // but there's not a huge amount we can do about it other than
+            // probably just ignore those segments since they're likely
+            // pointing off into space. This originally came up in
+            // rust-lang/backtrace-rs#329.
+            let segment = lib.segments.iter().find(|s| {
+                let svma = s.stated_virtual_memory_address;
+                let start = svma.wrapping_add(lib.bias);
+                let end = start.wrapping_add(s.len);
+                start <= address && address < end
+            })?;
+            Some((i, segment.len))
+        });
+        let first = candidates.next()?;
+        let i = match candidates.next() {
+            None => first.0,
+            Some(second) => {
+                let candidates = [first, second].into_iter().chain(candidates).collect();
+                self.pick_overlapping(address, candidates)
+            }
+        };
+
+        // Now that we know which library contains `addr`, we can offset with
+        // the bias to find the stated virtual memory address.
+        let svma = address.wrapping_sub(self.libraries[i].bias);
+        Some((i, svma as *const u8))
+    }
+
+    /// Picks the library actually mapped at `addr` out of several libraries
+    /// whose segments contain it, given as pairs of an index into `libraries`
+    /// and the size of the segment containing `addr`.
+    ///
+    /// Libraries only overlap when one was mapped over another, such as with
+    /// `MAP_FIXED` or after a library was partially unmapped, so this is rare
+    /// and free to be slow.
+    fn pick_overlapping(&self, addr: usize, candidates: Vec<(usize, usize)>) -> usize {
+        // The maps of the process know which file is mapped at `addr`. If
+        // that file is loaded more than once, the load whose bias is closest
+        // to where the start of the file would be mapped is the one.
+        if let Some((path, file_start)) = mapped_file_at(addr) {
+            let same_file = candidates
+                .iter()
+                .filter(|(i, _)| self.libraries[*i].name == path)
+                .min_by_key(|(i, _)| self.libraries[*i].bias.abs_diff(file_start));
+            if let Some(&(i, _)) = same_file {
+                return i;
+            }
+        }
+
+        // Otherwise the smallest segment is the most specific mapping, and
+        // among equally small ones the library loaded last, which is listed
+        // last, is the one most likely mapped over the others.
+        candidates
             .iter()
-            .enumerate()
-            .filter_map(|(i, lib)| {
-                // First up, test if this `lib` has any segment containing the
-                // `addr` (handling relocation). If this check passes then we
-                // can continue below and actually translate the address.
-                //
-                // Note that we're using `wrapping_add` here to avoid overflow
-                // checks. It's been seen in the wild that the SVMA + bias
-                // computation overflows. It seems a bit odd that would happen
-                // but there's not a huge amount we can do about it other than
-                // probably just ignore those segments since they're likely
-                // pointing off into space. This originally came up in
-                // rust-lang/backtrace-rs#329.
-                if !lib.segments.iter().any(|s| {
-                    let svma = s.stated_virtual_memory_address;
-                    let start = svma.wrapping_add(lib.bias);
-                    let end = start.wrapping_add(s.len);
-                    let address = addr as usize;
-                    start <= address && address < end
-                }) {
-                    return None;
-                }
+            .rev()
+            .min_by_key(|(_, len)| *len)
+            .map_or(candidates[0].0, |&(i, _)| i)
+    }
 
-                // Now that we know `lib` contains `addr`, we can offset with
-                // the bias to find the stated virtual memory address.
-                let svma = (addr as usize).wrapping_sub(lib.bias);
-                Some((i, svma as *const u8))
-            })
-            .next()
+    /// Creates the mapping of a library, reading its file with `opener` if
+    /// there is one.
+    fn create_mapping(&mut self, lib: usize) -> Option<Mapping> {
+        let lib = &self.libraries[lib];
+        #[cfg(feature = "std")]
+        if let Some(opener) = &self.opener {
+            return Mapping::new_opened(opener(Path::new(&lib.name))?);
+        }
+        if is_replaced(lib) {
+            self.stats.library_replaced = true;
+            return None;
+        }
+        create_mapping(lib)
     }
 
-    fn mapping_for_lib<'a>(&'a mut self, lib: usize) -> Option<(&'a mut Context<'a>, &'a Stash)> {
+    /// Returns the parsed debug information of a library, along with the path
+    /// of the library.
+    fn mapping_for_lib<'a>(
+        &'a mut self,
+        lib: usize,
+    ) -> Option<(&'a mut Context<'a>, &'a Stash, &'a Path)> {
         let cache_idx = self.mappings.iter().position(|(lib_id, _)| *lib_id == lib);
 
         let cache_entry = if let Some(idx) = cache_idx {
+            self.stats.hits += 1;
             self.mappings.move_to_front(idx)
         } else {
+            self.stats.misses += 1;
             // When the mapping is not in the cache, create a new mapping and insert it,
             // which will also evict the oldest entry.
-            create_mapping(&self.libraries[lib])
-                .and_then(|mapping| self.mappings.push_front((lib, mapping)))
+            self.create_mapping(lib).and_then(|mapping| {
+                if self.mappings.len() == self.mappings.capacity() {
+                    self.stats.evictions += 1;
+                }
+                self.mappings.push_front((lib, mapping))
+            })
         };
 
         let (_, mapping) = cache_entry?;
         let cx: &'a mut Context<'static> = &mut mapping.cx;
         let stash: &'a Stash = &mapping.stash;
+        let path = Path::new(&self.libraries[lib].name);
         // don't leak the `'static` lifetime, make sure it's scoped to just
         // ourselves
         Some((
             unsafe { mem::transmute::<&'a mut Context<'static>, &'a mut Context<'a>>(cx) },
             stash,
+            path,
         ))
     }
 }
 
+#[cfg(feature = "std")]
+impl Cache {
+    /// Reads the symbol table of a library, or returns `None` if it can't be
+    /// read on its own. The path of the library is returned along with it.
+    fn name_table_for_lib(&mut self, lib: usize) -> Option<(&NameTable, &Path)> {
+        let idx = self.name_tables.iter().position(|(l, _)| *l == lib);
+        let (_, table) = match idx {
+            Some(idx) => self.name_tables.move_to_front(idx),
+            None => {
+                if is_replaced(&self.libraries[lib]) {
+                    self.stats.library_replaced = true;
+                    return None;
+                }
+                let table = create_name_table(&self.libraries[lib])?;
+                self.name_tables.push_front((lib, table))
+            }
+        }?;
+        Some((&*table, Path::new(&self.libraries[lib].name)))
+    }
+}
+
+#[cfg(feature = "std")]
+fn create_name_table(lib: &Library) -> Option<NameTable> {
+    // Libraries inside an APK are left to `resolve`.
+    #[cfg(target_os = "android")]
+    if lib.zip_offset.is_some() {
+        return None;
+    }
+    NameTable::new(lib.name.as_ref())
+}
+
+/// Returns whether `what.address_or_ip()` is the instruction that was
+/// executing rather than one adjusted back from a return address.
+fn ip_is_exact(what: &ResolveWhat<'_>) -> bool {
+    matches!(what, ResolveWhat::Frame(f) if f.inner.ip_is_exact())
+}
+
 pub unsafe fn resolve(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
     let addr = what.address_or_ip();
-    let mut call = |sym: Symbol<'_>| {
-        // Extend the lifetime of `sym` to `'static` since we are unfortunately
-        // required to here, but it's only ever going out as a reference so no
-        // reference to it should be persisted beyond this frame anyway.
-        let sym = mem::transmute::<Symbol<'_>, Symbol<'static>>(sym);
-        (cb)(&super::Symbol { inner: sym });
-    };
+    let exact = ip_is_exact(&what);
+    Cache::with_global(|cache| {
+        cache.rescan_libraries_for(addr.cast_const().cast::<u8>());
+        if cache.avma_to_svma(addr.cast_const().cast::<u8>()).is_some() {
+            cache.resolve(addr, exact, cb)
+        } else {
+            cache.jit.resolve(addr, exact, cb)
+        }
+    });
+}
 
+#[cfg(feature = "std")]
+pub unsafe fn resolve_batch(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &super::Symbol)) {
+    let addrs = addrs
+        .iter()
+        .map(|&addr| ResolveWhat::Address(addr).address_or_ip())
+        .collect::<Vec<_>>();
     Cache::with_global(|cache| {
-        let (lib, addr) = match cache.avma_to_svma(addr.cast_const().cast::<u8>()) {
+        // Rescanning renumbers the libraries, so get that done with for all
+        // addresses before any library is looked up.
+        for &addr in &addrs {
+            cache.rescan_libraries_for(addr.cast_const().cast::<u8>());
+        }
+
+        let mut by_lib = Vec::with_capacity(addrs.len());
+        for (i, &addr) in addrs.iter().enumerate() {
+            match cache.avma_to_svma(addr.cast_const().cast::<u8>()) {
+                Some((lib, svma)) => by_lib.push((lib, svma, i)),
+                None => cache.jit.resolve(addr, false, &mut |symbol| cb(i, symbol)),
+            }
+        }
+        // A stable sort keeps the addresses of each library in order.
+        by_lib.sort_by_key(|&(lib, _, _)| lib);
+
+        for batch in by_lib.chunk_by(|a, b| a.0 == b.0) {
+            let Some((cx, stash, path)) = cache.mapping_for_lib(batch[0].0) else {
+                continue;
+            };
+            for &(_, svma, i) in batch {
+                resolve_svma(cx, stash, Some(path), svma as u64, false, &mut |symbol| {
+                    cb(i, symbol)
+                });
+            }
+        }
+    });
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_candidates(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+    let addr = what.address_or_ip().cast_const().cast::<u8>();
+    Cache::with_global(|cache| {
+        cache.rescan_libraries_for(addr);
+        let Some((lib, svma)) = cache.avma_to_svma(addr) else {
+            return;
+        };
+        let Some((cx, _, path)) = cache.mapping_for_lib(lib) else {
+            return;
+        };
+        cx.object
+            .search_symtab_candidates(svma as u64, &mut |name| {
+                let sym = Symbol::Symtab {
+                    name,
+                    module: Some(path),
+                };
+                // See `resolve_svma` on the lifetime.
+                let sym = unsafe { mem::transmute::<Symbol<'_>, Symbol<'static>>(sym) };
+                cb(&super::Symbol { inner: sym });
+            });
+    });
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_name_only(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+    let addr = what.address_or_ip();
+    let exact = ip_is_exact(&what);
+    Cache::with_global(|cache| {
+        cache.rescan_libraries_for(addr.cast_const().cast::<u8>());
+        let Some((lib, svma)) = cache.avma_to_svma(addr.cast_const().cast::<u8>()) else {
+            return cache.jit.resolve(addr, exact, cb);
+        };
+        let Some((table, path)) = cache.name_table_for_lib(lib) else {
+            return cache.resolve(addr, exact, cb);
+        };
+        if let Some(name) = table.search(svma as u64) {
+            let sym = Symbol::Symtab {
+                name,
+                module: Some(path),
+            };
+            // See `resolve_svma` on the lifetime.
+            let sym = unsafe { mem::transmute::<Symbol<'_>, Symbol<'static>>(sym) };
+            cb(&super::Symbol { inner: sym });
+        }
+    });
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn preload_library(path: &Path) -> bool {
+    let mut has_debug_info = false;
+    Cache::with_global(|cache| {
+        let lib = cache.library_by_path(path).or_else(|| {
+            cache.rescan_libraries();
+            cache.library_by_path(path)
+        });
+        if let Some((cx, _, _)) = lib.and_then(|lib| cache.mapping_for_lib(lib)) {
+            has_debug_info = cx.has_debug_info;
+        }
+    });
+    has_debug_info
+}
+
+// Libraries are found by enumerating them with the OS, which only knows of
+// the ones it loaded itself.
+#[cfg(feature = "std")]
+pub unsafe fn register_module(_base: *mut c_void, _size: usize, _path: Option<&Path>) -> bool {
+    false
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_location(
+    what: ResolveWhat<'_>,
+) -> Option<(mystd::path::PathBuf, u32, Option<u32>)> {
+    let addr = what.address_or_ip();
+    let mut location = None;
+    Cache::with_global(|cache| {
+        cache.rescan_libraries_for(addr.cast_const().cast::<u8>());
+        location = cache.resolve_location(addr);
+    });
+    location
+}
+
+impl Cache {
+    /// Looks up only the line number information for `addr`, see
+    /// `resolve_location`.
+    #[cfg(feature = "std")]
+    fn resolve_location(
+        &mut self,
+        addr: *mut c_void,
+    ) -> Option<(mystd::path::PathBuf, u32, Option<u32>)> {
+        let (lib, addr) = self.avma_to_svma(addr.cast_const().cast::<u8>())?;
+        let (cx, _, _) = self.mapping_for_lib(lib)?;
+        let addr = addr as u64;
+        let location = match cx.dwarf.find_location(addr) {
+            Ok(Some(location)) => location,
+            _ => {
+                let (object_cx, object_addr) = cx.object.search_object_map(addr)?;
+                object_cx.dwarf.find_location(object_addr).ok()??
+            }
+        };
+        let column = location.column.filter(|&column| column != 0);
+        Some((location.file?.into(), location.line?, column))
+    }
+
+    /// Resolves `addr`, which is `exact` if it wasn't adjusted back from a
+    /// return address, see `ResolveWhat::address_or_ip`.
+    fn resolve(&mut self, addr: *mut c_void, exact: bool, cb: &mut dyn FnMut(&super::Symbol)) {
+        let (lib, addr) = match self.avma_to_svma(addr.cast_const().cast::<u8>()) {
             Some(pair) => pair,
             None => return,
         };
 
         // Finally, get a cached mapping or create a new mapping for this file, and
         // evaluate the DWARF info to find the file/line/name for this address.
-        let (cx, stash) = match cache.mapping_for_lib(lib) {
-            Some((cx, stash)) => (cx, stash),
+        let (cx, stash, path) = match self.mapping_for_lib(lib) {
+            Some(mapping) => mapping,
             None => return,
         };
-        let mut any_frames = false;
-        if let Ok(mut frames) = cx.find_frames(stash, addr as u64) {
-            while let Ok(Some(frame)) = frames.next() {
-                any_frames = true;
-                let name = match frame.function {
-                    Some(f) => Some(f.name.slice()),
-                    None => cx.object.search_symtab(addr as u64),
-                };
-                call(Symbol::Frame {
-                    addr: addr as *mut c_void,
-                    location: frame.location,
-                    name,
-                });
-            }
+        resolve_svma(cx, stash, Some(path), addr as u64, exact, cb);
+    }
+}
+
+/// Resolves `addr`, a stated virtual memory address within the object `cx` was
+/// created from, which was loaded from `module` if it's a file. `addr` is
+/// `exact` if it wasn't adjusted back from a return address.
+fn resolve_svma<'a>(
+    cx: &'a Context<'a>,
+    stash: &'a Stash,
+    module: Option<&'a Path>,
+    addr: u64,
+    exact: bool,
+    cb: &mut dyn FnMut(&super::Symbol),
+) {
+    let mut call = |mut sym: Symbol<'_>| {
+        // Frames are looked up the same either way, only their call sites
+        // differ.
+        if let Symbol::Frame { die: Some(die), .. } = &mut sym {
+            die.exact = exact;
         }
-        if !any_frames {
-            if let Some((object_cx, object_addr)) = cx.object.search_object_map(addr as u64) {
-                if let Ok(mut frames) = object_cx.find_frames(stash, object_addr) {
-                    while let Ok(Some(frame)) = frames.next() {
-                        any_frames = true;
-                        call(Symbol::Frame {
-                            addr: addr as *mut c_void,
-                            location: frame.location,
-                            name: frame.function.map(|f| f.name.slice()),
-                        });
-                    }
-                }
-            }
+        // Extend the lifetime of `sym` to `'static` since we are unfortunately
+        // required to here, but it's only ever going out as a reference so no
+        // reference to it should be persisted beyond this frame anyway.
+        let sym = unsafe { mem::transmute::<Symbol<'_>, Symbol<'static>>(sym) };
+        (cb)(&super::Symbol { inner: sym });
+    };
+
+    let mut any_frames = emit_frames(cx, stash, addr, cx, addr, module, &mut call);
+    if !any_frames {
+        if let Some((object_cx, object_addr)) = cx.object.search_object_map(addr) {
+            any_frames = emit_frames(object_cx, stash, object_addr, cx, addr, module, &mut call);
         }
-        if !any_frames {
-            if let Some(name) = cx.object.search_symtab(addr as u64) {
-                call(Symbol::Symtab { name });
-            }
+    }
+    if !any_frames {
+        if let Some(name) = cx.object.search_symtab(addr) {
+            call(Symbol::Symtab { name, module });
         }
-    });
+    }
+}
+
+/// Passes a `Symbol::Frame` for each of the frames `cx` has for `probe` to
+/// `call`, returning whether there were any.
+///
+/// Debug information may lack the names of functions, for example when
+/// everything but `.debug_line` has been stripped, in which case the name is
+/// looked up for `addr` in the symbol table of `symtab_cx` instead. That only
+/// gives the name of the outermost function though, so inlined frames are
+/// left without a name.
+fn emit_frames<'a>(
+    cx: &'a Context<'a>,
+    stash: &'a Stash,
+    probe: u64,
+    symtab_cx: &'a Context<'a>,
+    addr: u64,
+    module: Option<&'a Path>,
+    call: &mut dyn FnMut(Symbol<'a>),
+) -> bool {
+    let Ok(mut frames) = cx.find_frames(stash, probe) else {
+        return false;
+    };
+    let mut next = frames.next().ok().flatten();
+    let any_frames = next.is_some();
+    while let Some(frame) = next {
+        // Frames are yielded innermost first, so the outermost is the last.
+        next = frames.next().ok().flatten();
+        let name = match frame.function {
+            Some(f) => Some(f.name.slice()),
+            None if next.is_none() => symtab_cx.object.search_symtab(addr),
+            None => None,
+        };
+        call(Symbol::Frame {
+            addr: addr as *mut c_void,
+            location: frame.location,
+            name,
+            die: FrameDie::new(cx, stash, probe, frame.dw_die_offset),
+            module,
+        });
+    }
+    any_frames
+}
+
+/// Resolves `svma` within the object file `member` of the archive at `path`.
+#[cfg(feature = "std")]
+pub fn resolve_in_archive(
+    path: &Path,
+    member: &[u8],
+    svma: u64,
+    cb: &mut dyn FnMut(&super::Symbol),
+) {
+    if let Some(mapping) = Mapping::new_archive_member(path, member) {
+        let cx: &Context<'static> = &mapping.cx;
+        // don't leak the `'static` lifetime, make sure it's scoped to just
+        // ourselves
+        let cx = unsafe { mem::transmute::<&Context<'static>, &Context<'_>>(cx) };
+        resolve_svma(cx, &mapping.stash, Some(path), svma, true, cb);
+    }
+}
+
+/// Finds the data of the member named `member` in the archive `data`.
+#[cfg(all(feature = "std", not(any(windows, target_os = "aix"))))]
+fn archive_member<'a>(data: &'a [u8], member: &[u8]) -> Option<&'a [u8]> {
+    let archive = object::read::archive::ArchiveFile::parse(data).ok()?;
+    let member = archive
+        .members()
+        .filter_map(Result::ok)
+        .find(|m| m.name() == member)?;
+    member.data(data).ok()
+}
+
+/// Symbolication state for the libraries of a core dump, kept separate from
+/// the global state for the current process.
+#[cfg(feature = "std")]
+pub struct CoreSymbolizer {
+    cache: Cache,
+}
+
+#[cfg(feature = "std")]
+impl CoreSymbolizer {
+    pub fn new(files: &[super::MappedFile]) -> CoreSymbolizer {
+        let open = |path: &Path| mmap(path).map(FileData::from);
+        CoreSymbolizer {
+            cache: Cache::with_libraries(core_file_libraries(files, &open)),
+        }
+    }
+
+    pub fn with_opener<F, M>(files: &[super::MappedFile], open: F) -> CoreSymbolizer
+    where
+        F: Fn(&Path) -> Option<M> + 'static,
+        M: core::ops::Deref<Target = [u8]> + 'static,
+    {
+        let open: FileOpener = Box::new(move |path| Some(FileData::Opened(Box::new(open(path)?))));
+        let mut cache = Cache::with_libraries(core_file_libraries(files, &open));
+        cache.opener = Some(open);
+        CoreSymbolizer { cache }
+    }
+
+    pub fn resolve(&mut self, addr: u64, cb: &mut dyn FnMut(&super::Symbol)) {
+        if let Ok(addr) = usize::try_from(addr) {
+            self.cache.resolve(addr as *mut c_void, false, cb);
+        }
+    }
+}
+
+/// Symbolication state for the current process owned by its user rather than
+/// kept in a global, see `super::Symbolizer`.
+///
+/// The list of libraries is only read when this is created, as rescanning it
+/// takes the dynamic loader's lock and allocates.
+#[cfg(feature = "std")]
+pub struct Symbolizer {
+    cache: Cache,
+}
+
+// The cache holds raw pointers into the files it has mapped or copied, which
+// it owns and which don't depend on the thread using them, and into the
+// libraries of this process, which are mapped for every thread alike. Nothing
+// is shared with other `Symbolizer`s or the global cache, so the whole of it
+// can move to another thread. Only `CoreSymbolizer` has a file opener, which
+// may not be `Send`.
+#[cfg(feature = "std")]
+unsafe impl Send for Symbolizer {}
+
+#[cfg(feature = "std")]
+impl Symbolizer {
+    pub fn new() -> Symbolizer {
+        Symbolizer {
+            cache: Cache::new(),
+        }
+    }
+
+    pub fn preload(&mut self, addr: *mut c_void) -> bool {
+        match self.cache.avma_to_svma(addr.cast_const().cast::<u8>()) {
+            Some((lib, _)) => self.cache.mapping_for_lib(lib).is_some(),
+            None => false,
+        }
+    }
+
+    pub fn resolve(&mut self, what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+        self.cache
+            .resolve(what.address_or_ip(), ip_is_exact(&what), cb);
+    }
 }
 
 pub enum Symbol<'a> {
@@ -495,10 +1556,15 @@ pub enum Symbol<'a> {
         addr: *mut c_void,
         location: Option<addr2line::Location<'a>>,
         name: Option<&'a [u8]>,
+        die: Option<FrameDie<'a>>,
+        module: Option<&'a Path>,
     },
     /// Couldn't find debug information, but we found it in the symbol table of
     /// the elf executable.
-    Symtab { name: &'a [u8] },
+    Symtab {
+        name: &'a [u8],
+        module: Option<&'a Path>,
+    },
 }
 
 impl Symbol<'_> {
@@ -512,6 +1578,34 @@ impl Symbol<'_> {
         }
     }
 
+    pub fn linkage_name(&self) -> Option<SymbolName<'_>> {
+        match self {
+            Symbol::Frame { die, .. } => Some(SymbolName::new(die.as_ref()?.linkage_name()?)),
+            Symbol::Symtab { name, .. } => Some(SymbolName::new(name)),
+        }
+    }
+
+    pub fn source_name(&self) -> Option<SymbolName<'_>> {
+        match self {
+            Symbol::Frame { die, .. } => Some(SymbolName::new(die.as_ref()?.source_name()?)),
+            Symbol::Symtab { .. } => None,
+        }
+    }
+
+    pub fn call_target(&self) -> Option<SymbolName<'_>> {
+        match self {
+            Symbol::Frame { die, .. } => Some(SymbolName::new(die.as_ref()?.call_target()?)),
+            Symbol::Symtab { .. } => None,
+        }
+    }
+
+    pub fn is_optimized(&self) -> Option<bool> {
+        match self {
+            Symbol::Frame { die, .. } => die.as_ref()?.is_optimized(),
+            Symbol::Symtab { .. } => None,
+        }
+    }
+
     pub fn addr(&self) -> Option<*mut c_void> {
         match self {
             Symbol::Frame { addr, .. } => Some(*addr),
@@ -539,6 +1633,12 @@ impl Symbol<'_> {
         }
     }
 
+    pub fn module(&self) -> Option<&Path> {
+        match self {
+            Symbol::Frame { module, .. } | Symbol::Symtab { module, .. } => *module,
+        }
+    }
+
     pub fn lineno(&self) -> Option<u32> {
         match self {
             Symbol::Frame { location, .. } => location.as_ref()?.line,
@@ -548,8 +1648,249 @@ impl Symbol<'_> {
 
     pub fn colno(&self) -> Option<u32> {
         match self {
-            Symbol::Frame { location, .. } => location.as_ref()?.column,
+            // DWARF uses column 0 for "no column", which addr2line passes on
+            // as `Some(0)`.
+            Symbol::Frame { location, .. } => location.as_ref()?.column.filter(|&c| c != 0),
             Symbol::Symtab { .. } => None,
         }
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    // Inlined frames and the outermost frame of a function often have a name
+    // but only partial (or no) location information. They should still come
+    // through with everything that is known rather than being dropped.
+    #[test]
+    fn named_frame_without_file() {
+        let symbol = super::super::Symbol {
+            inner: Symbol::Frame {
+                addr: 0x1000 as *mut c_void,
+                location: Some(addr2line::Location {
+                    file: None,
+                    line: Some(42),
+                    column: None,
+                }),
+                name: Some(b"inlined_function"),
+                die: None,
+                module: None,
+            },
+        };
+        assert_eq!(symbol.name().unwrap().as_bytes(), b"inlined_function");
+        assert!(symbol.filename().is_none());
+        assert!(symbol.filename_raw().is_none());
+        assert_eq!(symbol.lineno(), Some(42));
+        assert_eq!(symbol.colno(), None);
+
+        let symbol = super::super::Symbol {
+            inner: Symbol::Frame {
+                addr: 0x1000 as *mut c_void,
+                location: None,
+                name: Some(b"outer_function"),
+                die: None,
+                module: None,
+            },
+        };
+        assert_eq!(symbol.name().unwrap().as_bytes(), b"outer_function");
+        assert!(symbol.filename().is_none());
+        assert_eq!(symbol.lineno(), None);
+        assert_eq!(symbol.addr(), Some(0x1000 as *mut c_void));
+    }
+
+    #[test]
+    fn lru_set_capacity() {
+        let mut lru = Lru::new(2);
+        lru.push_front(1);
+        lru.push_front(2);
+        lru.push_front(3);
+        assert_eq!(lru.iter().copied().collect::<Vec<_>>(), [3, 2]);
+
+        lru.set_capacity(4);
+        lru.push_front(4);
+        lru.push_front(5);
+        lru.move_to_front(3);
+        assert_eq!(lru.iter().copied().collect::<Vec<_>>(), [2, 5, 4, 3]);
+
+        lru.set_capacity(1);
+        assert_eq!(lru.iter().copied().collect::<Vec<_>>(), [2]);
+        assert_eq!(lru.capacity(), 1);
+        assert!(lru.move_to_front(1).is_none());
+
+        lru.set_capacity(0);
+        assert!(lru.push_front(6).is_none());
+        assert_eq!(lru.len(), 0);
+    }
+
+    #[test]
+    fn producer_flags() {
+        assert_eq!(
+            producer_optimization(b"GNU C17 11.4.0 -mtune=generic -O2"),
+            Some(true)
+        );
+        assert_eq!(
+            producer_optimization(b"GNU C17 11.4.0 -O2 -O0"),
+            Some(false)
+        );
+        assert_eq!(
+            producer_optimization(b"GNU C++17 13.2.0 -Os -g"),
+            Some(true)
+        );
+        assert_eq!(
+            producer_optimization(b"clang LLVM (rustc version 1.80.0)"),
+            None
+        );
+    }
+
+    #[test]
+    fn column_zero_is_no_column() {
+        let symbol = |column| super::super::Symbol {
+            inner: Symbol::Frame {
+                addr: 0x1000 as *mut c_void,
+                location: Some(addr2line::Location {
+                    file: Some("foo.rs"),
+                    line: Some(42),
+                    column: Some(column),
+                }),
+                name: None,
+                die: None,
+                module: None,
+            },
+        };
+        assert_eq!(symbol(0).colno(), None);
+        assert_eq!(symbol(0).lineno(), Some(42));
+        assert_eq!(symbol(7).colno(), Some(7));
+    }
+
+    #[cfg(target_os = "linux")]
+    fn library(name: &str, start: usize, len: usize) -> Library {
+        Library {
+            name: name.into(),
+            inode: None,
+            segments: vec![LibrarySegment {
+                stated_virtual_memory_address: 0x1000,
+                len,
+            }],
+            bias: start - 0x1000,
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn overlapping_libraries() {
+        // Nothing is mapped from a file here.
+        let local = 0;
+        let addr = &local as *const i32 as usize;
+        let cache = Cache::with_libraries(vec![
+            library("/a", addr - 0x100, 0x1000),
+            library("/b", addr - 0x10, 0x100),
+            library("/c", addr - 0x20, 0x100),
+        ]);
+        let (i, svma) = cache.avma_to_svma(addr as *const u8).unwrap();
+        assert_eq!(i, 2);
+        assert_eq!(svma as usize, 0x1020);
+
+        // The file actually mapped at the address wins over tighter fits.
+        let addr = overlapping_libraries as fn() as usize;
+        let (exe, _) = mapped_file_at(addr).unwrap();
+        let cache = Cache::with_libraries(vec![
+            library(exe.to_str().unwrap(), addr - 0x100, 0x1000),
+            library("/b", addr - 0x10, 0x100),
+        ]);
+        assert_eq!(cache.avma_to_svma(addr as *const u8).unwrap().0, 0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn replaced_library() {
+        let cache = Cache::new();
+        let addr = replaced_library as fn() as usize;
+        let (i, _) = cache.avma_to_svma(addr as *const u8).unwrap();
+        let lib = &cache.libraries[i];
+        assert!(lib.inode.is_some());
+        assert!(!is_replaced(lib));
+
+        // As if another build had been installed in place of this one.
+        let mut replaced = library(lib.name.to_str().unwrap(), addr, 0x1000);
+        replaced.inode = lib.inode.map(|inode| inode + 1);
+        assert!(is_replaced(&replaced));
+        let mut cache = Cache::with_libraries(vec![replaced]);
+        assert!(cache.mapping_for_lib(0).is_none());
+        assert!(cache.stats.library_replaced);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn unmapped_addresses_survive_rescans() {
+        // Nothing is mapped from a file here.
+        let local = [0u8; 2];
+        let (a, b) = (&local[0] as *const u8, &local[1] as *const u8);
+        let mut cache = Cache::new();
+        cache.rescan_libraries_for(a);
+        assert!(cache.unmapped.iter().any(|addr| *addr == a as usize));
+
+        // Nothing was loaded in between, so `a` is still known to be unmapped.
+        cache.rescan_libraries_for(b);
+        assert_eq!(cache.unmapped.len(), 2);
+
+        // As if a library had been loaded since the last scan.
+        cache.libraries.pop();
+        cache.rescan_libraries();
+        assert_eq!(cache.unmapped.len(), 0);
+    }
+
+    // Linkers nowadays put code in a segment of its own, apart from read-only
+    // data, like GNU ld's `-z separate-code` does. All of the segments, and
+    // only them, should be known, so that addresses in any of them map to the
+    // stated address they were linked at. Binaries linked with code and
+    // read-only data in one segment have nothing to check.
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn separate_code_segments() {
+        use object::elf::{PF_W, PF_X, PT_LOAD};
+        use object::read::elf::{FileHeader, ProgramHeader};
+
+        let cache = Cache::new();
+        let addr = separate_code_segments as fn() as usize;
+        let (i, svma) = cache.avma_to_svma(addr as *const u8).unwrap();
+        let lib = &cache.libraries[i];
+
+        let data = mystd::fs::read(&lib.name).unwrap();
+        let elf = elf::Elf::parse(&*data).unwrap();
+        let endian = elf.endian().unwrap();
+        let loads = elf
+            .program_headers(endian, &*data)
+            .unwrap()
+            .iter()
+            .filter(|header| header.p_type(endian) == PT_LOAD)
+            .map(|header| {
+                let vaddr = header.p_vaddr(endian) as usize;
+                let len = header.p_memsz(endian) as usize;
+                let flags = header.p_flags(endian);
+                (vaddr, len, flags & PF_X != 0, flags & (PF_W | PF_X) == 0)
+            })
+            .collect::<Vec<_>>();
+        let separate_code = loads.iter().any(|&(_, _, exec, _)| exec)
+            && loads.iter().any(|&(_, _, _, read_only)| read_only);
+        if !separate_code {
+            return;
+        }
+
+        let segments = lib
+            .segments
+            .iter()
+            .map(|s| (s.stated_virtual_memory_address, s.len))
+            .collect::<Vec<_>>();
+        let expected = loads
+            .iter()
+            .map(|&(vaddr, len, _, _)| (vaddr, len))
+            .collect::<Vec<_>>();
+        assert_eq!(segments, expected);
+
+        let svma = svma as usize;
+        assert!(loads
+            .iter()
+            .any(|&(vaddr, len, exec, _)| exec && vaddr <= svma && svma - vaddr < len));
+    }
+}
@@ -21,6 +21,13 @@ impl Mapping {
             Context::new(stash, Object::parse(data)?, None, None)
         })
     }
+
+    /// Archive members are COFF objects rather than PE images, which aren't
+    /// supported, so nothing is loaded.
+    #[cfg(feature = "std")]
+    pub fn new_archive_member(_path: &Path, _member: &[u8]) -> Option<Mapping> {
+        None
+    }
 }
 
 pub struct Object<'a> {
@@ -38,6 +45,11 @@ pub fn get_image_base(data: &[u8]) -> Option<usize> {
 }
 
 impl<'a> Object<'a> {
+    pub fn dwarf_endian(&self) -> Endian {
+        // PE files are always little-endian.
+        Endian::Little
+    }
+
     fn parse(data: &'a [u8]) -> Option<Object<'a>> {
         let dos_header = ImageDosHeader::parse(data).ok()?;
         let mut offset = dos_header.nt_headers_offset().into();
@@ -75,6 +87,11 @@ impl<'a> Object<'a> {
     }
 
     pub fn section(&self, _: &Stash, name: &str) -> Option<&'a [u8]> {
+        // MinGW emits DWARF into ordinary PE sections, and since names like
+        // `.debug_info` don't fit in the 8-byte section header they're stored
+        // as `/<offset>` into the COFF string table. `section_by_name` resolves
+        // those through `self.strings`, so this works for both short and long
+        // section names.
         Some(
             self.sections
                 .section_by_name(self.strings, name.as_bytes())?
@@ -102,6 +119,14 @@ impl<'a> Object<'a> {
         self.symbols[i].1.name(self.strings).ok()
     }
 
+    /// Symbols have no size here, so only the closest one is a candidate.
+    #[cfg(feature = "std")]
+    pub fn search_symtab_candidates(&self, addr: u64, cb: &mut dyn FnMut(&[u8])) {
+        if let Some(name) = self.search_symtab(addr) {
+            cb(name);
+        }
+    }
+
     pub(super) fn search_object_map(&self, _addr: u64) -> Option<(&Context<'_>, u64)> {
         None
     }
@@ -1,10 +1,16 @@
 #![allow(clippy::useless_conversion)]
 
+use super::mystd::env;
 use super::mystd::ffi::OsStr;
 use super::mystd::fs;
+#[cfg(feature = "std")]
+use super::mystd::io::{Read, Seek, SeekFrom};
 use super::mystd::os::unix::ffi::OsStrExt;
 use super::mystd::path::{Path, PathBuf};
+use super::mystd::sync::OnceLock;
 use super::Either;
+#[cfg(feature = "std")]
+use super::FileData;
 use super::{gimli, Context, Endian, EndianSlice, Mapping, Stash};
 use alloc::string::String;
 use alloc::sync::Arc;
@@ -14,41 +20,71 @@ use core::str;
 #[cfg(feature = "ruzstd")]
 use object::elf::ELFCOMPRESS_ZSTD;
 use object::elf::{ELFCOMPRESS_ZLIB, ELF_NOTE_GNU, NT_GNU_BUILD_ID, SHF_COMPRESSED};
-use object::read::elf::{CompressionHeader, FileHeader, SectionHeader, SectionTable, Sym};
-use object::read::StringTable;
-use object::{BigEndian, Bytes, NativeEndian};
+use object::read::elf::{
+    CompressionHeader, FileHeader, SectionHeader, SectionTable, Sym, SymbolTable,
+};
+#[cfg(feature = "std")]
+use object::read::{ReadCache, ReadCacheOps};
+use object::read::{ReadRef, StringTable};
+use object::{BigEndian, Bytes, Endian as _, Endianness};
 
 #[cfg(target_pointer_width = "32")]
-type Elf = object::elf::FileHeader32<NativeEndian>;
+pub(super) type Elf = object::elf::FileHeader32<Endianness>;
 #[cfg(target_pointer_width = "64")]
-type Elf = object::elf::FileHeader64<NativeEndian>;
+pub(super) type Elf = object::elf::FileHeader64<Endianness>;
 
 impl Mapping {
     pub fn new(path: &Path) -> Option<Mapping> {
         let map = super::mmap(path)?;
         Mapping::mk_or_other(map, |map, stash| {
             let object = Object::parse(map)?;
+            let build_id = object.build_id();
+            let invoked = invocation_path(path);
 
             // Try to locate an external debug file using the build ID.
-            if let Some(path_debug) = object.build_id().and_then(locate_build_id) {
-                if let Some(mapping) = Mapping::new_debug(path, path_debug, None) {
+            if let Some(path_debug) = build_id.and_then(locate_build_id) {
+                if let Some(mapping) = Mapping::new_debug(path, path_debug, None, build_id) {
                     return Some(Either::A(mapping));
                 }
             }
 
             // Try to locate an external debug file using the GNU debug link section.
-            if let Some((path_debug, crc)) = object.gnu_debuglink_path(path) {
-                if let Some(mapping) = Mapping::new_debug(path, path_debug, Some(crc)) {
+            if let Some((path_debug, crc)) = object.gnu_debuglink_path(path, invoked.as_deref()) {
+                if let Some(mapping) = Mapping::new_debug(path, path_debug, Some(crc), build_id) {
                     return Some(Either::A(mapping));
                 }
             }
 
-            let dwp = Mapping::load_dwarf_package(path, stash);
+            let dwp = Mapping::load_dwarf_package(path, stash).or_else(|| {
+                let invoked = invoked.as_deref()?;
+                Mapping::load_dwarf_package(invoked, stash)
+            });
 
             Context::new(stash, object, None, dwp).map(Either::B)
         })
     }
 
+    /// Creates a mapping of a file read by a `CoreSymbolizer`'s file opener.
+    ///
+    /// Debug information is only looked for in the file itself, as separate
+    /// debug files on the local filesystem may well be of other builds.
+    #[cfg(feature = "std")]
+    pub fn new_opened(data: FileData) -> Option<Mapping> {
+        Mapping::mk(data, |data, stash| {
+            Context::new(stash, Object::parse(data)?, None, None)
+        })
+    }
+
+    /// Loads the object file `member` out of the archive at `path`.
+    #[cfg(feature = "std")]
+    pub fn new_archive_member(path: &Path, member: &[u8]) -> Option<Mapping> {
+        let map = super::mmap(path)?;
+        Mapping::mk(map, |data, stash| {
+            let object = Object::parse(super::archive_member(data, member)?)?;
+            Context::new(stash, object, None, None)
+        })
+    }
+
     /// On Android, shared objects can be loaded directly from a ZIP archive
     /// (see: [`super::Library::zip_offset`]).
     ///
@@ -91,11 +127,28 @@ impl Mapping {
     }
 
     /// Load debuginfo from an external debug file.
-    fn new_debug(original_path: &Path, path: PathBuf, crc: Option<u32>) -> Option<Mapping> {
+    ///
+    /// `build_id` is the build ID of the original file, if it has one. A debug
+    /// file with a different build ID was produced by a different build, and
+    /// its line numbers would be confidently wrong, so it's rejected and the
+    /// caller falls back to the original file's own symbols.
+    fn new_debug(
+        original_path: &Path,
+        path: PathBuf,
+        crc: Option<u32>,
+        build_id: Option<&[u8]>,
+    ) -> Option<Mapping> {
         let map = super::mmap(&path)?;
         Mapping::mk(map, |map, stash| {
             let object = Object::parse(map)?;
 
+            if let (Some(expected), Some(actual)) = (build_id, object.build_id()) {
+                if expected != actual {
+                    super::record_debuginfo_mismatch();
+                    return None;
+                }
+            }
+
             if let Some(_crc) = crc {
                 // TODO: check crc
             }
@@ -148,71 +201,207 @@ struct ParsedSym {
     name: u32,
 }
 
+/// Finds the symbol table of a file, falling back to the dynamic symbol table
+/// for stripped files.
+fn symbol_table<'data, R: ReadRef<'data>>(
+    endian: Endianness,
+    sections: &SectionTable<'data, Elf, R>,
+    data: R,
+) -> Option<SymbolTable<'data, Elf, R>> {
+    let syms = sections
+        .symbols(endian, data, object::elf::SHT_SYMTAB)
+        .ok()?;
+    if !syms.is_empty() {
+        return Some(syms);
+    }
+    sections.symbols(endian, data, object::elf::SHT_DYNSYM).ok()
+}
+
+/// Collects the symbols worth searching by address out of `syms`, sorted by
+/// their address.
+fn parse_symbols<'data, R: ReadRef<'data>>(
+    endian: Endianness,
+    sections: &SectionTable<'data, Elf, R>,
+    syms: &SymbolTable<'data, Elf, R>,
+    relocatable: bool,
+) -> Vec<ParsedSym> {
+    let mut syms = syms
+        .enumerate()
+        // Only look at function/object symbols. This mirrors what
+        // libbacktrace does and in general we're only symbolicating
+        // function addresses in theory. Object symbols correspond
+        // to data, and maybe someone's crazy enough to have a
+        // function go into static data?
+        .filter(|(_, sym)| {
+            let st_type = sym.st_type();
+            st_type == object::elf::STT_FUNC || st_type == object::elf::STT_OBJECT
+        })
+        // skip anything that's in an undefined section header,
+        // since it means it's an imported function and we're only
+        // symbolicating with locally defined functions. Files with too
+        // many sections to number in `st_shndx` keep the section index in
+        // an extended `SHT_SYMTAB_SHNDX` table instead.
+        .filter(|(index, sym)| match sym.st_shndx(endian) {
+            object::elf::SHN_XINDEX => syms
+                .shndx(endian, *index)
+                .is_some_and(|shndx| shndx != u32::from(object::elf::SHN_UNDEF)),
+            shndx => shndx != object::elf::SHN_UNDEF,
+        })
+        .map(|(index, sym)| {
+            let mut address: u64 = sym.st_value(endian).into();
+            // Symbols of relocatable objects are relative to their section.
+            // Sections of objects on disk are at address 0, but JITs point
+            // them at where they were loaded when registering objects.
+            if relocatable {
+                if let Ok(Some(section)) = syms.symbol_section(endian, sym, index) {
+                    if let Ok(section) = sections.section(section) {
+                        address = address.wrapping_add(section.sh_addr(endian).into());
+                    }
+                }
+            }
+            let size = sym.st_size(endian).into();
+            let name = sym.st_name(endian);
+            ParsedSym {
+                address,
+                size,
+                name,
+            }
+        })
+        .collect::<Vec<_>>();
+    syms.sort_unstable_by_key(|s| s.address);
+    syms
+}
+
+/// Finds the symbol containing `addr` in `syms`, as sorted by `parse_symbols`.
+fn search_symbols(syms: &[ParsedSym], addr: u64) -> Option<&ParsedSym> {
+    // Same sort of binary search as Windows above
+    let i = match syms.binary_search_by_key(&addr, |sym| sym.address) {
+        Ok(i) => i,
+        Err(i) => i.checked_sub(1)?,
+    };
+    let sym = syms.get(i)?;
+    if sym.address <= addr && addr <= sym.address + sym.size {
+        Some(sym)
+    } else {
+        None
+    }
+}
+
+/// The symbol table of a file, read on its own without mapping the rest of
+/// the file, see `resolve_name_only`.
+#[cfg(feature = "std")]
+pub(super) struct NameTable {
+    syms: Vec<ParsedSym>,
+    /// A copy of the string table the names of `syms` point into.
+    strings: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl NameTable {
+    pub(super) fn new(path: &Path) -> Option<NameTable> {
+        // Only the headers and the two tables are read from the file, and
+        // the cache of what was read is dropped once they're copied.
+        let file = ReadCache::new(FileReader(fs::File::open(path).ok()?));
+        let elf = Elf::parse(&file).ok()?;
+        let endian = elf.endian().ok()?;
+        let relocatable = elf.e_type(endian) == object::elf::ET_REL;
+        let sections = elf.sections(endian, &file).ok()?;
+        let syms = symbol_table(endian, &sections, &file)?;
+        let strings = sections
+            .section(syms.string_section())
+            .ok()?
+            .data(endian, &file)
+            .ok()?
+            .to_vec();
+        Some(NameTable {
+            syms: parse_symbols(endian, &sections, &syms, relocatable),
+            strings,
+        })
+    }
+
+    pub(super) fn search(&self, addr: u64) -> Option<&[u8]> {
+        let sym = search_symbols(&self.syms, addr)?;
+        let name = self.strings.get(usize::try_from(sym.name).ok()?..)?;
+        Some(&name[..name.iter().position(|&b| b == 0)?])
+    }
+}
+
+/// Reads a file for `ReadCache`, which only knows how to read files itself
+/// with the `std` feature of `object`.
+#[cfg(feature = "std")]
+struct FileReader(fs::File);
+
+#[cfg(feature = "std")]
+impl ReadCacheOps for FileReader {
+    fn len(&mut self) -> Result<u64, ()> {
+        self.0.metadata().map(|m| m.len()).map_err(drop)
+    }
+
+    fn seek(&mut self, pos: u64) -> Result<u64, ()> {
+        self.0.seek(SeekFrom::Start(pos)).map_err(drop)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+        self.0.read(buf).map_err(drop)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ()> {
+        self.0.read_exact(buf).map_err(drop)
+    }
+}
+
 pub struct Object<'a> {
-    /// Zero-sized type representing the native endianness.
+    /// The endianness of the file, as read from its header.
     ///
-    /// We could use a literal instead, but this helps ensure correctness.
-    endian: NativeEndian,
+    /// This is usually the native endianness, but needn't be when looking at
+    /// files from another machine, such as the libraries of a core dump.
+    endian: Endianness,
     /// The entire file data.
     data: &'a [u8],
     sections: SectionTable<'a, Elf>,
     strings: StringTable<'a>,
     /// List of pre-parsed and sorted symbols by base address.
     syms: Vec<ParsedSym>,
+    /// Whether this is a relocatable object file rather than a linked binary.
+    relocatable: bool,
 }
 
 impl<'a> Object<'a> {
-    fn parse(data: &'a [u8]) -> Option<Object<'a>> {
+    pub(super) fn parse(data: &'a [u8]) -> Option<Object<'a>> {
         let elf = Elf::parse(data).ok()?;
         let endian = elf.endian().ok()?;
+        let relocatable = elf.e_type(endian) == object::elf::ET_REL;
         let sections = elf.sections(endian, data).ok()?;
-        let mut syms = sections
-            .symbols(endian, data, object::elf::SHT_SYMTAB)
-            .ok()?;
-        if syms.is_empty() {
-            syms = sections
-                .symbols(endian, data, object::elf::SHT_DYNSYM)
-                .ok()?;
-        }
+        let syms = symbol_table(endian, &sections, data)?;
         let strings = syms.strings();
-
-        let mut syms = syms
-            .iter()
-            // Only look at function/object symbols. This mirrors what
-            // libbacktrace does and in general we're only symbolicating
-            // function addresses in theory. Object symbols correspond
-            // to data, and maybe someone's crazy enough to have a
-            // function go into static data?
-            .filter(|sym| {
-                let st_type = sym.st_type();
-                st_type == object::elf::STT_FUNC || st_type == object::elf::STT_OBJECT
-            })
-            // skip anything that's in an undefined section header,
-            // since it means it's an imported function and we're only
-            // symbolicating with locally defined functions.
-            .filter(|sym| sym.st_shndx(endian) != object::elf::SHN_UNDEF)
-            .map(|sym| {
-                let address = sym.st_value(endian).into();
-                let size = sym.st_size(endian).into();
-                let name = sym.st_name(endian);
-                ParsedSym {
-                    address,
-                    size,
-                    name,
-                }
-            })
-            .collect::<Vec<_>>();
-        syms.sort_unstable_by_key(|s| s.address);
+        let syms = parse_symbols(endian, &sections, &syms, relocatable);
         Some(Object {
             endian,
             data,
             sections,
             strings,
             syms,
+            relocatable,
         })
     }
 
+    pub fn dwarf_endian(&self) -> Endian {
+        match self.endian {
+            Endianness::Little => Endian::Little,
+            Endianness::Big => Endian::Big,
+        }
+    }
+
     pub fn section(&self, stash: &'a Stash, name: &str) -> Option<&'a [u8]> {
+        // Debug information in relocatable objects (like archive members)
+        // refers to code through relocations which we don't apply, so all
+        // of its addresses would be wrong. Fall back to the symbol table
+        // instead. Split DWARF objects are relocatable too, but don't have
+        // any relocations.
+        if self.relocatable && self.has_relocations(name) {
+            return None;
+        }
+
         if let Some(section) = self.section_header(name) {
             let mut data = Bytes(section.data(self.endian, self.data).ok()?);
 
@@ -274,6 +463,18 @@ impl<'a> Object<'a> {
         Some(buf)
     }
 
+    fn has_relocations(&self, name: &str) -> bool {
+        let index = match self.sections.section_by_name(self.endian, name.as_bytes()) {
+            Some((index, _section)) => index,
+            None => return false,
+        };
+        self.sections.iter().any(|header| {
+            let sh_type = header.sh_type(self.endian);
+            (sh_type == object::elf::SHT_REL || sh_type == object::elf::SHT_RELA)
+                && header.sh_info(self.endian) as usize == index.0
+        })
+    }
+
     fn section_header(&self, name: &str) -> Option<&<Elf as FileHeader>::SectionHeader> {
         self.sections
             .section_by_name(self.endian, name.as_bytes())
@@ -281,16 +482,22 @@ impl<'a> Object<'a> {
     }
 
     pub fn search_symtab(&self, addr: u64) -> Option<&[u8]> {
-        // Same sort of binary search as Windows above
-        let i = match self.syms.binary_search_by_key(&addr, |sym| sym.address) {
-            Ok(i) => i,
-            Err(i) => i.checked_sub(1)?,
-        };
-        let sym = self.syms.get(i)?;
-        if sym.address <= addr && addr <= sym.address + sym.size {
-            self.strings.get(sym.name).ok()
-        } else {
-            None
+        self.strings
+            .get(search_symbols(&self.syms, addr)?.name)
+            .ok()
+    }
+
+    /// Passes the name of every symbol containing `addr` to `cb`, the closest
+    /// first, see `resolve_candidates`.
+    #[cfg(feature = "std")]
+    pub fn search_symtab_candidates(&self, addr: u64, cb: &mut dyn FnMut(&[u8])) {
+        let end = self.syms.partition_point(|sym| sym.address <= addr);
+        for sym in self.syms[..end].iter().rev() {
+            if addr <= sym.address + sym.size {
+                if let Ok(name) = self.strings.get(sym.name) {
+                    cb(name);
+                }
+            }
         }
     }
 
@@ -313,7 +520,10 @@ impl<'a> Object<'a> {
 
     // The contents of the ".gnu_debuglink" section is documented at:
     // https://sourceware.org/gdb/onlinedocs/gdb/Separate-Debug-Files.html
-    fn gnu_debuglink_path(&self, path: &Path) -> Option<(PathBuf, u32)> {
+    //
+    // `invoked` is another path leading to the file whose directories are
+    // searched as well, see `invocation_path`.
+    fn gnu_debuglink_path(&self, path: &Path, invoked: Option<&Path>) -> Option<(PathBuf, u32)> {
         let section = self.section_header(".gnu_debuglink")?;
         let data = section.data(self.endian, self.data).ok()?;
         let len = data.iter().position(|x| *x == 0)?;
@@ -322,8 +532,9 @@ impl<'a> Object<'a> {
         let crc_bytes = data
             .get(offset..offset + 4)
             .and_then(|bytes| bytes.try_into().ok())?;
-        let crc = u32::from_ne_bytes(crc_bytes);
-        let path_debug = locate_debuglink(path, filename)?;
+        let crc = self.endian.read_u32_bytes(crc_bytes);
+        let path_debug = locate_debuglink(path, filename)
+            .or_else(|| locate_debuglink_beside(invoked?, filename))?;
         Some((path_debug, crc))
     }
 
@@ -462,6 +673,12 @@ fn locate_build_id(build_id: &[u8]) -> Option<PathBuf> {
 /// gdb also supports debuginfod, but we don't yet.
 fn locate_debuglink(path: &Path, filename: &OsStr) -> Option<PathBuf> {
     let path = fs::canonicalize(path).ok()?;
+    locate_debuglink_beside(&path, filename)
+}
+
+/// Like `locate_debuglink`, but for an absolute `path` whose final component
+/// is left as it is, rather than resolved if it's a symlink.
+fn locate_debuglink_beside(path: &Path, filename: &OsStr) -> Option<PathBuf> {
     let parent = path.parent()?;
     let mut f =
         PathBuf::with_capacity(DEBUG_PATH.len() + parent.as_os_str().len() + filename.len() + 2);
@@ -497,6 +714,49 @@ fn locate_debuglink(path: &Path, filename: &OsStr) -> Option<PathBuf> {
     None
 }
 
+/// Returns the path the main binary was run through, if `path` is the main
+/// binary and that differs from it.
+///
+/// The main binary is always known by its resolved path, but deployments which
+/// run it through a symlink may put its debug files next to the symlink rather
+/// than the binary it points to. The symlink is found from `argv[0]`, searching
+/// `PATH` like the shell would if it's a bare name, and its final component is
+/// left unresolved so that its own directory can be searched.
+///
+/// None of this changes while the process runs, so it's only worked out the
+/// first time the main binary is mapped.
+fn invocation_path(path: &Path) -> Option<PathBuf> {
+    static INVOKED: OnceLock<Option<(PathBuf, PathBuf)>> = OnceLock::new();
+    let (exe, invoked) = INVOKED
+        .get_or_init(|| {
+            let exe = env::current_exe().ok()?;
+            let invoked = find_invocation_path(&exe)?;
+            Some((exe, invoked))
+        })
+        .as_ref()?;
+    if path != exe {
+        return None;
+    }
+    Some(invoked.clone())
+}
+
+fn find_invocation_path(exe: &Path) -> Option<PathBuf> {
+    let arg0 = PathBuf::from(env::args_os().next()?);
+    let (dir, name) = if arg0.components().count() > 1 {
+        (arg0.parent()?.to_path_buf(), arg0.file_name()?)
+    } else {
+        let search = env::var_os("PATH")?;
+        let dir = env::split_paths(&search).find(|dir| dir.join(&arg0).is_file())?;
+        (dir, arg0.as_os_str())
+    };
+    let invoked = fs::canonicalize(dir).ok()?.join(name);
+    let resolved = fs::canonicalize(exe).ok()?;
+    if invoked == resolved || fs::canonicalize(&invoked).ok()? != resolved {
+        return None;
+    }
+    Some(invoked)
+}
+
 /// Locate a file specified in a `.gnu_debugaltlink` section.
 ///
 /// `path` is the file containing the section.
@@ -554,7 +814,7 @@ pub(super) fn handle_split_dwarf<'data>(
                     .dwo_name()
                     .and_then(|name| dwo.section(stash, name))
                     .unwrap_or(&[]);
-                Ok(EndianSlice::new(data, Endian))
+                Ok(EndianSlice::new(data, dwo.dwarf_endian()))
             })
             .ok()
             .map(|mut dwo_dwarf| {
@@ -566,3 +826,167 @@ pub(super) fn handle_split_dwarf<'data>(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_file_build_id_mismatch() {
+        // Any file with a build ID will do as a debug file.
+        let exe = env::current_exe().unwrap();
+        let map = super::super::mmap(&exe).unwrap();
+        let Some(build_id) = Object::parse(&map).and_then(|o| Some(o.build_id()?.to_vec())) else {
+            return;
+        };
+
+        let debug = Mapping::new_debug(&exe, exe.clone(), None, Some(&build_id));
+        assert!(debug.is_some());
+
+        let mut other_build = build_id;
+        other_build[0] ^= 0xff;
+        let debug = Mapping::new_debug(&exe, exe.clone(), None, Some(&other_build));
+        assert!(debug.is_none());
+        assert!(super::super::debuginfo_mismatch());
+    }
+
+    /// Builds a 64-bit executable in the byte order `endian` with a symbol
+    /// table of `syms`, given as the offset of their name in `strings`, their
+    /// section index and their address. If there are `shndx` entries then
+    /// they're put in a `SHT_SYMTAB_SHNDX` table.
+    #[cfg(target_pointer_width = "64")]
+    fn build_elf(
+        endian: Endianness,
+        strings: &[u8],
+        syms: &[(u32, u16, u64)],
+        shndx: &[u32],
+    ) -> Vec<u8> {
+        use object::Endian;
+
+        let mut data = Vec::new();
+        let u16 = |data: &mut Vec<u8>, value| data.extend(endian.write_u16_bytes(value));
+        let u32 = |data: &mut Vec<u8>, value| data.extend(endian.write_u32_bytes(value));
+        let u64 = |data: &mut Vec<u8>, value| data.extend(endian.write_u64_bytes(value));
+
+        // The contents of the sections, starting with the string table right
+        // after the file header.
+        data.resize(64, 0);
+        let strings_offset = data.len() as u64;
+        data.extend(strings);
+        data.resize(data.len().next_multiple_of(8), 0);
+        let syms_offset = data.len() as u64;
+        data.extend([0; 24]);
+        for &(name, st_shndx, value) in syms {
+            u32(&mut data, name);
+            data.push(object::elf::STT_FUNC);
+            data.push(0);
+            u16(&mut data, st_shndx);
+            u64(&mut data, value);
+            u64(&mut data, 8);
+        }
+        let shndx_offset = data.len() as u64;
+        for &index in shndx {
+            u32(&mut data, index);
+        }
+        data.resize(data.len().next_multiple_of(8), 0);
+
+        // Then the section headers, starting with the null section.
+        let shoff = data.len() as u64;
+        let mut sections = vec![
+            (object::elf::SHT_PROGBITS, 0, 0, 0, 0),
+            (
+                object::elf::SHT_SYMTAB,
+                syms_offset,
+                shndx_offset - syms_offset,
+                3,
+                24,
+            ),
+            (
+                object::elf::SHT_STRTAB,
+                strings_offset,
+                strings.len() as u64,
+                0,
+                0,
+            ),
+        ];
+        if !shndx.is_empty() {
+            let size = 4 * shndx.len() as u64;
+            sections.push((object::elf::SHT_SYMTAB_SHNDX, shndx_offset, size, 2, 4));
+        }
+        data.extend([0; 64]);
+        for &(sh_type, offset, size, link, entsize) in &sections {
+            u32(&mut data, 0);
+            u32(&mut data, sh_type);
+            u64(&mut data, 0);
+            u64(&mut data, 0);
+            u64(&mut data, offset);
+            u64(&mut data, size);
+            u32(&mut data, link);
+            u32(&mut data, 0);
+            u64(&mut data, 1);
+            u64(&mut data, entsize);
+        }
+
+        let mut header = Vec::new();
+        header.extend(b"\x7fELF\x02");
+        header.push(if endian.is_big_endian() { 2 } else { 1 });
+        header.extend([1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        u16(&mut header, object::elf::ET_EXEC);
+        u16(&mut header, object::elf::EM_X86_64);
+        u32(&mut header, 1);
+        u64(&mut header, 0); // e_entry
+        u64(&mut header, 0); // e_phoff
+        u64(&mut header, shoff);
+        u32(&mut header, 0);
+        u16(&mut header, 64);
+        u16(&mut header, 56);
+        u16(&mut header, 0);
+        u16(&mut header, 64);
+        u16(&mut header, 1 + sections.len() as u16);
+        u16(&mut header, 3); // e_shstrndx
+        data[..64].copy_from_slice(&header);
+        data
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn extended_section_indices() {
+        // All the symbols have their section index in a `SHT_SYMTAB_SHNDX`
+        // table, as if there were too many sections to number otherwise. The
+        // one in section 1 is defined, but the one in section 0 isn't.
+        let data = build_elf(
+            Endianness::Little,
+            b"\0defined\0undefined\0",
+            &[
+                (1, object::elf::SHN_XINDEX, 0x1000),
+                (9, object::elf::SHN_XINDEX, 0x2000),
+            ],
+            &[0, 1, 0],
+        );
+        let object = Object::parse(&data).unwrap();
+        assert_eq!(object.search_symtab(0x1000), Some(&b"defined"[..]));
+        assert_eq!(object.search_symtab(0x2000), None);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn foreign_endianness() {
+        // Files are read in their own byte order, whichever is native.
+        for endian in [Endianness::Little, Endianness::Big] {
+            let data = build_elf(
+                endian,
+                b"\0first\0second\0",
+                &[(1, 1, 0x1000), (7, 1, 0x2000)],
+                &[],
+            );
+            let object = Object::parse(&data).unwrap();
+            assert_eq!(object.search_symtab(0x1000), Some(&b"first"[..]));
+            assert_eq!(object.search_symtab(0x2004), Some(&b"second"[..]));
+            let expected = match endian {
+                Endianness::Little => Endian::Little,
+                Endianness::Big => Endian::Big,
+            };
+            assert_eq!(object.dwarf_endian(), expected);
+        }
+    }
+}
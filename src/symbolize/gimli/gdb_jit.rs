@@ -0,0 +1,249 @@
+//! Symbolication of code generated by JIT compilers, through the interface
+//! they use to describe it to GDB.
+//!
+//! A JIT supporting this interface builds an ELF object in memory for the code
+//! it generates, with its sections pointed at where the code ended up, and
+//! links it into the list of `__jit_debug_descriptor`. Debuggers then read the
+//! objects out of that list, and so can we. For more details see
+//! <https://sourceware.org/gdb/current/onlinedocs/gdb.html/JIT-Interface.html>.
+//!
+//! Objects which are relocatable, as the ones of LLVM's JITs are, usually still
+//! have their debug information waiting to be relocated, which isn't done
+//! here. Only the names in their symbol table are found for them.
+//!
+//! The list is only meant to be read by a debugger with the process stopped,
+//! and JITs modify it without any lock we could take. Reading it races with a
+//! JIT registering or unregistering an object, so it's only read once
+//! `enable_gdb_jit` has been called, whose caller vouches that this doesn't
+//! happen while resolving.
+
+#![allow(clippy::useless_conversion)]
+
+use super::elf::Elf;
+use super::{resolve_svma, Context, Mapping, Object};
+use alloc::vec::Vec;
+use core::iter;
+use core::mem;
+use core::ops::Range;
+use core::ptr;
+use core::slice;
+use core::sync::atomic::{AtomicBool, Ordering};
+use libc::c_void;
+use object::elf::{SHF_ALLOC, SHF_EXECINSTR};
+use object::read::elf::{FileHeader, SectionHeader};
+
+/// `struct jit_code_entry` of the interface.
+#[repr(C)]
+struct JitCodeEntry {
+    next_entry: *const JitCodeEntry,
+    prev_entry: *const JitCodeEntry,
+    symfile_addr: *const u8,
+    symfile_size: u64,
+}
+
+/// `struct jit_descriptor` of the interface.
+#[repr(C)]
+struct JitDescriptor {
+    version: u32,
+    action_flag: u32,
+    relevant_entry: *const JitCodeEntry,
+    first_entry: *const JitCodeEntry,
+}
+
+/// The only version of the interface there is.
+const JIT_DESCRIPTOR_VERSION: u32 = 1;
+
+/// How many registered objects are looked at, in case the list is modified
+/// while it's walked and ends up in a cycle.
+const MAX_OBJECTS: usize = 1 << 16;
+
+/// Set by `enable_gdb_jit`, after which registered objects are looked up.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "std")]
+pub unsafe fn enable_gdb_jit() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Registered objects keyed by their address and size, as a JIT could
+/// register a new object at the same address with the same size in place of
+/// one it unregistered, but then it'd most likely describe the same code.
+type Key = (usize, usize);
+
+/// What's known of the registered objects.
+///
+/// The most recently used object is copied as JITs free objects when they
+/// unregister them.
+#[derive(Default)]
+pub struct JitCache {
+    /// Where the code of each object registered at the last lookup is, so
+    /// objects are only parsed once to find the one with an address.
+    code: Vec<(Key, Vec<Range<u64>>)>,
+    last: Option<(Key, Mapping)>,
+}
+
+impl JitCache {
+    pub fn resolve(
+        &mut self,
+        addr: *mut c_void,
+        exact: bool,
+        cb: &mut dyn FnMut(&super::super::Symbol),
+    ) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+        // JITs define this themselves, it's only found if it's exported.
+        let descriptor =
+            unsafe { libc::dlsym(libc::RTLD_DEFAULT, c"__jit_debug_descriptor".as_ptr()) };
+        // SAFETY: the caller of `enable_gdb_jit` vouched that JITs don't
+        // modify the list while resolving.
+        unsafe { self.resolve_registered(descriptor.cast(), addr as u64, exact, cb) }
+    }
+
+    /// Resolves `addr` in the objects registered in `descriptor`, which is
+    /// either null or a valid descriptor whose list isn't modified meanwhile.
+    unsafe fn resolve_registered(
+        &mut self,
+        descriptor: *const JitDescriptor,
+        addr: u64,
+        exact: bool,
+        cb: &mut dyn FnMut(&super::super::Symbol),
+    ) {
+        // Objects which aren't registered anymore are dropped along the way.
+        let mut found = None;
+        let mut code = Vec::new();
+        for object in registered_objects(descriptor) {
+            let key = (object.as_ptr() as usize, object.len());
+            let ranges = match self.code.iter().position(|(k, _)| *k == key) {
+                Some(i) => self.code.swap_remove(i).1,
+                None => code_ranges(object),
+            };
+            if found.is_none() && ranges.iter().any(|range| range.contains(&addr)) {
+                found = Some(object);
+            }
+            code.push((key, ranges));
+        }
+        self.code = code;
+        let Some(object) = found else {
+            return;
+        };
+        let key = (object.as_ptr() as usize, object.len());
+        if self.last.as_ref().map(|(last, _)| *last) != Some(key) {
+            self.last = None;
+            let mapping = Mapping::mk_copy(object, |data, stash| {
+                Context::new(stash, Object::parse(data)?, None, None)
+            });
+            self.last = mapping.map(|mapping| (key, mapping));
+        }
+        if let Some((_, mapping)) = &self.last {
+            let cx: &Context<'static> = &mapping.cx;
+            // don't leak the `'static` lifetime, make sure it's scoped to just
+            // ourselves
+            let cx = mem::transmute::<&Context<'static>, &Context<'_>>(cx);
+            // Registered objects are described at the addresses they're
+            // loaded at, so there's no bias to remove.
+            resolve_svma(cx, &mapping.stash, None, addr, exact, cb);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.code.clear();
+        self.last = None;
+    }
+}
+
+/// Returns the objects registered in `descriptor`, which is either null or a
+/// valid descriptor whose list isn't modified while it's walked.
+unsafe fn registered_objects<'a>(
+    descriptor: *const JitDescriptor,
+) -> impl Iterator<Item = &'a [u8]> {
+    let mut entry = match descriptor.as_ref() {
+        Some(descriptor) if descriptor.version == JIT_DESCRIPTOR_VERSION => descriptor.first_entry,
+        _ => ptr::null(),
+    };
+    iter::from_fn(move || {
+        // SAFETY: entries stay valid while they're linked into the list, and
+        // the caller makes sure the list isn't modified in the meantime. A JIT
+        // unlinking and freeing an entry concurrently would leave this
+        // reading freed memory.
+        let current = unsafe { entry.as_ref()? };
+        entry = current.next_entry;
+        Some(current)
+    })
+    .take(MAX_OBJECTS)
+    .filter(|entry| !entry.symfile_addr.is_null())
+    .filter_map(|entry| {
+        let len = usize::try_from(entry.symfile_size).ok()?;
+        Some(unsafe { slice::from_raw_parts(entry.symfile_addr, len) })
+    })
+}
+
+/// Returns where the ELF object `data` has code loaded.
+fn code_ranges(data: &[u8]) -> Vec<Range<u64>> {
+    let Ok(elf) = Elf::parse(data) else {
+        return Vec::new();
+    };
+    let Ok(endian) = elf.endian() else {
+        return Vec::new();
+    };
+    let Ok(sections) = elf.sections(endian, data) else {
+        return Vec::new();
+    };
+    let code = u64::from(SHF_ALLOC | SHF_EXECINSTR);
+    sections
+        .iter()
+        .filter(|section| {
+            let flags: u64 = section.sh_flags(endian).into();
+            flags & code == code
+        })
+        .map(|section| {
+            let start: u64 = section.sh_addr(endian).into();
+            let size: u64 = section.sh_size(endian).into();
+            start..start.saturating_add(size)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mystd;
+    use super::*;
+
+    #[test]
+    fn registered_executable() {
+        // Any ELF object will do, as long as its sections have addresses.
+        let exe = mystd::fs::read("/proc/self/exe").unwrap();
+        let elf = Elf::parse(&*exe).unwrap();
+        let endian = elf.endian().unwrap();
+        let sections = elf.sections(endian, &*exe).unwrap();
+        let (_, text) = sections.section_by_name(endian, b".text").unwrap();
+        let text: u64 = text.sh_addr(endian).into();
+
+        let entry = JitCodeEntry {
+            next_entry: ptr::null(),
+            prev_entry: ptr::null(),
+            symfile_addr: exe.as_ptr(),
+            symfile_size: exe.len() as u64,
+        };
+        let descriptor = JitDescriptor {
+            version: JIT_DESCRIPTOR_VERSION,
+            action_flag: 0,
+            relevant_entry: &entry,
+            first_entry: &entry,
+        };
+
+        let mut cache = JitCache::default();
+        let mut count = 0;
+        unsafe { cache.resolve_registered(&descriptor, text, false, &mut |_| count += 1) };
+        assert!(count > 0);
+        assert!(cache.last.is_some());
+        assert_eq!(cache.code.len(), 1);
+
+        count = 0;
+        unsafe { cache.resolve_registered(&descriptor, 0, false, &mut |_| count += 1) };
+        unsafe { cache.resolve_registered(ptr::null(), text, false, &mut |_| count += 1) };
+        assert_eq!(count, 0);
+        // Unregistered objects are forgotten.
+        assert!(cache.code.is_empty());
+    }
+}
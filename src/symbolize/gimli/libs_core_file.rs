@@ -0,0 +1,130 @@
+#![allow(clippy::useless_conversion)]
+
+// Core dumps of ELF platforms record which files were mapped where in their
+// `NT_FILE` note, and from that the load address of each library can be
+// recovered by matching the mappings up with the library's program headers.
+
+use super::super::MappedFile;
+use super::mystd::path::{Path, PathBuf};
+use super::parse_running_mmaps::MapsEntry;
+use super::{FileData, Library, LibrarySegment};
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use object::elf::PT_LOAD;
+use object::read::elf::{FileHeader, ProgramHeader};
+use object::Endianness;
+
+#[cfg(target_pointer_width = "32")]
+type Elf = object::elf::FileHeader32<Endianness>;
+#[cfg(target_pointer_width = "64")]
+type Elf = object::elf::FileHeader64<Endianness>;
+
+/// Finds out where the libraries among `files` were loaded, reading each of
+/// them with `open`.
+pub(super) fn core_file_libraries(
+    files: &[MappedFile],
+    open: &dyn Fn(&Path) -> Option<FileData>,
+) -> Vec<Library> {
+    let mut libs = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        // Each file is typically mapped several times, once per segment, so
+        // handle all of its mappings together when we first come across it.
+        if files[..i].iter().any(|f| f.path() == file.path()) {
+            continue;
+        }
+        let mappings = files[i..]
+            .iter()
+            .filter(|f| f.path() == file.path())
+            .collect::<Vec<_>>();
+        libs.extend(library(file.path(), &mappings, open));
+    }
+    libs
+}
+
+/// Parses the text of a `/proc/<pid>/maps` file into the files it lists as
+/// mapped, skipping anonymous mappings, pseudo-paths like `[heap]` and lines
+/// which can't be parsed.
+pub fn parse_maps_text(text: &str) -> Vec<MappedFile> {
+    text.lines()
+        .filter_map(|line| line.parse::<MapsEntry>().ok())
+        .filter(|entry| Path::new(entry.pathname()).is_absolute())
+        .map(|entry| {
+            let (start, end) = entry.address();
+            MappedFile::new(
+                PathBuf::from(entry.pathname().clone()),
+                start as u64,
+                end as u64,
+                entry.offset(),
+            )
+        })
+        .collect()
+}
+
+/// The largest page size in use, segments are mapped starting at most this
+/// far before their file offset.
+const MAX_PAGE_SIZE: u64 = 0x10000;
+
+fn library(
+    path: &Path,
+    mappings: &[&MappedFile],
+    open: &dyn Fn(&Path) -> Option<FileData>,
+) -> Option<Library> {
+    let map = open(path)?;
+    let elf = Elf::parse(&*map).ok()?;
+    let endian = elf.endian().ok()?;
+    let loads = elf
+        .program_headers(endian, &*map)
+        .ok()?
+        .iter()
+        .filter(|header| header.p_type(endian) == PT_LOAD)
+        .map(|header| {
+            let offset: u64 = header.p_offset(endian).into();
+            let vaddr: u64 = header.p_vaddr(endian).into();
+            let len: u64 = header.p_memsz(endian).into();
+            (offset, vaddr, len)
+        })
+        .collect::<Vec<_>>();
+
+    // Each segment is mapped starting at the page containing its file offset,
+    // so a mapping made for a segment tells us where it was loaded. The file
+    // may also have been mapped in other ways though (for example read whole
+    // by a debugger, or this crate), so go with the load bias which accounts
+    // for the most segments.
+    let biases = |&(offset, vaddr, _): &(u64, u64, u64)| {
+        mappings.iter().filter_map(move |m| {
+            let delta = offset.checked_sub(m.file_offset())?;
+            if delta < MAX_PAGE_SIZE && delta < m.end().saturating_sub(m.start()) {
+                Some((m.start() + delta).wrapping_sub(vaddr))
+            } else {
+                None
+            }
+        })
+    };
+    let bias = loads.iter().flat_map(biases).max_by_key(|bias| {
+        loads
+            .iter()
+            .filter(|load| biases(load).any(|b| b == *bias))
+            .count()
+    })?;
+
+    let mut segments = Vec::new();
+    for load in loads.iter().filter(|load| biases(load).any(|b| b == bias)) {
+        let &(_, vaddr, len) = load;
+        segments.push(LibrarySegment {
+            stated_virtual_memory_address: vaddr.try_into().ok()?,
+            len: len.try_into().ok()?,
+        });
+    }
+
+    Some(Library {
+        name: path.as_os_str().to_owned(),
+        #[cfg(target_os = "android")]
+        zip_offset: None,
+        // The files are those of another process, mapped at some other time.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        inode: None,
+        segments,
+        bias: bias.try_into().ok()?,
+    })
+}
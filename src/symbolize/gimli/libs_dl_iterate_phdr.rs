@@ -10,6 +10,7 @@ use alloc::borrow::ToOwned;
 use alloc::vec::Vec;
 use core::ffi::CStr;
 use core::slice;
+use object::elf::PT_LOAD;
 
 struct CallbackData {
     libs: Vec<Library>,
@@ -105,12 +106,30 @@ unsafe extern "C" fn callback(
         // SAFETY: We just checked for nullness or 0-len slices
         unsafe { slice::from_raw_parts(dlpi_phdr, dlpi_phnum as usize) }
     };
+    // The file mapped at the first loadable segment is the library's.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let inode = maps.as_ref().and_then(|maps| {
+        let first = headers.iter().find(|header| header.p_type == PT_LOAD)?;
+        let addr = (dlpi_addr as usize).wrapping_add(first.p_vaddr as usize);
+        let entry = maps.iter().find(|m| m.ip_matches(addr))?;
+        // Anonymous memory has no inode.
+        (entry.inode() != 0).then_some(entry.inode())
+    });
     libs.push(Library {
         name,
         #[cfg(target_os = "android")]
         zip_offset,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        inode,
+        // Only loadable segments are mapped. Other headers such as
+        // `PT_GNU_EH_FRAME` or `PT_TLS` describe parts of them, or memory
+        // which isn't at their stated address at all, and mustn't be mistaken
+        // for segments of their own. Note that the loadable segments needn't
+        // be contiguous, with `-z separate-code` code gets segments of its
+        // own, but all of them share the one bias of the library.
         segments: headers
             .iter()
+            .filter(|header| header.p_type == PT_LOAD)
             .map(|header| LibrarySegment {
                 len: header.p_memsz as usize,
                 stated_virtual_memory_address: header.p_vaddr as usize,
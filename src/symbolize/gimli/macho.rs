@@ -6,6 +6,7 @@ use alloc::vec::Vec;
 use core::convert::TryInto;
 use object::macho;
 use object::read::macho::{MachHeader, Nlist, Section, Segment as _};
+use object::read::StringTable;
 use object::{Bytes, NativeEndian};
 
 #[cfg(target_pointer_width = "32")]
@@ -17,6 +18,18 @@ type MachSection = <Mach as MachHeader>::Section;
 type MachNlist = <Mach as MachHeader>::Nlist;
 
 impl Mapping {
+    /// Loads the object file `member` out of the archive at `path`.
+    #[cfg(feature = "std")]
+    pub fn new_archive_member(path: &Path, member: &[u8]) -> Option<Mapping> {
+        let map = super::mmap(path)?;
+        Mapping::mk(map, |data, stash| {
+            let (macho, data) = find_header(super::archive_member(data, member)?)?;
+            let endian = macho.endian().ok()?;
+            let obj = Object::parse(macho, endian, data)?;
+            Context::new(stash, obj, None, None)
+        })
+    }
+
     // The loading path for macOS is so different we just have a completely
     // different implementation of the function here. On macOS we need to go
     // probing the filesystem for a bunch of files.
@@ -40,6 +53,10 @@ impl Mapping {
                     return Some(mapping);
                 }
             }
+        } else if let Some(mapping) = Mapping::load_dsym_by_name(path) {
+            // Binaries linked with `-no_uuid` can't be matched with their
+            // `*.dSYM` that way, so fall back to the one named after them.
+            return Some(mapping);
         }
 
         // Looks like nothing matched our UUID, so let's at least return our own
@@ -71,6 +88,34 @@ impl Mapping {
         None
     }
 
+    /// Loads `<name>.dSYM` next to the binary at `path`, for binaries without
+    /// a UUID.
+    ///
+    /// Without a UUID any `*.dSYM` could be the right one, so only the one
+    /// named after the binary is tried, and only if it has no UUID either. A
+    /// `*.dSYM` with a UUID belongs to a build which had one, so it isn't for
+    /// this binary.
+    fn load_dsym_by_name(path: &Path) -> Option<Mapping> {
+        let name = path.file_name()?;
+        let mut bundle = name.to_owned();
+        bundle.push(".dSYM");
+        let dwarf = path
+            .parent()?
+            .join(bundle)
+            .join("Contents/Resources/DWARF")
+            .join(name);
+        let map = super::mmap(&dwarf)?;
+        Mapping::mk(map, |data, stash| {
+            let (macho, data) = find_header(data)?;
+            let endian = macho.endian().ok()?;
+            if macho.uuid(endian, data, 0).ok()?.is_some() {
+                return None;
+            }
+            let obj = Object::parse(macho, endian, data)?;
+            Context::new(stash, obj, None, None)
+        })
+    }
+
     fn try_dsym_candidate(dir: &Path, uuid: [u8; 16]) -> Option<Mapping> {
         // Look for files in the `DWARF` directory which have a matching uuid to
         // the original object file. If we find one then we found the debug
@@ -173,6 +218,11 @@ pub struct Object<'a> {
 }
 
 impl<'a> Object<'a> {
+    pub fn dwarf_endian(&self) -> Endian {
+        // Only Mach-O files of the native endianness are parsed.
+        Endian::default()
+    }
+
     fn parse(mach: &'a Mach, endian: NativeEndian, data: &'a [u8]) -> Option<Object<'a>> {
         let is_object = mach.filetype(endian) == object::macho::MH_OBJECT;
         let mut dwarf = None;
@@ -189,25 +239,19 @@ impl<'a> Object<'a> {
                 }
             } else if let Some(symtab) = command.symtab().ok()? {
                 let symbols = symtab.symbols::<Mach, _>(endian, data).ok()?;
-                syms = symbols
-                    .iter()
-                    .filter_map(|nlist: &MachNlist| {
-                        let name = nlist.name(endian, symbols.strings()).ok()?;
-                        if name.len() > 0 && nlist.is_definition() {
-                            Some((name, u64::from(nlist.n_value(endian))))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+                let defined = defined_symbols(symbols.iter(), symbols.strings(), endian);
                 if is_object {
                     // We never search object file symbols by address.
                     // Instead, we already know the symbol name from the executable, and we
                     // need to search by name to find the matching symbol in the object file.
+                    syms = defined
+                        .into_iter()
+                        .map(|(name, addr, _rank)| (name, addr))
+                        .collect();
                     syms.sort_unstable_by_key(|(name, _)| *name);
                     syms_sort_by_name = true;
                 } else {
-                    syms.sort_unstable_by_key(|(_, addr)| *addr);
+                    syms = sort_by_address(defined);
                     let map = symbols.object_map(endian);
                     object_mappings.resize_with(map.objects().len(), || None);
                     object_map = Some(map);
@@ -250,6 +294,14 @@ impl<'a> Object<'a> {
         Some(sym)
     }
 
+    /// Symbols have no size here, so only the closest one is a candidate.
+    #[cfg(feature = "std")]
+    pub fn search_symtab_candidates(&self, addr: u64, cb: &mut dyn FnMut(&[u8])) {
+        if let Some(name) = self.search_symtab(addr) {
+            cb(name);
+        }
+    }
+
     /// Try to load a context for an object file.
     ///
     /// If dsymutil was not run, then the DWARF may be found in the source object files.
@@ -284,6 +336,54 @@ impl<'a> Object<'a> {
     }
 }
 
+/// Collects the named definitions out of a symbol table, along with a rank
+/// of how good a name each one is for its address (see `symbol_rank`).
+fn defined_symbols<'a>(
+    nlists: impl Iterator<Item = &'a MachNlist>,
+    strings: StringTable<'a>,
+    endian: NativeEndian,
+) -> Vec<(&'a [u8], u64, u8)> {
+    nlists
+        .filter_map(|nlist| {
+            let name = nlist.name(endian, strings).ok()?;
+            if !name.is_empty() && nlist.is_definition() {
+                let addr = u64::from(nlist.n_value(endian));
+                Some((name, addr, symbol_rank(nlist, endian)))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Ranks a symbol for when several share the same address, lower is better.
+///
+/// Weak definitions (`N_WEAK_DEF`) are commonly emitted as aliases for C++
+/// template instantiations and inline functions, so we'd rather report the
+/// real external definition, then local and private external ones, and only
+/// fall back to a weak symbol if there's nothing else.
+fn symbol_rank(nlist: &MachNlist, endian: NativeEndian) -> u8 {
+    let n_type = nlist.n_type();
+    let external = n_type & macho::N_EXT != 0 && n_type & macho::N_PEXT == 0;
+    let weak = nlist.n_desc(endian) & macho::N_WEAK_DEF != 0;
+    match (weak, external) {
+        (false, true) => 0,
+        (false, false) => 1,
+        (true, true) => 2,
+        (true, false) => 3,
+    }
+}
+
+/// Sorts symbols for `search_symtab`, keeping only the best ranked symbol at
+/// each address so a binary search can't land on a worse alias.
+fn sort_by_address(mut syms: Vec<(&[u8], u64, u8)>) -> Vec<(&[u8], u64)> {
+    syms.sort_unstable_by_key(|&(_, addr, rank)| (addr, rank));
+    syms.dedup_by_key(|&mut (_, addr, _)| addr);
+    syms.into_iter()
+        .map(|(name, addr, _rank)| (name, addr))
+        .collect()
+}
+
 fn object_mapping(file: &object::read::ObjectMapFile<'_>) -> Option<Mapping> {
     use super::mystd::ffi::OsStr;
     use super::mystd::os::unix::prelude::*;
@@ -316,3 +416,44 @@ pub(super) fn handle_split_dwarf<'data>(
 ) -> Option<Arc<gimli::Dwarf<EndianSlice<'data, Endian>>>> {
     None
 }
+
+#[cfg(all(test, target_pointer_width = "64"))]
+mod tests {
+    use super::*;
+    use object::{U64Bytes, U16, U32};
+
+    fn nlist(n_strx: u32, n_type: u8, n_desc: u16, n_value: u64) -> MachNlist {
+        MachNlist {
+            n_strx: U32::new(NativeEndian, n_strx),
+            n_type,
+            n_sect: 1,
+            n_desc: U16::new(NativeEndian, n_desc),
+            n_value: U64Bytes::new(NativeEndian, n_value),
+        }
+    }
+
+    #[test]
+    fn prefers_strong_external_symbols() {
+        let strings = b"\0weak\0strong\0local\0pext\0undef\0";
+        let strings = StringTable::new(&strings[..], 0, strings.len() as u64);
+        let sect = macho::N_SECT;
+        let nlists = [
+            nlist(1, sect | macho::N_EXT, macho::N_WEAK_DEF, 0x1000),
+            nlist(6, sect | macho::N_EXT, 0, 0x1000),
+            nlist(13, sect, 0, 0x1000),
+            nlist(19, sect | macho::N_EXT | macho::N_PEXT, 0, 0x2000),
+            nlist(1, sect | macho::N_EXT, macho::N_WEAK_DEF, 0x2000),
+            nlist(24, macho::N_UNDF | macho::N_EXT, 0, 0),
+            nlist(1, sect | macho::N_EXT, macho::N_WEAK_DEF, 0x3000),
+        ];
+        let syms = sort_by_address(defined_symbols(nlists.iter(), strings, NativeEndian));
+        assert_eq!(
+            syms,
+            [
+                (&b"strong"[..], 0x1000),
+                (&b"pext"[..], 0x2000),
+                (&b"weak"[..], 0x3000),
+            ]
+        );
+    }
+}
@@ -1,12 +1,9 @@
-// Note: This file is only currently used on targets that call out to the code
-// in `mod libs_dl_iterate_phdr` (e.g. linux, freebsd, ...); it may be more
-// general purpose, but it hasn't been tested elsewhere.
+// Note: This file is used on targets that call out to the code in
+// `mod libs_dl_iterate_phdr` (e.g. linux, freebsd, ...) to read the maps of
+// the current process, and by `libs_core_file` to parse the maps of other
+// processes.
 
 use super::mystd::ffi::OsString;
-use super::mystd::fs::File;
-use super::mystd::io::Read;
-use alloc::string::String;
-use alloc::vec::Vec;
 use core::str::FromStr;
 
 #[derive(PartialEq, Eq, Debug)]
@@ -26,7 +23,9 @@ pub(super) struct MapsEntry {
     /// device (major, minor)
     dev: (usize, usize),
     /// inode on the device. 0 indicates that no inode is associated with the memory region (e.g. uninitalized data aka BSS).
-    inode: usize,
+    ///
+    /// Unlike the other numbers this is listed in decimal.
+    inode: u64,
     /// Usually the file backing the mapping.
     ///
     /// Note: The man page for proc includes a note about "coordination" by
@@ -55,36 +54,87 @@ pub(super) struct MapsEntry {
     pathname: OsString,
 }
 
-pub(super) fn parse_maps() -> Result<Vec<MapsEntry>, &'static str> {
-    let mut v = Vec::new();
-    let mut proc_self_maps =
-        File::open("/proc/self/maps").map_err(|_| "Couldn't open /proc/self/maps")?;
-    let mut buf = String::new();
-    let _bytes_read = proc_self_maps
-        .read_to_string(&mut buf)
-        .map_err(|_| "Couldn't read /proc/self/maps")?;
-    for line in buf.lines() {
-        v.push(line.parse()?);
-    }
-
-    Ok(v)
-}
-
 impl MapsEntry {
     pub(super) fn pathname(&self) -> &OsString {
         &self.pathname
     }
 
-    pub(super) fn ip_matches(&self, ip: usize) -> bool {
-        self.address.0 <= ip && ip < self.address.1
+    pub(super) fn address(&self) -> (usize, usize) {
+        self.address
     }
 
-    #[cfg(target_os = "android")]
     pub(super) fn offset(&self) -> u64 {
         self.offset
     }
 }
 
+// The maps of the current process are only read along with `dl_iterate_phdr`.
+cfg_if::cfg_if! {
+    if #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "fuchsia",
+            target_os = "freebsd",
+            target_os = "hurd",
+            target_os = "openbsd",
+            target_os = "netbsd",
+            target_os = "nto",
+            target_os = "android",
+        ),
+        not(target_env = "uclibc"),
+    ))] {
+        use super::mystd::fs::File;
+        use super::mystd::io::Read;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+
+        pub(super) fn parse_maps() -> Result<Vec<MapsEntry>, &'static str> {
+            let mut v = Vec::new();
+            let mut proc_self_maps =
+                File::open("/proc/self/maps").map_err(|_| "Couldn't open /proc/self/maps")?;
+            let mut buf = String::new();
+            let _bytes_read = proc_self_maps
+                .read_to_string(&mut buf)
+                .map_err(|_| "Couldn't read /proc/self/maps")?;
+            for line in buf.lines() {
+                v.push(line.parse()?);
+            }
+
+            Ok(v)
+        }
+
+        impl MapsEntry {
+            pub(super) fn ip_matches(&self, ip: usize) -> bool {
+                let (start, end) = self.address();
+                start <= ip && ip < end
+            }
+
+            // Only compared to files being opened on Linux and Android.
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            pub(super) fn inode(&self) -> u64 {
+                self.inode
+            }
+
+            /// Returns how to label addresses in this mapping if it's one of
+            /// the pseudo-paths which don't hold the code of any library, such
+            /// as the stack or the heap.
+            #[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+            pub(super) fn special_label(&self) -> Option<&'static str> {
+                let pathname = self.pathname.to_str()?;
+                match pathname {
+                    "[stack]" => Some("<stack>"),
+                    "[heap]" => Some("<heap data>"),
+                    "[vvar]" | "[vvar_vclock]" => Some("<vvar data>"),
+                    "[vdso]" => Some("<vdso>"),
+                    "[vsyscall]" => Some("<vsyscall>"),
+                    _ if pathname.starts_with("[stack:") => Some("<stack>"),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
 impl FromStr for MapsEntry {
     type Err = &'static str;
 
@@ -147,7 +197,7 @@ impl FromStr for MapsEntry {
         } else {
             return Err("Couldn't parse dev");
         };
-        let inode = hex(inode_str)?;
+        let inode = inode_str.parse().map_err(|_| "Couldn't parse inode")?;
         let pathname = pathname_str.into();
 
         Ok(MapsEntry {
@@ -175,7 +225,7 @@ fn check_maps_entry_parsing_64bit() {
             perms: ['-', '-', 'x', 'p'],
             offset: 0x00000000,
             dev: (0x00, 0x00),
-            inode: 0x0,
+            inode: 0,
             pathname: "[vsyscall]".into(),
         }
     );
@@ -190,7 +240,7 @@ fn check_maps_entry_parsing_64bit() {
             perms: ['r', 'w', '-', 'p'],
             offset: 0x00039000,
             dev: (0x103, 0x06),
-            inode: 0x76021795,
+            inode: 76021795,
             pathname: "/usr/lib/x86_64-linux-gnu/ld-linux-x86-64.so.2".into(),
         }
     );
@@ -203,12 +253,47 @@ fn check_maps_entry_parsing_64bit() {
             perms: ['r', 'w', '-', 'p'],
             offset: 0x00000000,
             dev: (0x00, 0x00),
-            inode: 0x0,
+            inode: 0,
             pathname: Default::default(),
         }
     );
 }
 
+#[test]
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+fn check_special_labels() {
+    let label = |line: &str| line.parse::<MapsEntry>().unwrap().special_label();
+    assert_eq!(
+        label("7ffd9c1d5000-7ffd9c1f6000 rw-p 00000000 00:00 0    [stack]"),
+        Some("<stack>")
+    );
+    assert_eq!(
+        label("7f5985e00000-7f5985e21000 rw-p 00000000 00:00 0    [stack:1234]"),
+        Some("<stack>")
+    );
+    assert_eq!(
+        label("08056000-08077000 rw-p 00000000 00:00 0    [heap]"),
+        Some("<heap data>")
+    );
+    assert_eq!(
+        label("7ffd9c1f8000-7ffd9c1fc000 r--p 00000000 00:00 0    [vvar]"),
+        Some("<vvar data>")
+    );
+    assert_eq!(
+        label("7ffd9c1fc000-7ffd9c1fe000 r-xp 00000000 00:00 0    [vdso]"),
+        Some("<vdso>")
+    );
+    assert_eq!(
+        label("7f5985e00000-7f5985e21000 rw-p 00000000 00:00 0    [anon:scudo]"),
+        None
+    );
+    assert_eq!(label("35b1a21000-35b1a22000 rw-p 00000000 00:00 0"), None);
+    assert_eq!(
+        label("b7c79000-b7e02000 r--p 00000000 08:01 60662705   /usr/lib/[heap]"),
+        None
+    );
+}
+
 // (This output was taken from a 32-bit machine, but will work on any target)
 #[test]
 fn check_maps_entry_parsing_32bit() {
@@ -227,7 +312,7 @@ fn check_maps_entry_parsing_32bit() {
             perms: ['r', 'w', '-', 'p'],
             offset: 0x00000000,
             dev: (0x00, 0x00),
-            inode: 0x0,
+            inode: 0,
             pathname: "[heap]".into(),
         }
     );
@@ -242,7 +327,7 @@ fn check_maps_entry_parsing_32bit() {
             perms: ['r', '-', '-', 'p'],
             offset: 0x00000000,
             dev: (0x08, 0x01),
-            inode: 0x60662705,
+            inode: 60662705,
             pathname: "/usr/lib/locale/locale-archive".into(),
         }
     );
@@ -255,7 +340,7 @@ fn check_maps_entry_parsing_32bit() {
             perms: ['r', 'w', '-', 'p'],
             offset: 0x00000000,
             dev: (0x00, 0x00),
-            inode: 0x0,
+            inode: 0,
             pathname: Default::default(),
         }
     );
@@ -269,7 +354,7 @@ fn check_maps_entry_parsing_32bit() {
             perms: ['r', '-', '-', 'p'],
             offset: 0x00000000,
             dev: (0x08, 0x01),
-            inode: 0x60662705,
+            inode: 60662705,
             pathname: "/executable/path/with some spaces".into(),
         }
     );
@@ -283,7 +368,7 @@ fn check_maps_entry_parsing_32bit() {
             perms: ['r', '-', '-', 'p'],
             offset: 0x00000000,
             dev: (0x08, 0x01),
-            inode: 0x60662705,
+            inode: 60662705,
             pathname: "/executable/path/with  multiple-continuous    spaces  ".into(),
         }
     );
@@ -297,7 +382,7 @@ fn check_maps_entry_parsing_32bit() {
             perms: ['r', '-', '-', 'p'],
             offset: 0x00000000,
             dev: (0x08, 0x01),
-            inode: 0x60662705,
+            inode: 60662705,
             pathname: "/executable/path/starts-with-spaces".into(),
         }
     );
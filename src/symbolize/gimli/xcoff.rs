@@ -40,6 +40,12 @@ impl Mapping {
             }
         })
     }
+
+    /// Loads the object file `member` out of the archive at `path`.
+    #[cfg(feature = "std")]
+    pub fn new_archive_member(path: &Path, member: &[u8]) -> Option<Mapping> {
+        Mapping::new(path, OsStr::from_bytes(member))
+    }
 }
 
 struct ParsedSym<'a> {
@@ -103,6 +109,11 @@ pub fn parse_image(path: &Path, member_name: &OsStr) -> Option<Image> {
 }
 
 impl<'a> Object<'a> {
+    pub fn dwarf_endian(&self) -> Endian {
+        // XCOFF files are always big-endian.
+        Endian::Big
+    }
+
     fn get_concrete_size(file: &XcoffFile<'a, Xcoff>, sym: &XcoffSymbol<'a, '_, Xcoff>) -> u64 {
         match sym.flags() {
             SymbolFlags::Xcoff {
@@ -174,6 +185,14 @@ impl<'a> Object<'a> {
         }
     }
 
+    /// Symbols don't overlap here, so only the closest one is a candidate.
+    #[cfg(feature = "std")]
+    pub fn search_symtab_candidates(&self, addr: u64, cb: &mut dyn FnMut(&[u8])) {
+        if let Some(name) = self.search_symtab(addr) {
+            cb(name);
+        }
+    }
+
     pub(super) fn search_object_map(&self, _addr: u64) -> Option<(&Context<'_>, u64)> {
         None
     }
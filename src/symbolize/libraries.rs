@@ -0,0 +1,146 @@
+//! Listing the libraries loaded into the current process, see
+//! `native_libraries`.
+
+use super::imp;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+
+/// A library (or executable) loaded into the current process, as returned
+/// from `native_libraries`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone, Debug)]
+pub struct Library {
+    name: PathBuf,
+    segments: Vec<LibrarySegment>,
+    bias: usize,
+}
+
+impl Library {
+    // Only the DbgHelp and gimli backends list libraries, see `native_libraries`.
+    #[cfg(all(
+        not(miri),
+        any(
+            all(windows, target_env = "msvc", not(target_vendor = "uwp")),
+            all(
+                any(unix, all(windows, target_env = "gnu")),
+                not(target_vendor = "uwp"),
+                not(target_os = "emscripten"),
+                any(not(backtrace_in_libstd), feature = "backtrace"),
+            ),
+        ),
+    ))]
+    pub(crate) fn new(name: PathBuf, segments: Vec<LibrarySegment>, bias: usize) -> Library {
+        Library {
+            name,
+            segments,
+            bias,
+        }
+    }
+
+    /// Returns the path the library was loaded from.
+    ///
+    /// This is the path as the platform reports it, which isn't necessarily
+    /// absolute. On Android, libraries loaded straight out of an APK have
+    /// paths like `/path/to/my.apk!/lib/mylib.so`.
+    pub fn name(&self) -> &Path {
+        &self.name
+    }
+
+    /// Returns the segments of the library loaded into memory.
+    pub fn segments(&self) -> &[LibrarySegment] {
+        &self.segments
+    }
+
+    /// Returns the bias of the library, the difference between the address
+    /// each of its segments is loaded at and the address it was linked at.
+    ///
+    /// With the default symbolication backend, subtracting this from an
+    /// address within the library gives the address to look up in its debug
+    /// information. With DbgHelp on Windows the bias is the address the
+    /// module is loaded at, so subtracting it gives the address relative to
+    /// the module's base (its RVA), which is what PDB files are keyed by.
+    pub fn bias(&self) -> usize {
+        self.bias
+    }
+}
+
+/// A segment of a `Library` loaded into memory.
+///
+/// # Required features
+///
+/// This type requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Copy, Clone, Debug)]
+pub struct LibrarySegment {
+    stated_virtual_memory_address: usize,
+    size: usize,
+}
+
+impl LibrarySegment {
+    #[cfg(all(
+        not(miri),
+        any(
+            all(windows, target_env = "msvc", not(target_vendor = "uwp")),
+            all(
+                any(unix, all(windows, target_env = "gnu")),
+                not(target_vendor = "uwp"),
+                not(target_os = "emscripten"),
+                any(not(backtrace_in_libstd), feature = "backtrace"),
+            ),
+        ),
+    ))]
+    pub(crate) fn new(stated_virtual_memory_address: usize, size: usize) -> LibrarySegment {
+        LibrarySegment {
+            stated_virtual_memory_address,
+            size,
+        }
+    }
+
+    /// Returns the address the segment was linked at. Adding the bias of its
+    /// library gives the address it's loaded at.
+    ///
+    /// With DbgHelp on Windows, where each module is listed as a single
+    /// segment spanning its whole image, this is always zero, as the
+    /// addresses within the module are relative to its base there.
+    pub fn stated_virtual_memory_address(&self) -> usize {
+        self.stated_virtual_memory_address
+    }
+
+    /// Returns the size of the segment in memory, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Lists the libraries currently loaded into this process, including the
+/// executable itself.
+///
+/// This is meant for tools which need to know which modules a backtrace's
+/// addresses may be in, for example to fetch their debug information from a
+/// symbol server. The libraries are listed afresh on every call.
+///
+/// With DbgHelp on Windows each module is listed with a single segment
+/// spanning its whole image, and a bias of the address it's loaded at. With
+/// the default symbolication backend on platforms where libraries can't be
+/// listed, and on Miri, nothing is returned.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// for library in backtrace::native_libraries() {
+///     println!("{} at {:#x}", library.name().display(), library.bias());
+/// }
+/// ```
+pub fn native_libraries() -> Vec<Library> {
+    let _guard = crate::lock::lock();
+    unsafe { imp::loaded_libraries() }
+}
@@ -2,13 +2,14 @@ use core::{fmt, str};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
-        use std::path::Path;
+        use std::path::{Path, PathBuf};
         use std::prelude::v1::*;
     }
 }
 
 use super::backtrace::Frame;
 use super::types::BytesOrWideString;
+use alloc::string::String;
 use core::ffi::c_void;
 use rustc_demangle::{try_demangle, Demangle};
 
@@ -105,6 +106,217 @@ pub fn resolve_frame<F: FnMut(&Symbol)>(frame: &Frame, cb: F) {
     unsafe { resolve_frame_unsynchronized(frame, cb) }
 }
 
+/// Resolves an address to just its source location, as a filename, line
+/// number and optional column number.
+///
+/// This is meant for tools which only care about where in the source code an
+/// address is, such as coverage and line profilers. Where possible only the
+/// line number tables of the debug information are consulted, skipping the
+/// lookup of function names and inlined frames which `resolve` does, so it's
+/// cheaper. The location returned is that of the innermost inlined function,
+/// the same as the first symbol `resolve` yields.
+///
+/// Like `resolve`, `addr` is assumed to be a return address, so the location
+/// of the instruction before it is returned. `None` is returned if no
+/// location information was found for the address.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// backtrace::trace(|frame| {
+///     if let Some((file, line, _column)) = backtrace::resolve_location(frame.ip()) {
+///         println!("{}:{line}", file.display());
+///     }
+///     false // only look at the top frame
+/// });
+/// ```
+#[cfg(feature = "std")]
+pub fn resolve_location(addr: *mut c_void) -> Option<(PathBuf, u32, Option<u32>)> {
+    let _guard = crate::lock::lock();
+    unsafe { imp::resolve_location(ResolveWhat::Address(addr)) }
+}
+
+/// Resolves an address to just the name of its function, looked up in the
+/// symbol table, passing the symbol to the closure.
+///
+/// `resolve` maps the whole file of a library, debug information included,
+/// and parses some of the debug information up front. For large binaries
+/// that can take a lot of memory when only names are wanted, such as in
+/// memory-capped environments. Instead this only reads the symbol table and
+/// its string table out of the file and keeps just those, so the symbol
+/// passed to the closure has a name and address but no source location, and
+/// functions inlined into others aren't found.
+///
+/// Symbol tables are only read on their own for ELF files, of libraries not
+/// inside an APK on Android. Separate debug info files aren't consulted, so
+/// stripped libraries only have the names of their dynamic symbol table.
+/// Elsewhere this is the same as `resolve`. Like `resolve`, `addr` is
+/// assumed to be a return address.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// backtrace::trace(|frame| {
+///     backtrace::resolve_name_only(frame.ip(), |symbol| {
+///         println!("{:?}", symbol.name());
+///     });
+///     false // only look at the top frame
+/// });
+/// ```
+#[cfg(feature = "std")]
+pub fn resolve_name_only<F: FnMut(&Symbol)>(addr: *mut c_void, mut cb: F) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::resolve_name_only(ResolveWhat::Address(addr), &mut cb) }
+}
+
+/// Resolves an address to every symbol of the symbol table it could belong to,
+/// passing each to the closure.
+///
+/// `resolve` picks a single symbol for an address, which isn't always the
+/// only plausible one: symbols may overlap, several names may alias the
+/// same code, and an address at the very end of one function is also at the
+/// start of the next. This is meant for tools, such as for binary analysis,
+/// which would rather see that ambiguity and disambiguate themselves.
+///
+/// Only the symbol table is consulted, so the symbols have names but no
+/// source locations, and inlined functions aren't found. Symbols are yielded
+/// closest first, so the first one is usually the name `resolve` finds too.
+/// This looks at every symbol of the library, so it's much slower than
+/// `resolve`.
+///
+/// Overlapping symbols are only looked for in ELF files, elsewhere only the
+/// closest symbol is found, and backends which don't read symbol tables
+/// themselves yield the same symbols as `resolve`. Like `resolve`, `addr` is
+/// assumed to be a return address.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// backtrace::trace(|frame| {
+///     backtrace::resolve_candidates(frame.ip(), |symbol| {
+///         println!("could be {:?}", symbol.name());
+///     });
+///     false // only look at the top frame
+/// });
+/// ```
+#[cfg(feature = "std")]
+pub fn resolve_candidates<F: FnMut(&Symbol)>(addr: *mut c_void, mut cb: F) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::resolve_candidates(ResolveWhat::Address(addr), &mut cb) }
+}
+
+/// Resolves a batch of addresses at once, passing each symbol to the closure
+/// along with the index of its address in `addrs`.
+///
+/// This is the same as calling `resolve` for each address in turn, but looks
+/// up which library each address is in just once, and then resolves all the
+/// addresses of one library before moving on to the next. Resolving the
+/// hundreds of addresses of a deep stack, which are mostly in the same few
+/// libraries, is faster this way as the debug information of each library is
+/// looked at once, rather than possibly being evicted from the cache and
+/// loaded again in between.
+///
+/// Symbols are passed in order of library rather than of the addresses,
+/// though the symbols of any one address are passed together and in the
+/// same order as by `resolve`. Like `resolve`, the addresses are assumed to
+/// be return addresses.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// let bt = backtrace::Backtrace::new_unresolved();
+/// let ips = bt.frames().iter().map(|f| f.ip()).collect::<Vec<_>>();
+/// let mut names = vec![Vec::new(); ips.len()];
+/// backtrace::resolve_batch(&ips, |i, symbol| {
+///     names[i].push(symbol.name().map(|name| name.to_string()));
+/// });
+/// ```
+#[cfg(feature = "std")]
+pub fn resolve_batch<F: FnMut(usize, &Symbol)>(addrs: &[*mut c_void], mut cb: F) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::resolve_batch(addrs, &mut cb) }
+}
+
+/// Resolves each of `addrs` in turn, for backends which have no faster way to
+/// resolve them together.
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+unsafe fn resolve_each(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &Symbol)) {
+    for (i, &addr) in addrs.iter().enumerate() {
+        unsafe { imp::resolve(ResolveWhat::Address(addr), &mut |symbol| cb(i, symbol)) }
+    }
+}
+
+/// Finds the location of the first symbol `resolve` yields, for backends which
+/// have no cheaper way to find just the location.
+#[cfg(all(
+    feature = "std",
+    any(miri, all(windows, target_env = "msvc", not(target_vendor = "uwp")))
+))]
+unsafe fn location_of_first_symbol(what: ResolveWhat<'_>) -> Option<(PathBuf, u32, Option<u32>)> {
+    let mut location = None;
+    let mut first = true;
+    imp::resolve(what, &mut |symbol| {
+        if first {
+            first = false;
+            if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                location = Some((file.to_path_buf(), line, symbol.colno()));
+            }
+        }
+    });
+    location
+}
+
+/// Resolves an address within an object file inside of a static library (a
+/// `.a` archive), passing the symbol to the specified closure.
+///
+/// This is meant for offline analysis, for example to find which of the
+/// objects contributing to a statically linked binary some code came from,
+/// when only the original object files have the needed debug information.
+/// The `member` of `archive` is loaded and `svma` is looked up in it, where
+/// `svma` is an address in the object file's own address space (not that of
+/// the binary it was linked into, nor where it's loaded in memory). Unlike
+/// `resolve`, no adjustment is made to the address. The closure isn't called
+/// if the archive or member couldn't be loaded or nothing was found.
+///
+/// Note that the debug information in ELF object files can't be used since
+/// it needs relocations applied, so only names from the symbol table are
+/// available for those. Also, sections in such object files haven't been
+/// assigned addresses yet, so `svma` is effectively an offset within the
+/// section containing the code. Mach-O object files have no such caveats.
+///
+/// Only gimli currently supports this, and only for ELF, Mach-O and XCOFF
+/// archives.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn resolve_in_archive<F: FnMut(&Symbol)>(archive: &Path, member: &str, svma: u64, mut cb: F) {
+    imp::resolve_in_archive(archive, member.as_bytes(), svma, &mut cb)
+}
+
 pub enum ResolveWhat<'a> {
     Address(*mut c_void),
     Frame(&'a Frame),
@@ -115,6 +327,7 @@ impl<'a> ResolveWhat<'a> {
     fn address_or_ip(&self) -> *mut c_void {
         match self {
             ResolveWhat::Address(a) => adjust_ip(*a),
+            ResolveWhat::Frame(f) if f.inner.ip_is_exact() => f.ip(),
             ResolveWhat::Frame(f) => adjust_ip(f.ip()),
         }
     }
@@ -137,7 +350,9 @@ impl<'a> ResolveWhat<'a> {
 //
 // For now though this is a pretty niche concern so we just internally always
 // subtract one. Consumers should keep working and getting pretty good results,
-// so we should be good enough.
+// so we should be good enough. The one exception is frames whose ip is known
+// to be exact, such as the frame a signal interrupted, which is executing the
+// instruction at its ip rather than returning to it.
 fn adjust_ip(a: *mut c_void) -> *mut c_void {
     if a.is_null() {
         a
@@ -208,6 +423,68 @@ impl Symbol {
         self.inner.name()
     }
 
+    /// Returns the linkage name of this function, the name the linker and
+    /// symbol tables know it by.
+    ///
+    /// For Rust and C++ this is the mangled name. This is usually the same
+    /// symbol that `name` returns, but this returns `None` rather than falling
+    /// back to a different name if the debug information doesn't record a
+    /// linkage name (as is the case for C functions, for example).
+    ///
+    /// Only gimli currently provides a value here.
+    pub fn linkage_name(&self) -> Option<SymbolName<'_>> {
+        self.inner.linkage_name()
+    }
+
+    /// Returns the source-level name of this function, as it was written in
+    /// the source code.
+    ///
+    /// This is the unqualified identifier from the debug information (DWARF's
+    /// `DW_AT_name`), for example `foo` for `my_crate::module::foo` or
+    /// `{closure#0}` for a Rust closure. It's useful for matching against
+    /// identifiers in source code, whereas `name` is better for display.
+    ///
+    /// Only gimli currently provides a value here, and only if debug
+    /// information is available.
+    pub fn source_name(&self) -> Option<SymbolName<'_>> {
+        self.inner.source_name()
+    }
+
+    /// Returns the name of the function this frame was calling, according to
+    /// the call site information in the debug information.
+    ///
+    /// This is useful for spotting frames missing from a backtrace because of
+    /// tail calls. When a function ends by calling another one, optimized code
+    /// often jumps to it instead, reusing the caller's frame, so the caller
+    /// never shows up in a backtrace. If the frame above this one in the
+    /// backtrace isn't the function named here, then the frames in between
+    /// were lost to tail calls (or the call was into a function with a
+    /// different name, like a trait object's method).
+    ///
+    /// This is only meaningful for the innermost symbol resolved for a frame,
+    /// as that's the function the call was made from, and only for frames
+    /// that are returned to rather than the frame which was executing.
+    ///
+    /// Compilers only emit call site information for optimized code, and not
+    /// for indirect calls. Only gimli currently provides a value here.
+    pub fn call_target(&self) -> Option<SymbolName<'_>> {
+        self.inner.call_target()
+    }
+
+    /// Returns whether the debug information says this function was compiled
+    /// with optimizations, if it says anything about that.
+    ///
+    /// This is `Some(true)` for functions inlined into their caller, and
+    /// otherwise based on how the compiler describes the compilation unit the
+    /// function is in: the optimization flags it records in `DW_AT_producer`
+    /// (which GCC and Clang do, but rustc doesn't) or Apple's
+    /// `DW_AT_APPLE_optimized` flag. This is `None` if neither is available.
+    ///
+    /// Only gimli currently provides a value here.
+    pub fn is_optimized(&self) -> Option<bool> {
+        self.inner.is_optimized()
+    }
+
     /// Returns the starting address of this function.
     pub fn addr(&self) -> Option<*mut c_void> {
         self.inner.addr()
@@ -251,6 +528,72 @@ impl Symbol {
     pub fn filename(&self) -> Option<&Path> {
         self.inner.filename()
     }
+
+    /// Returns the path of the library (or executable) this symbol was found
+    /// in, like the `in libfoo.so` gdb shows for a frame.
+    ///
+    /// This is the path the library was loaded from, see
+    /// `Library::name`. It's currently only known with the default
+    /// symbolication backend on Unix and MinGW, and not for code registered by
+    /// JIT compilers. Elsewhere this returns `None`.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[cfg(feature = "std")]
+    pub fn module(&self) -> Option<&Path> {
+        self.inner.module()
+    }
+
+    /// Returns whether this is one of the functions this crate itself goes
+    /// through to capture or resolve a backtrace, such as `trace`,
+    /// `Backtrace::new` or `resolve`, or the platform unwinder they call.
+    ///
+    /// These frames usually show up at the top of a backtrace and are rarely
+    /// of interest, so custom formatters can use this to leave them out. This
+    /// is based on the symbol's name, so it's `false` if the name isn't known.
+    pub fn is_backtrace_internal(&self) -> bool {
+        self.name().is_some_and(|name| is_backtrace_internal(&name))
+    }
+}
+
+/// Returns whether `name` is a function of this crate's capturing and
+/// resolving machinery, see `Symbol::is_backtrace_internal`.
+pub(crate) fn is_backtrace_internal(name: &SymbolName<'_>) -> bool {
+    // Just enough of the demangled name to compare the paths below against.
+    struct Prefix {
+        buf: [u8; 48],
+        len: usize,
+    }
+
+    impl fmt::Write for Prefix {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let n = s.len().min(self.buf.len() - self.len);
+            self.buf[self.len..][..n].copy_from_slice(&s.as_bytes()[..n]);
+            self.len += n;
+            Ok(())
+        }
+    }
+
+    if let b"_Unwind_Backtrace" | b"unw_backtrace" = name.as_bytes() {
+        return true;
+    }
+    let mut prefix = Prefix {
+        buf: [0; 48],
+        len: 0,
+    };
+    let _ = fmt::write(&mut prefix, format_args!("{name:#}"));
+    // Trait impls demangle as `<backtrace::capture::Backtrace as Trait>::f`.
+    let path = prefix.buf[..prefix.len]
+        .strip_prefix(b"<")
+        .unwrap_or(&prefix.buf[..prefix.len]);
+    // This crate is also built into std as `std::backtrace_rs`.
+    let path = path
+        .strip_prefix(b"backtrace::")
+        .or_else(|| path.strip_prefix(b"std::backtrace_rs::"));
+    let modules: [&[u8]; 3] = [b"backtrace::", b"capture::", b"symbolize::"];
+    path.is_some_and(|path| modules.iter().any(|m| path.starts_with(m)))
 }
 
 impl fmt::Debug for Symbol {
@@ -295,6 +638,49 @@ cfg_if::cfg_if! {
     }
 }
 
+#[cfg(feature = "std")]
+type DemangleFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+#[cfg(feature = "std")]
+static DEMANGLER: crate::hook::Hook<DemangleFn> = crate::hook::Hook::new();
+
+/// Installs `demangler` to demangle the symbol names this crate doesn't know
+/// how to demangle by itself, such as those of Swift or D.
+///
+/// `demangler` is given the raw name of a symbol and returns its demangled
+/// name, or `None` if it doesn't recognize the name. It's consulted by the
+/// `Display` and `Debug` implementations of `SymbolName` for every name which
+/// isn't a Rust symbol name, before the name is tried as a C++ one with the
+/// `cpp_demangle` feature. Without a custom demangler names are demangled as
+/// Rust and then C++ names, as usual.
+///
+/// Installing another demangler replaces this one, which is dropped once no
+/// name is being demangled with it anymore.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// backtrace::set_custom_demangler(|name| {
+///     // Hand Swift names off to a Swift demangler here.
+///     name.strip_prefix("$s").map(|rest| format!("swift: {rest}"))
+/// });
+///
+/// let name = backtrace::SymbolName::new(b"$s4main3fooyyF");
+/// assert_eq!(name.to_string(), "swift: 4main3fooyyF");
+/// ```
+#[cfg(feature = "std")]
+pub fn set_custom_demangler<F>(demangler: F)
+where
+    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    DEMANGLER.set(std::sync::Arc::new(demangler));
+}
+
 /// A wrapper around a symbol name to provide ergonomic accessors to the
 /// demangled name, the raw bytes, the raw string, etc.
 pub struct SymbolName<'a> {
@@ -302,6 +688,8 @@ pub struct SymbolName<'a> {
     demangled: Option<Demangle<'a>>,
     #[cfg(feature = "cpp_demangle")]
     cpp_demangled: OptionCppSymbol<'a>,
+    // A demangled name rendered ahead of time, see `Backtrace::demangle_names`.
+    rendered: Option<&'a str>,
 }
 
 impl<'a> SymbolName<'a> {
@@ -322,9 +710,48 @@ impl<'a> SymbolName<'a> {
             demangled,
             #[cfg(feature = "cpp_demangle")]
             cpp_demangled: cpp,
+            rendered: None,
         }
     }
 
+    /// Creates a symbol name whose demangled form, `rendered`, is already
+    /// known, so that it isn't demangled again.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_rendered(bytes: &'a [u8], rendered: &'a str) -> SymbolName<'a> {
+        SymbolName {
+            bytes,
+            demangled: None,
+            #[cfg(feature = "cpp_demangle")]
+            cpp_demangled: OptionCppSymbol::none(),
+            rendered: Some(rendered),
+        }
+    }
+
+    /// Renders the name if it's a C++ one, which is much slower to demangle
+    /// than a Rust one.
+    #[cfg(feature = "parallel-demangle")]
+    pub(crate) fn render_cpp(&self) -> Option<String> {
+        use std::fmt::Write;
+
+        let cpp = self.cpp_demangled.0.as_ref()?;
+        let mut s = String::new();
+        write!(s, "{cpp}").ok()?;
+        Some(s)
+    }
+
+    /// Demangles the name with the demangler installed with
+    /// `set_custom_demangler`, if any, unless it's a Rust name.
+    fn custom_demangled(&self) -> Option<String> {
+        if self.demangled.is_some() {
+            return None;
+        }
+        #[cfg(feature = "std")]
+        if let Some(demangler) = DEMANGLER.get() {
+            return demangler(str::from_utf8(self.bytes).ok()?);
+        }
+        None
+    }
+
     /// Returns the raw (mangled) symbol name as a `str` if the symbol is valid utf-8.
     ///
     /// Use the `Display` implementation if you want the demangled version.
@@ -339,6 +766,81 @@ impl<'a> SymbolName<'a> {
     pub fn as_bytes(&self) -> &'a [u8] {
         self.bytes
     }
+
+    /// Returns whether the name was demangled, as a Rust name, a C++ name with
+    /// the `cpp_demangle` feature, or by the demangler installed with
+    /// `set_custom_demangler`.
+    ///
+    /// The `Display` implementation falls back to the raw name if it couldn't
+    /// be demangled, which this tells apart from a demangled one, for example
+    /// to render raw names differently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::SymbolName;
+    ///
+    /// assert!(SymbolName::new(b"_ZN3foo3barE").is_demangled());
+    /// assert!(!SymbolName::new(b"foo_bar").is_demangled());
+    /// ```
+    pub fn is_demangled(&self) -> bool {
+        #[cfg(feature = "cpp_demangle")]
+        {
+            if self.cpp_demangled.0.is_some() {
+                return true;
+            }
+        }
+        self.demangled.is_some() || self.rendered.is_some() || self.custom_demangled().is_some()
+    }
+
+    /// Returns the demangled name without the hash at the end of Rust symbol
+    /// names, or `None` if the name couldn't be demangled.
+    ///
+    /// This is the name the alternate format (`{:#}`) of the `Display`
+    /// implementation prints, so the `.llvm.<digits>` suffix LLVM adds to some
+    /// functions is left out as well, for C++ names too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::SymbolName;
+    ///
+    /// let name = SymbolName::new(b"_ZN4core3ptr13drop_in_place17h0123456789abcdefE");
+    /// assert_eq!(name.demangled_no_hash().unwrap(), "core::ptr::drop_in_place");
+    /// assert_eq!(SymbolName::new(b"foo_bar").demangled_no_hash(), None);
+    /// ```
+    pub fn demangled_no_hash(&self) -> Option<String> {
+        if !self.is_demangled() {
+            return None;
+        }
+        Some(alloc::format!("{self:#}"))
+    }
+
+    /// Returns the length in bytes of the raw (mangled) symbol name.
+    ///
+    /// Note that this is the length of the name as stored in the binary, not
+    /// of the demangled name printed by the `Display` implementation, which
+    /// can be shorter (dropping a Rust symbol's hash) or longer. No
+    /// demangling or formatting happens to compute this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::SymbolName;
+    ///
+    /// let name = SymbolName::new(b"_ZN4core3ptr13drop_in_place17h0123456789abcdefE");
+    /// assert_eq!(name.len(), 47);
+    /// assert_eq!(name.to_string(), "core::ptr::drop_in_place::h0123456789abcdef");
+    /// assert_eq!(name.to_string().len(), 43);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns whether the raw symbol name is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
 }
 
 fn format_symbol_name(
@@ -365,27 +867,119 @@ fn format_symbol_name(
     Ok(())
 }
 
+/// Strips the `.llvm.<digits>` suffix LLVM appends to the names of functions
+/// it internalized during ThinLTO, which is just noise to a reader.
+fn strip_llvm_suffix(name: &str) -> &str {
+    fn is_suffix(digits: &str) -> bool {
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    if let Some(i) = name.rfind(".llvm.") {
+        if is_suffix(&name[i + ".llvm.".len()..]) {
+            return &name[..i];
+        }
+    }
+    // `cpp_demangle` renders the suffix as a clone of the function.
+    if let Some(rest) = name.strip_suffix(']') {
+        if let Some(i) = rest.rfind(" [clone .llvm.") {
+            if is_suffix(&rest[i + " [clone .llvm.".len()..]) {
+                return &name[..i];
+            }
+        }
+    }
+    name
+}
+
+/// Displays the demangled name, or the raw name if it couldn't be demangled.
+///
+/// The alternate format (`{:#}`), which is what `PrintFmt::Short` uses, leaves
+/// out the hash at the end of Rust symbol names and the `.llvm.<digits>`
+/// suffix LLVM adds to some functions during ThinLTO.
 impl<'a> fmt::Display for SymbolName<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `rustc-demangle` already strips LLVM's suffixes from Rust symbols.
         if let Some(ref s) = self.demangled {
             return s.fmt(f);
         }
 
+        if let Some(s) = self.custom_demangled() {
+            if f.alternate() {
+                return f.write_str(strip_llvm_suffix(&s));
+            }
+            return f.write_str(&s);
+        }
+
+        if let Some(s) = self.rendered {
+            if f.alternate() {
+                return f.write_str(strip_llvm_suffix(s));
+            }
+            return f.write_str(s);
+        }
+
         #[cfg(feature = "cpp_demangle")]
         {
             if let Some(ref cpp) = self.cpp_demangled.0 {
+                if f.alternate() {
+                    use alloc::string::ToString;
+                    return f.write_str(strip_llvm_suffix(&cpp.to_string()));
+                }
                 return cpp.fmt(f);
             }
         }
 
+        if f.alternate() {
+            if let Ok(name) = str::from_utf8(self.bytes) {
+                return f.write_str(strip_llvm_suffix(name));
+            }
+        }
         format_symbol_name(fmt::Display::fmt, self.bytes, f)
     }
 }
 
+/// Shows both the raw symbol name and, if it could be demangled, the demangled
+/// name, for example
+/// `SymbolName { raw: "_ZN3foo3barE", demangled: "foo::bar" }`.
 impl<'a> fmt::Debug for SymbolName<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Raw<'a>(&'a [u8]);
+
+        impl fmt::Debug for Raw<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("\"")?;
+                format_symbol_name(escape_debug, self.0, f)?;
+                f.write_str("\"")
+            }
+        }
+
+        struct Demangled<'a, T>(&'a T);
+
+        impl<T: fmt::Display> fmt::Debug for Demangled<'_, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                struct Escape<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+                impl fmt::Write for Escape<'_, '_> {
+                    fn write_str(&mut self, s: &str) -> fmt::Result {
+                        escape_debug(s, self.0)
+                    }
+                }
+
+                f.write_str("\"")?;
+                fmt::write(&mut Escape(f), format_args!("{}", self.0))?;
+                f.write_str("\"")
+            }
+        }
+
+        let mut d = f.debug_struct("SymbolName");
+        d.field("raw", &Raw(self.bytes));
         if let Some(ref s) = self.demangled {
-            return s.fmt(f);
+            d.field("demangled", &Demangled(s));
+        }
+        if let Some(s) = self.custom_demangled() {
+            d.field("demangled", &Demangled(&s));
+            return d.finish();
+        }
+        if let Some(ref s) = self.rendered {
+            d.field("demangled", &Demangled(s));
         }
 
         #[cfg(all(feature = "std", feature = "cpp_demangle"))]
@@ -393,20 +987,134 @@ impl<'a> fmt::Debug for SymbolName<'a> {
             use std::fmt::Write;
 
             // This may to print if the demangled symbol isn't actually
-            // valid, so handle the error here gracefully by not propagating
-            // it outwards.
+            // valid, so handle the error here gracefully by leaving out the
+            // demangled name.
             if let Some(ref cpp) = self.cpp_demangled.0 {
                 let mut s = String::new();
                 if write!(s, "{cpp}").is_ok() {
-                    return s.fmt(f);
+                    d.field("demangled", &Demangled(&s));
                 }
             }
         }
 
-        format_symbol_name(fmt::Debug::fmt, self.bytes, f)
+        d.finish()
     }
 }
 
+fn escape_debug(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    s.chars()
+        .flat_map(char::escape_debug)
+        .try_for_each(|c| fmt::Write::write_char(f, c))
+}
+
+/// Loads and caches the debug information of the loaded library at `path`
+/// ahead of time, returning whether any debug information was found for it.
+///
+/// Symbolication normally loads the debug information of a library the first
+/// time an address in it is resolved, which for a large library can make
+/// that first backtrace slow. A plugin host, for example, can call this
+/// right after loading a plugin so that the first error in the plugin is
+/// symbolicated quickly.
+///
+/// The library must already be loaded into the process, and `path` is
+/// matched against the paths libraries were loaded from, following
+/// symlinks if needed. `false` is returned if no loaded library matches, if
+/// its debug information can't be found, and on backends which don't keep a
+/// cache per library, such as on Windows with dbghelp. Only a few libraries
+/// are cached at a time, so the preloaded one may be evicted again before
+/// it's used if many other libraries are symbolicated in between. Debug
+/// information which is only found later, such as that of the object files
+/// of a macOS debug map, isn't counted.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// let plugin = Path::new("/usr/lib/my-app/plugins/libplugin.so");
+/// // ... after loading the plugin with `dlopen` ...
+/// if !backtrace::preload_library(plugin) {
+///     eprintln!("no debug info for the plugin, backtraces will lack line numbers");
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn preload_library(path: &Path) -> bool {
+    let _guard = crate::lock::lock();
+    unsafe { imp::preload_library(path) }
+}
+
+/// Looks up code generated by JIT compilers in the objects they register for
+/// debuggers through the GDB JIT interface, on Linux and Android.
+///
+/// Code generated at runtime isn't part of any library, so it's otherwise left
+/// without symbols. Once this is called, addresses outside of every library
+/// are looked up in the objects listed by `__jit_debug_descriptor`, if the
+/// process exports one. This does nothing on other platforms.
+///
+/// # Safety
+///
+/// The interface is meant to be read by a debugger while the process is
+/// stopped, and JITs link and unlink objects without any lock this crate
+/// could take. From the time this is called, no JIT in the process may
+/// register or unregister an object while an address is being symbolicated
+/// on any thread, as that would read memory the JIT may have freed. This
+/// holds if, for example, JIT compilation is finished before backtraces are
+/// resolved, or is paused while they are.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub unsafe fn enable_gdb_jit() {
+    unsafe { imp::enable_gdb_jit() }
+}
+
+/// Registers a module which was mapped into memory by hand, rather than
+/// loaded by the OS, so that addresses in it can be symbolicated.
+///
+/// On Windows, DLLs loaded from memory by a reflective or packed loader are
+/// unknown to `dbghelp`, which only finds the modules loaded with
+/// `LoadLibrary`, so their frames don't resolve. This registers the module
+/// mapped at `base`, spanning `size` bytes, with `dbghelp` through
+/// `SymLoadModuleExW`. `path` is the image the module was mapped from, if it
+/// exists on disk, otherwise `dbghelp` reads the headers of the image from
+/// memory at `base` to find its PDB.
+///
+/// Returns whether the module was registered, which is `false` if `dbghelp`
+/// already knew of a module at `base` and on all other backends, which
+/// can't symbolicate modules the OS doesn't know of.
+///
+/// # Safety
+///
+/// A module image must be mapped at `base`, spanning `size` bytes, and it
+/// must stay mapped for as long as addresses in it may be symbolicated.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```no_run
+/// # let (base, size) = (std::ptr::null_mut(), 0);
+/// // ... after mapping a DLL at `base` ...
+/// if !unsafe { backtrace::register_module(base, size, None) } {
+///     eprintln!("frames in the DLL won't be symbolicated");
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub unsafe fn register_module(base: *mut c_void, size: usize, path: Option<&Path>) -> bool {
+    let _guard = crate::lock::lock();
+    unsafe { imp::register_module(base, size, path) }
+}
+
 /// Attempt to reclaim that cached memory used to symbolicate addresses.
 ///
 /// This method will attempt to release any global data structures that have
@@ -428,13 +1136,329 @@ pub fn clear_symbol_cache() {
     }
 }
 
+/// Sets how many libraries have their debug information kept loaded at a time.
+///
+/// Reading the debug information of a library is expensive, so it's kept
+/// around for the most recently used libraries, four of them by default.
+/// Symbolicating backtraces which go through more libraries than that over
+/// and over, such as in a process hosting many plugins, keeps reading the same
+/// debug information again. A bigger cache avoids that, at the cost of the
+/// memory the extra debug information takes up. Shrinking the cache evicts
+/// the least recently used libraries right away. At least one library is
+/// always kept loaded, so a `size` of zero is treated as one.
+///
+/// The number of addresses outside of any library, such as in JIT-compiled
+/// code, which are remembered so that looking them up again doesn't rescan
+/// the loaded libraries, grows and shrinks along with this size.
+///
+/// This only has an effect with the default symbolication backend, DbgHelp
+/// manages its own memory.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// backtrace::set_mapping_cache_size(16);
+/// println!("{:?}", backtrace::Backtrace::new());
+/// ```
+#[cfg(feature = "std")]
+pub fn set_mapping_cache_size(size: usize) {
+    let _guard = crate::lock::lock();
+    unsafe {
+        imp::set_mapping_cache_size(size);
+    }
+}
+
+/// Resets all of the state this crate keeps for symbolication, as if nothing
+/// had been symbolicated yet.
+///
+/// Besides doing everything `clear_symbol_cache` does, which includes
+/// resetting `cache_stats`, this forgets the list of loaded libraries, which
+/// is read again right away. On Windows the options and search path of
+/// dbghelp are set up again the next time it's used, so that the directories
+/// of modules loaded since are searched for symbols. This is meant for
+/// isolating tests from each other, and for processes which unload and reload
+/// their plugins and want to start over.
+///
+/// The unwinder installed with `set_unwinder` stays installed, as it's
+/// configuration rather than cached state.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn reset() {
+    let _guard = crate::lock::lock();
+    unsafe {
+        imp::reset();
+    }
+}
+
+/// Returns the address ranges the libraries loaded into this process span,
+/// or `None` if the symbolication backend can't tell.
+#[cfg(feature = "std")]
+pub(crate) fn mapped_ranges() -> Option<Vec<core::ops::Range<usize>>> {
+    let _guard = crate::lock::lock();
+    unsafe { imp::mapped_ranges() }
+}
+
+/// Returns the address ranges of this process which are known not to hold
+/// code of any library, such as its stack and heap, along with the labels
+/// addresses in them are printed with instead of `<unknown>`.
+#[cfg(feature = "std")]
+pub(crate) fn special_regions() -> Vec<(core::ops::Range<usize>, &'static str)> {
+    imp::special_regions()
+}
+
+#[cfg(feature = "std")]
+mod core_file;
+#[cfg(feature = "std")]
+pub use self::core_file::{CoreSymbolizer, MappedFile};
+
+#[cfg(feature = "std")]
+mod symbolizer;
+#[cfg(feature = "std")]
+pub use self::symbolizer::Symbolizer;
+
+#[cfg(feature = "std")]
+mod libraries;
+#[cfg(feature = "std")]
+pub use self::libraries::{native_libraries, Library, LibrarySegment};
+
+/// The symbolication implementation selected when this crate was compiled.
+///
+/// This is returned from `SymbolizationStatus::backend`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SymbolizeBackend {
+    /// DWARF and symbol table parsing through the `gimli` crate, used on most
+    /// unix platforms and on Windows with the GNU toolchain.
+    Gimli,
+    /// `dbghelp.dll`, used on Windows with the MSVC toolchain.
+    Dbghelp,
+    /// Miri's own symbolication hooks.
+    Miri,
+    /// No symbolication support at all for this platform.
+    Noop,
+}
+
+impl SymbolizeBackend {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SymbolizeBackend::Gimli => "gimli",
+            SymbolizeBackend::Dbghelp => "dbghelp",
+            SymbolizeBackend::Miri => "miri",
+            SymbolizeBackend::Noop => "noop",
+        }
+    }
+}
+
+/// A report on how well symbols can currently be resolved, returned from
+/// `symbolization_status`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug)]
+pub struct SymbolizationStatus {
+    backend: SymbolizeBackend,
+    resolves_symbols: bool,
+    has_debuginfo: bool,
+    debuginfo_mismatch: bool,
+    library_replaced: bool,
+}
+
+#[cfg(feature = "std")]
+impl SymbolizationStatus {
+    /// Returns which symbolication implementation is compiled in.
+    pub fn backend(&self) -> SymbolizeBackend {
+        self.backend
+    }
+
+    /// Returns whether symbol names could be resolved for code in the binary
+    /// containing this crate.
+    ///
+    /// This is typically `false` if the backend is `Noop`, if the backend
+    /// failed to initialize (e.g. `dbghelp.dll` couldn't be loaded), or if the
+    /// binary has been stripped of its symbol table.
+    pub fn resolves_symbols(&self) -> bool {
+        self.resolves_symbols
+    }
+
+    /// Returns whether filename and line number information could be found
+    /// for code in the binary containing this crate.
+    ///
+    /// If this is `false` then backtraces will at best contain function names,
+    /// which usually means debug information wasn't generated by the compiler
+    /// or has been split off into a file which couldn't be found.
+    pub fn has_debuginfo(&self) -> bool {
+        self.has_debuginfo
+    }
+
+    /// Returns whether a separate debug info file was found for some library
+    /// but ignored because its build ID didn't match the library's.
+    ///
+    /// Mismatched debug info usually means a `-dbg` package or `.debug` file
+    /// from a different build is installed. It's skipped since it would produce
+    /// wrong line numbers, and symbols come from the library itself instead.
+    /// This only reflects libraries symbolicated so far and is reset by
+    /// `clear_symbol_cache`.
+    pub fn debuginfo_mismatch(&self) -> bool {
+        self.debuginfo_mismatch
+    }
+
+    /// Returns whether some library was left unsymbolicated because the file
+    /// it was loaded from has been replaced since.
+    ///
+    /// Replacing a file, as upgrading a package or deploying a new build
+    /// does, leaves the old file mapped but makes its path refer to a new
+    /// file, whose symbols would be confidently wrong for the mapped code.
+    /// Frames in such libraries are left without symbols instead. This is
+    /// only detected on Linux and Android, only reflects libraries
+    /// symbolicated so far and is reset by `clear_symbol_cache`.
+    pub fn library_replaced(&self) -> bool {
+        self.library_replaced
+    }
+}
+
+/// Reports which symbolication backend is active and whether it currently has
+/// access to symbols and debug information for the running binary.
+///
+/// This works by tracing and resolving a frame within this crate, so it's as
+/// expensive as resolving a single frame of a backtrace. It's intended for
+/// diagnostics, for example warning a user that installed binaries lack debug
+/// information before producing a backtrace without any line numbers in it.
+///
+/// Note that this only inspects the binary this crate was linked into, other
+/// shared libraries may have more or less information available.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// let status = backtrace::symbolization_status();
+/// if !status.has_debuginfo() {
+///     eprintln!("no debug info found, backtraces will lack line numbers");
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[inline(never)]
+pub fn symbolization_status() -> SymbolizationStatus {
+    let mut resolves_symbols = false;
+    let mut has_debuginfo = false;
+    crate::trace(|frame| {
+        resolve_frame(frame, |symbol| {
+            resolves_symbols |= symbol.name().is_some();
+            has_debuginfo |= symbol.filename_raw().is_some() && symbol.lineno().is_some();
+        });
+        // The first frame is always code in this crate, so that's all we need.
+        false
+    });
+    SymbolizationStatus {
+        backend: BACKEND,
+        resolves_symbols,
+        has_debuginfo,
+        debuginfo_mismatch: imp::debuginfo_mismatch(),
+        library_replaced: unsafe {
+            let _guard = crate::lock::lock();
+            imp::library_replaced()
+        },
+    }
+}
+
+/// Counters for the cache of parsed debug information kept by the
+/// symbolication backend, returned from `cache_stats`.
+///
+/// Parsing a library's debug information is the most expensive part of
+/// resolving symbols, so the parsed form of the most recently used libraries
+/// is kept around. These counters show how well that works for the addresses
+/// a program resolves, which is mostly useful when benchmarking or when
+/// investigating why symbolication is slow.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    mapping_hits: u64,
+    mapping_misses: u64,
+    evictions: u64,
+}
+
+#[cfg(feature = "std")]
+impl CacheStats {
+    /// Returns how many times an address was resolved within a library whose
+    /// debug information was already cached.
+    pub fn mapping_hits(&self) -> u64 {
+        self.mapping_hits
+    }
+
+    /// Returns how many times the debug information of a library had to be
+    /// loaded and parsed to resolve an address.
+    pub fn mapping_misses(&self) -> u64 {
+        self.mapping_misses
+    }
+
+    /// Returns how many times the cache was full, so that loading a library
+    /// dropped the least recently used one.
+    ///
+    /// A high number of evictions compared to misses means addresses are
+    /// spread over more libraries than the cache holds at once.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+}
+
+/// Returns counters of how the symbolication backend's cache of parsed debug
+/// information has been used since the program started, or since the last
+/// call to `clear_symbol_cache`.
+///
+/// Counting is always on and costs an increment per resolved address. Only
+/// the global cache is counted, lookups made through a `Symbolizer` or
+/// `CoreSymbolizer` keep their own cache and aren't included. Only the default
+/// backend on unix platforms keeps such a cache, elsewhere all counters are
+/// zero.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Example
+///
+/// ```
+/// let backtrace = backtrace::Backtrace::new();
+/// let stats = backtrace::cache_stats();
+/// println!("{} hits, {} misses", stats.mapping_hits(), stats.mapping_misses());
+/// ```
+#[cfg(feature = "std")]
+pub fn cache_stats() -> CacheStats {
+    let _guard = crate::lock::lock();
+    unsafe { imp::cache_stats() }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(miri)] {
         mod miri;
         use miri as imp;
+        pub(crate) const BACKEND: SymbolizeBackend = SymbolizeBackend::Miri;
     } else if #[cfg(all(windows, target_env = "msvc", not(target_vendor = "uwp")))] {
         mod dbghelp;
         use dbghelp as imp;
+        pub(crate) const BACKEND: SymbolizeBackend = SymbolizeBackend::Dbghelp;
     } else if #[cfg(all(
         any(unix, all(windows, target_env = "gnu")),
         not(target_vendor = "uwp"),
@@ -443,8 +1467,10 @@ cfg_if::cfg_if! {
     ))] {
         mod gimli;
         use gimli as imp;
+        pub(crate) const BACKEND: SymbolizeBackend = SymbolizeBackend::Gimli;
     } else {
         mod noop;
         use noop as imp;
+        pub(crate) const BACKEND: SymbolizeBackend = SymbolizeBackend::Noop;
     }
 }
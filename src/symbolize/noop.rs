@@ -2,6 +2,8 @@
 //! support.
 
 use super::{BytesOrWideString, ResolveWhat, SymbolName};
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
 use core::ffi::c_void;
 use core::marker;
 
@@ -16,6 +18,22 @@ impl Symbol<'_> {
         None
     }
 
+    pub fn linkage_name(&self) -> Option<SymbolName<'_>> {
+        None
+    }
+
+    pub fn source_name(&self) -> Option<SymbolName<'_>> {
+        None
+    }
+
+    pub fn call_target(&self) -> Option<SymbolName<'_>> {
+        None
+    }
+
+    pub fn is_optimized(&self) -> Option<bool> {
+        None
+    }
+
     pub fn addr(&self) -> Option<*mut c_void> {
         None
     }
@@ -29,6 +47,11 @@ impl Symbol<'_> {
         None
     }
 
+    #[cfg(feature = "std")]
+    pub fn module(&self) -> Option<&::std::path::Path> {
+        None
+    }
+
     pub fn lineno(&self) -> Option<u32> {
         None
     }
@@ -39,3 +62,125 @@ impl Symbol<'_> {
 }
 
 pub unsafe fn clear_symbol_cache() {}
+
+pub unsafe fn set_mapping_cache_size(_size: usize) {}
+
+pub unsafe fn reset() {}
+
+#[cfg(feature = "std")]
+pub fn resolve_in_archive(
+    _path: &std::path::Path,
+    _member: &[u8],
+    _svma: u64,
+    _cb: &mut dyn FnMut(&super::Symbol),
+) {
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_location(
+    _what: ResolveWhat<'_>,
+) -> Option<(std::path::PathBuf, u32, Option<u32>)> {
+    None
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_candidates(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+    resolve(what, cb)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_batch(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &super::Symbol)) {
+    super::resolve_each(addrs, cb)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_name_only(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+    resolve(what, cb)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn preload_library(_path: &std::path::Path) -> bool {
+    false
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn register_module(
+    _base: *mut c_void,
+    _size: usize,
+    _path: Option<&std::path::Path>,
+) -> bool {
+    false
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn enable_gdb_jit() {}
+
+pub fn debuginfo_mismatch() -> bool {
+    false
+}
+
+pub unsafe fn library_replaced() -> bool {
+    false
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn mapped_ranges() -> Option<Vec<core::ops::Range<usize>>> {
+    None
+}
+
+#[cfg(feature = "std")]
+pub fn special_regions() -> Vec<(core::ops::Range<usize>, &'static str)> {
+    Vec::new()
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn cache_stats() -> super::CacheStats {
+    super::CacheStats::default()
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn loaded_libraries() -> Vec<super::Library> {
+    Vec::new()
+}
+
+// Core dumps can't be symbolicated with this backend.
+#[cfg(feature = "std")]
+pub struct CoreSymbolizer;
+
+#[cfg(feature = "std")]
+impl CoreSymbolizer {
+    pub fn new(_files: &[super::MappedFile]) -> CoreSymbolizer {
+        CoreSymbolizer
+    }
+
+    pub fn with_opener<F, M>(_files: &[super::MappedFile], _open: F) -> CoreSymbolizer
+    where
+        F: Fn(&std::path::Path) -> Option<M> + 'static,
+        M: core::ops::Deref<Target = [u8]> + 'static,
+    {
+        CoreSymbolizer
+    }
+
+    pub fn resolve(&mut self, _addr: u64, _cb: &mut dyn FnMut(&super::Symbol)) {}
+}
+
+#[cfg(feature = "std")]
+pub struct Symbolizer;
+
+#[cfg(feature = "std")]
+impl Symbolizer {
+    pub fn new() -> Symbolizer {
+        Symbolizer
+    }
+
+    pub fn preload(&mut self, _addr: *mut c_void) -> bool {
+        false
+    }
+
+    pub fn resolve(&mut self, _what: ResolveWhat<'_>, _cb: &mut dyn FnMut(&super::Symbol)) {}
+}
+
+#[cfg(feature = "std")]
+pub fn parse_maps_text(_text: &str) -> Vec<super::MappedFile> {
+    Vec::new()
+}
@@ -0,0 +1,99 @@
+//! Symbolication with state owned by the caller rather than kept in globals,
+//! see `Symbolizer`.
+
+use super::{imp, Frame, ResolveWhat, Symbol};
+use core::ffi::c_void;
+
+/// Resolves addresses of the current process using symbolication state owned
+/// by this value, rather than the global state `resolve` shares.
+///
+/// Lookups through a `Symbolizer` don't take the lock `resolve` and `trace`
+/// take, and don't touch the global cache of debug information, so they can
+/// be made from places where those can't be used, such as a signal handler
+/// which may have interrupted a thread in the middle of symbolicating. Each
+/// `Symbolizer` is only usable through `&mut self`, so every thread or handler
+/// needs its own, but one may be created and warmed up on a thread other than
+/// the one using it.
+///
+/// The libraries loaded into the process are listed once, when the
+/// `Symbolizer` is created, so libraries loaded afterwards won't be resolved.
+/// Debug information is read the first time a library is looked up, which
+/// opens and maps files and allocates memory. Calling `preload` with addresses
+/// from the libraries of interest, such as the frames of a backtrace, gets
+/// that done up front. Note that resolving still allocates, and a small number
+/// of libraries is kept loaded at a time, so symbolication is never fully
+/// async-signal-safe, but a pre-warmed `Symbolizer` avoids locks and I/O in the
+/// common case.
+///
+/// Code generated by JIT compilers isn't resolved.
+///
+/// With DbgHelp, the backend used on MSVC Windows, lookups go through the
+/// global state and its lock just like with `resolve`, as DbgHelp keeps its
+/// state per process.
+///
+/// # Examples
+///
+/// ```
+/// let mut symbolizer = backtrace::Symbolizer::new();
+/// // Warm up before the signal handler is installed.
+/// backtrace::trace(|frame| {
+///     symbolizer.preload(frame.ip());
+///     true
+/// });
+///
+/// // Later on, say from within the signal handler:
+/// backtrace::trace(|frame| {
+///     symbolizer.resolve_frame(frame, |symbol| {
+///         println!("{:?}", symbol.name());
+///     });
+///     true
+/// });
+/// ```
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+pub struct Symbolizer {
+    inner: imp::Symbolizer,
+}
+
+impl Symbolizer {
+    /// Creates a symbolizer for the libraries currently loaded into this
+    /// process.
+    pub fn new() -> Symbolizer {
+        Symbolizer {
+            inner: imp::Symbolizer::new(),
+        }
+    }
+
+    /// Reads the debug information of the library containing `addr` ahead of
+    /// time, returning whether there was any to read.
+    ///
+    /// Only a handful of libraries stay loaded, so preloading more than that
+    /// evicts the ones preloaded first.
+    pub fn preload(&mut self, addr: *mut c_void) -> bool {
+        self.inner.preload(addr)
+    }
+
+    /// Resolves an address to a symbol, passing the symbol to the specified
+    /// closure just like `resolve` does.
+    ///
+    /// Like `resolve`, `addr` is assumed to be a return address, so the
+    /// instruction before it is what gets resolved.
+    pub fn resolve<F: FnMut(&Symbol)>(&mut self, addr: *mut c_void, mut cb: F) {
+        self.inner.resolve(ResolveWhat::Address(addr), &mut cb)
+    }
+
+    /// Resolves a previously captured frame to a symbol, passing the symbol to
+    /// the specified closure just like `resolve_frame` does.
+    pub fn resolve_frame<F: FnMut(&Symbol)>(&mut self, frame: &Frame, mut cb: F) {
+        self.inner.resolve(ResolveWhat::Frame(frame), &mut cb)
+    }
+}
+
+impl Default for Symbolizer {
+    fn default() -> Symbolizer {
+        Symbolizer::new()
+    }
+}
@@ -21,6 +21,44 @@ pub enum BytesOrWideString<'a> {
     Wide(&'a [u16]),
 }
 
+/// Strings of the same kind are equal if they're made up of the same bytes or
+/// UTF-16 code units. `Bytes` and `Wide` strings are equal if the bytes are
+/// UTF-8 which encodes the same text as the code units do as UTF-16, so `Bytes`
+/// which aren't UTF-8 and `Wide` strings with unpaired surrogates are never
+/// equal to the other kind.
+impl PartialEq for BytesOrWideString<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        use self::BytesOrWideString::*;
+
+        match (self, other) {
+            (Bytes(a), Bytes(b)) => a == b,
+            (Wide(a), Wide(b)) => a == b,
+            (Bytes(bytes), Wide(wide)) | (Wide(wide), Bytes(bytes)) => {
+                core::str::from_utf8(bytes).is_ok_and(|s| s.encode_utf16().eq(wide.iter().copied()))
+            }
+        }
+    }
+}
+
+impl Eq for BytesOrWideString<'_> {}
+
+impl core::hash::Hash for BytesOrWideString<'_> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        // UTF-8 is hashed as UTF-16 to match `Wide` strings it's equal to.
+        match *self {
+            BytesOrWideString::Bytes(bytes) => match core::str::from_utf8(bytes) {
+                Ok(s) => s.encode_utf16().for_each(|unit| state.write_u16(unit)),
+                Err(_) => {
+                    state.write(bytes);
+                    state.write_u8(1);
+                }
+            },
+            BytesOrWideString::Wide(wide) => wide.iter().for_each(|&unit| state.write_u16(unit)),
+        }
+        state.write_u8(0);
+    }
+}
+
 #[cfg(feature = "std")]
 impl<'a> BytesOrWideString<'a> {
     /// Lossy converts to a `Cow<str>`, will allocate if `Bytes` is not valid
@@ -41,6 +79,10 @@ impl<'a> BytesOrWideString<'a> {
 
     /// Provides a `Path` representation of `BytesOrWideString`.
     ///
+    /// The path is returned exactly as given, so it's suitable for accessing
+    /// the file. Verbatim Windows paths keep their `\\?\` prefix, which the
+    /// `Display` implementation strips on Windows.
+    ///
     /// # Required features
     ///
     /// This function requires the `std` feature of the `backtrace` crate to be
@@ -75,9 +117,121 @@ impl<'a> BytesOrWideString<'a> {
     }
 }
 
+/// Displays the string lossily, see `to_str_lossy`.
+///
+/// On Windows, paths in the verbatim form some tools emit, such as
+/// `\\?\C:\foo` or `\\?\UNC\server\share`, are displayed in their usual form
+/// `C:\foo` and `\\server\share` instead. Use `into_path_buf` to get at the
+/// path exactly as it was given.
 #[cfg(feature = "std")]
 impl<'a> fmt::Display for BytesOrWideString<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.to_str_lossy().fmt(f)
+        let s = self.to_str_lossy();
+        #[cfg(windows)]
+        let s = strip_verbatim_prefix(&s);
+        s.fmt(f)
+    }
+}
+
+/// Removes the `\\?\` prefix from verbatim Windows paths to a drive or UNC
+/// share, for display.
+///
+/// Anything else is returned unchanged, including verbatim paths to a volume
+/// GUID, which have no other form.
+#[cfg(all(feature = "std", windows))]
+pub(crate) fn strip_verbatim_prefix(path: &str) -> Cow<'_, str> {
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        return Cow::Owned(format!(r"\\{rest}"));
+    }
+    if let Some(rest) = path.strip_prefix(r"\\?\") {
+        let mut chars = rest.chars();
+        if let (Some(drive), Some(':')) = (chars.next(), chars.next()) {
+            if drive.is_ascii_alphabetic() {
+                return Cow::Borrowed(rest);
+            }
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// Same as `strip_verbatim_prefix`, but for a `PathBuf`.
+#[cfg(all(feature = "std", windows))]
+pub(crate) fn path_without_verbatim_prefix(path: PathBuf) -> PathBuf {
+    match path.to_str().map(strip_verbatim_prefix) {
+        Some(Cow::Owned(stripped)) => PathBuf::from(stripped),
+        Some(Cow::Borrowed(stripped)) if stripped.len() < path.as_os_str().len() => {
+            PathBuf::from(stripped)
+        }
+        _ => path,
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(windows)]
+    fn verbatim_prefix() {
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\C:\src\lib.rs"),
+            r"C:\src\lib.rs"
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\lib.rs"),
+            r"\\server\share\lib.rs"
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\Volume{1234}\lib.rs"),
+            r"\\?\Volume{1234}\lib.rs"
+        );
+        assert_eq!(strip_verbatim_prefix(r"\\server\share"), r"\\server\share");
+        assert_eq!(strip_verbatim_prefix("/src/lib.rs"), "/src/lib.rs");
+
+        let wide = r"\\?\D:\a.rs".encode_utf16().collect::<Vec<_>>();
+        assert_eq!(BytesOrWideString::Wide(&wide).to_string(), r"D:\a.rs");
+        assert_eq!(
+            path_without_verbatim_prefix(PathBuf::from(r"\\?\D:\a.rs")),
+            PathBuf::from(r"D:\a.rs")
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn verbatim_prefix_only_on_windows() {
+        // Elsewhere, `\\?\` is just part of a file name.
+        let path = BytesOrWideString::Bytes(br"\\?\C:\src\lib.rs");
+        assert_eq!(path.to_string(), r"\\?\C:\src\lib.rs");
+    }
+
+    #[test]
+    fn eq_and_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash(s: &BytesOrWideString<'_>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            s.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let wide = "src/lib.rs".encode_utf16().collect::<Vec<_>>();
+        let bytes = BytesOrWideString::Bytes(b"src/lib.rs");
+        assert_eq!(bytes, BytesOrWideString::Bytes(b"src/lib.rs"));
+        assert_eq!(bytes, BytesOrWideString::Wide(&wide));
+        assert_eq!(BytesOrWideString::Wide(&wide), bytes);
+        assert_eq!(hash(&bytes), hash(&BytesOrWideString::Wide(&wide)));
+        assert_ne!(bytes, BytesOrWideString::Bytes(b"src/main.rs"));
+        assert_ne!(bytes, BytesOrWideString::Wide(&wide[..3]));
+
+        let unpaired = [0xd800];
+        assert_ne!(
+            BytesOrWideString::Bytes(b"\xed\xa0\x80"),
+            BytesOrWideString::Wide(&unpaired)
+        );
+        assert_eq!(
+            BytesOrWideString::Bytes(b"\xff"),
+            BytesOrWideString::Bytes(b"\xff")
+        );
     }
 }
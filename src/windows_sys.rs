@@ -20,6 +20,7 @@ windows_targets::link!("dbghelp.dll" "system" fn SymGetModuleBase64(hprocess : H
 windows_targets::link!("dbghelp.dll" "system" fn SymGetOptions() -> u32);
 windows_targets::link!("dbghelp.dll" "system" fn SymGetSearchPathW(hprocess : HANDLE, searchpatha : PWSTR, searchpathlength : u32) -> BOOL);
 windows_targets::link!("dbghelp.dll" "system" fn SymInitializeW(hprocess : HANDLE, usersearchpath : PCWSTR, finvadeprocess : BOOL) -> BOOL);
+windows_targets::link!("dbghelp.dll" "system" fn SymLoadModuleExW(hprocess : HANDLE, hfile : HANDLE, imagename : PCWSTR, modulename : PCWSTR, baseofdll : u64, dllsize : u32, data : *const MODLOAD_DATA, flags : SYM_LOAD_FLAGS) -> u64);
 windows_targets::link!("dbghelp.dll" "system" fn SymQueryInlineTrace(hprocess : HANDLE, startaddress : u64, startcontext : u32, startretaddress : u64, curaddress : u64, curcontext : *mut u32, curframeindex : *mut u32) -> BOOL);
 windows_targets::link!("dbghelp.dll" "system" fn SymSetOptions(symoptions : u32) -> u32);
 windows_targets::link!("dbghelp.dll" "system" fn SymSetSearchPathW(hprocess : HANDLE, searchpatha : PCWSTR) -> BOOL);
@@ -478,6 +479,16 @@ pub struct MEMORY_MAPPED_VIEW_ADDRESS {
 }
 #[repr(C)]
 #[derive(Clone, Copy)]
+pub struct MODLOAD_DATA {
+    pub ssize: u32,
+    pub ssig: MODLOAD_DATA_TYPE,
+    pub data: *mut core::ffi::c_void,
+    pub size: u32,
+    pub flags: u32,
+}
+pub type MODLOAD_DATA_TYPE = u32;
+#[repr(C)]
+#[derive(Clone, Copy)]
 pub struct MODULEENTRY32W {
     pub dwSize: u32,
     pub th32ModuleID: u32,
@@ -582,6 +593,7 @@ pub struct SYMBOL_INFOW {
 }
 pub type SYMBOL_INFO_FLAGS = u32;
 pub const SYMOPT_DEFERRED_LOADS: u32 = 4u32;
+pub type SYM_LOAD_FLAGS = u32;
 pub const TH32CS_SNAPMODULE: CREATE_TOOLHELP_SNAPSHOT_FLAGS = 8u32;
 pub const TRUE: BOOL = 1i32;
 #[repr(C)]
@@ -18,6 +18,10 @@ type Pos = (&'static str, u32);
 
 #[test]
 fn doit() {
+    outer(pos!());
+
+    // The library is loaded after symbols have already been resolved above,
+    // which checks that libraries loaded late are still picked up.
     if
     // Skip musl which is by default statically linked and doesn't support
     // dynamic libraries.
@@ -25,8 +29,6 @@ fn doit() {
     // Skip Miri, since it doesn't support dynamic libraries.
     && !cfg!(miri)
     {
-        // TODO(#238) this shouldn't have to happen first in this function, but
-        // currently it does.
         let mut dir = std::env::current_exe().unwrap();
         dir.pop();
         if cfg!(windows) {
@@ -46,8 +48,6 @@ fn doit() {
             });
         }
     }
-
-    outer(pos!());
 }
 
 #[inline(never)]
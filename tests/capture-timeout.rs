@@ -0,0 +1,34 @@
+use backtrace::{Backtrace, Frame, TraceResult};
+use std::ffi::c_void;
+use std::time::Duration;
+
+#[test]
+fn endless_stack_times_out() {
+    // A stack which never ends, as unwinding a corrupt one might look.
+    backtrace::set_unwinder(|cb| {
+        for ip in (0x1000usize..).step_by(0x10) {
+            if !cb(&Frame::new(
+                ip as *mut c_void,
+                ip as *mut c_void,
+                ip as *mut c_void,
+            )) {
+                break;
+            }
+        }
+    });
+
+    let mut count = 0;
+    let result = backtrace::trace_with_timeout(Duration::from_millis(10), |_| {
+        count += 1;
+        true
+    });
+    assert_eq!(result, TraceResult::TimedOut);
+    assert!(count > 0);
+
+    let result = backtrace::trace_with_timeout(Duration::MAX, |_| false);
+    assert_eq!(result, TraceResult::Stopped);
+
+    let (bt, result) = Backtrace::new_unresolved_with_timeout(Duration::from_millis(10));
+    assert_eq!(result, TraceResult::TimedOut);
+    assert!(!bt.frames().is_empty());
+}
@@ -0,0 +1,35 @@
+use backtrace::SymbolName;
+
+#[test]
+fn custom_demangler() {
+    backtrace::set_custom_demangler(|name| {
+        name.strip_prefix("_D")
+            .map(|rest| format!("d::{rest}.llvm.42"))
+    });
+
+    let name = SymbolName::new(b"_D3foo");
+    assert_eq!(format!("{name}"), "d::3foo.llvm.42");
+    assert_eq!(format!("{name:#}"), "d::3foo");
+    assert_eq!(
+        format!("{name:?}"),
+        r#"SymbolName { raw: "_D3foo", demangled: "d::3foo.llvm.42" }"#
+    );
+
+    // Rust names are still demangled as before, and names the demangler
+    // doesn't recognize are left alone.
+    let name = SymbolName::new(b"_ZN3foo3barE");
+    assert_eq!(format!("{name}"), "foo::bar");
+    let name = SymbolName::new(b"plain");
+    assert_eq!(format!("{name}"), "plain");
+
+    // Installing another demangler replaces the first one, and drops it.
+    let token = std::sync::Arc::new(());
+    let held = token.clone();
+    backtrace::set_custom_demangler(move |_| {
+        let _ = &held;
+        None
+    });
+    assert_eq!(format!("{}", SymbolName::new(b"_D3foo")), "_D3foo");
+    backtrace::set_custom_demangler(|_| None);
+    assert_eq!(std::sync::Arc::strong_count(&token), 1);
+}
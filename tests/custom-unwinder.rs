@@ -0,0 +1,77 @@
+use backtrace::{Frame, TraceResult};
+use std::ffi::c_void;
+
+#[test]
+fn trace_uses_custom_unwinder() {
+    let ips = [0x1000usize, 0x2000, 0x3000];
+    backtrace::set_unwinder(move |cb| {
+        for (i, &ip) in ips.iter().enumerate() {
+            let sp = (0x8000 + i * 0x10) as *mut c_void;
+            if !cb(&Frame::new(ip as *mut c_void, sp, ip as *mut c_void)) {
+                break;
+            }
+        }
+    });
+
+    let mut frames = Vec::new();
+    backtrace::trace(|frame| {
+        frames.push((frame.ip() as usize, frame.sp() as usize));
+        true
+    });
+    assert_eq!(
+        frames,
+        [(0x1000, 0x8000), (0x2000, 0x8010), (0x3000, 0x8020)]
+    );
+
+    let mut count = 0;
+    backtrace::trace(|_| {
+        count += 1;
+        false
+    });
+    assert_eq!(count, 1);
+
+    let bt = backtrace::Backtrace::new_unresolved();
+    let ips = bt
+        .frames()
+        .iter()
+        .map(|f| f.ip() as usize)
+        .collect::<Vec<_>>();
+    assert_eq!(ips, [0x1000, 0x2000, 0x3000]);
+
+    // None of the made up frames are in a library.
+    if cfg!(target_os = "linux") {
+        let mut count = 0;
+        let result = backtrace::trace_validated(|_| {
+            count += 1;
+            true
+        });
+        assert_eq!(result, TraceResult::Implausible(0x1000 as *mut c_void));
+        assert_eq!(count, 0);
+    }
+
+    // Replaced unwinders are dropped.
+    let token = std::sync::Arc::new(());
+    let held = token.clone();
+    backtrace::set_unwinder(move |_| {
+        let _ = &held;
+    });
+    assert_eq!(std::sync::Arc::strong_count(&token), 2);
+    backtrace::set_unwinder(|_| {});
+    assert_eq!(std::sync::Arc::strong_count(&token), 1);
+
+    // Uninstalled unwinders are dropped too, and the stack is walked as usual.
+    let held = token.clone();
+    backtrace::set_unwinder(move |_| {
+        let _ = &held;
+    });
+    backtrace::clear_unwinder();
+    assert_eq!(std::sync::Arc::strong_count(&token), 1);
+    let mut count = 0;
+    unsafe {
+        backtrace::trace_unsynchronized(|_| {
+            count += 1;
+            true
+        })
+    };
+    assert!(count > 0);
+}
@@ -0,0 +1,26 @@
+use backtrace::Backtrace;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn filename_remapper() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
+    backtrace::set_filename_remapper(move |path| {
+        let relative = path.strip_prefix(&dir).ok()?;
+        Some(Path::new("/remapped").join(relative))
+    });
+
+    let bt = Backtrace::new();
+    let filenames = bt
+        .frames()
+        .iter()
+        .flat_map(|frame| frame.symbols())
+        .filter_map(|symbol| symbol.filename())
+        .collect::<Vec<_>>();
+    if !cfg!(all(target_os = "linux", debug_assertions)) {
+        return;
+    }
+    let this_file = PathBuf::from("/remapped/tests/filename-remapper.rs");
+    assert!(filenames.contains(&&*this_file), "{filenames:?}");
+    // Filenames the remapper doesn't recognize are kept as they are.
+    assert!(filenames.iter().any(|path| !path.starts_with("/remapped")));
+}
@@ -0,0 +1,44 @@
+use backtrace::Backtrace;
+use std::panic;
+use std::sync::{Arc, Mutex};
+
+fn main() {
+    let applied = Arc::new(Mutex::new(None));
+    let applied2 = applied.clone();
+    panic::set_hook(Box::new(move |info| {
+        let location = info.location().unwrap();
+        let mut bt = Backtrace::new();
+        let found = bt.apply_panic_location(location);
+        let lines = bt
+            .frames()
+            .iter()
+            .flat_map(|frame| frame.symbols())
+            .filter(|symbol| {
+                symbol
+                    .filename()
+                    .is_some_and(|f| f.ends_with(location.file()))
+            })
+            .map(|symbol| (symbol.lineno(), symbol.colno()))
+            .collect::<Vec<_>>();
+        *applied2.lock().unwrap() = Some((found, lines));
+    }));
+
+    let line = line!() + 2;
+    let result = panic::catch_unwind(|| {
+        panic!("the panic site");
+    });
+    assert!(result.is_err());
+    drop(panic::take_hook());
+
+    let (found, lines) = applied.lock().unwrap().take().unwrap();
+    if cfg!(all(target_os = "linux", debug_assertions)) {
+        assert!(found);
+    }
+    if found {
+        // The hook's own frames come from this file too, and must be left
+        // alone.
+        assert!(lines.contains(&(Some(line), Some(9))));
+        assert!(lines.len() > 1);
+    }
+    println!("test result: ok");
+}
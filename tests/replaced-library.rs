@@ -0,0 +1,51 @@
+// Only Linux and Android check whether mapped files have been replaced.
+#![cfg(all(target_os = "linux", not(target_env = "musl"), not(miri)))]
+
+use std::ffi::c_void;
+use std::fs;
+
+fn names(addr: *mut c_void) -> Vec<String> {
+    let mut names = Vec::new();
+    backtrace::resolve(addr, |symbol| {
+        names.extend(symbol.name().map(|n| n.to_string()));
+    });
+    names
+}
+
+#[test]
+fn replaced_library_is_not_symbolicated() {
+    let mut original = std::env::current_exe().unwrap();
+    original.pop();
+    original.push("libdylib_dep.so");
+
+    // Load a copy of the library which can be replaced without affecting
+    // other tests.
+    let dir = std::env::temp_dir().join(format!("backtrace-replaced-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("libdylib_dep.so");
+    fs::copy(&original, &path).unwrap();
+
+    let lib = unsafe { libloading::Library::new(&path).unwrap() };
+    // Addresses are resolved as return addresses, so look past the start.
+    let foo = unsafe { *lib.get::<*mut c_void>(b"foo").unwrap() };
+    let foo = foo.wrapping_byte_add(1);
+    assert_eq!(names(foo), ["foo"]);
+    assert!(!backtrace::symbolization_status().library_replaced());
+
+    // As a deploy would, write a new file and move it over the loaded one,
+    // which stays mapped but is no longer the file at its path.
+    let new = dir.join("libdylib_dep.so.new");
+    fs::copy(&original, &new).unwrap();
+    fs::rename(&new, &path).unwrap();
+
+    backtrace::clear_symbol_cache();
+    assert!(names(foo).is_empty());
+    assert!(backtrace::symbolization_status().library_replaced());
+
+    // The state is cleared along with the cache.
+    backtrace::clear_symbol_cache();
+    assert!(!backtrace::symbolization_status().library_replaced());
+
+    drop(lib);
+    fs::remove_dir_all(&dir).unwrap();
+}
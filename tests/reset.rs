@@ -0,0 +1,30 @@
+#[test]
+fn reset_starts_over() {
+    let bt = backtrace::Backtrace::new();
+    assert!(!bt.frames().is_empty());
+
+    backtrace::reset();
+    let stats = backtrace::cache_stats();
+    assert_eq!(stats.mapping_hits(), 0);
+    assert_eq!(stats.mapping_misses(), 0);
+
+    // Everything is found again from scratch.
+    let mut names = 0;
+    backtrace::trace(|frame| {
+        backtrace::resolve_frame(frame, |symbol| names += symbol.name().is_some() as usize);
+        true
+    });
+    if cfg!(all(target_os = "linux", not(miri))) {
+        assert!(names > 0);
+        assert!(backtrace::cache_stats().mapping_misses() > 0);
+    }
+
+    // A `Symbolizer` keeps its own cache, which isn't counted.
+    let stats = backtrace::cache_stats();
+    let mut symbolizer = backtrace::Symbolizer::new();
+    backtrace::trace(|frame| {
+        symbolizer.resolve_frame(frame, |_| {});
+        true
+    });
+    assert_eq!(backtrace::cache_stats(), stats);
+}
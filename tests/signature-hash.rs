@@ -0,0 +1,36 @@
+use backtrace::Backtrace;
+use std::panic;
+use std::sync::{Arc, Mutex};
+
+#[inline(never)]
+fn parse() {
+    panic!("parse failed");
+}
+
+#[inline(never)]
+fn render() {
+    panic!("render failed");
+}
+
+fn main() {
+    let hashes = Arc::new(Mutex::new(Vec::new()));
+    let hashes2 = hashes.clone();
+    panic::set_hook(Box::new(move |_| {
+        let bt = Backtrace::new();
+        hashes2.lock().unwrap().push(bt.top_signature_hash(5));
+    }));
+
+    for f in [parse, parse, render] {
+        assert!(panic::catch_unwind(f).is_err());
+    }
+    drop(panic::take_hook());
+
+    let hashes = hashes.lock().unwrap();
+    // The frames of the hook, std's panic machinery and this crate are the
+    // same for every panic, so only the panic sites make a difference.
+    assert_eq!(hashes[0], hashes[1]);
+    if cfg!(target_os = "linux") {
+        assert_ne!(hashes[0], hashes[2]);
+    }
+    println!("test result: ok");
+}
@@ -49,3 +49,97 @@ fn backtrace_new_should_start_with_call_site_trace() {
     // FIXME: need more stacktrace content tests
     assert!(trace.ends_with("\n"));
 }
+
+#[test]
+#[inline(never)]
+fn backtrace_new_limited_should_start_with_call_site_trace() {
+    let b = Backtrace::new_unresolved_limited(2);
+    assert!(b.frames().len() <= 2);
+    assert!(Backtrace::new_limited(0).frames().is_empty());
+    if !ENABLED {
+        return;
+    }
+
+    assert_eq!(b.frames().len(), 2);
+    let this_ip = backtrace_new_limited_should_start_with_call_site_trace as *mut c_void;
+    let frame_ip = b.frames().first().unwrap().symbol_address();
+    assert_eq!(this_ip, frame_ip);
+}
+
+#[test]
+#[inline(never)]
+fn backtrace_new_top_frame_is_call_site_line() {
+    if !ENABLED || !cfg!(debug_assertions) {
+        return;
+    }
+    let line = line!() + 1;
+    let b = Backtrace::new();
+    println!("{b:?}");
+
+    // The top frame's ip is the return address of the call to `new`. The
+    // line of the call itself, rather than of the code returned to, is found
+    // for it.
+    let frame = b.frames().first().unwrap();
+    let symbol = frame.symbols().last().unwrap();
+    assert_eq!(symbol.lineno(), Some(line));
+    assert!(symbol.filename().unwrap().ends_with("skip_inner_frames.rs"));
+}
+
+// A function which faults on its very first instruction, so that the
+// instruction before it belongs to some other function.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+core::arch::global_asm!(
+    ".globl skip_inner_frames_fault",
+    ".type skip_inner_frames_fault, @function",
+    "skip_inner_frames_fault:",
+    ".cfi_startproc",
+    "ud2",
+    "ret",
+    ".cfi_endproc",
+    ".size skip_inner_frames_fault, . - skip_inner_frames_fault",
+);
+
+#[test]
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn signal_frame_ip_is_exact() {
+    use std::sync::Mutex;
+
+    extern "C" {
+        fn skip_inner_frames_fault();
+    }
+
+    static NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    extern "C" fn handler(_: i32, _: *mut libc::siginfo_t, context: *mut c_void) {
+        let fault = skip_inner_frames_fault as *const () as usize;
+        backtrace::trace(|frame| {
+            if frame.ip() as usize == fault {
+                backtrace::resolve_frame(frame, |symbol| {
+                    let name = symbol.name().map(|n| n.to_string());
+                    NAMES.lock().unwrap().extend(name);
+                });
+            }
+            true
+        });
+        // Carry on after the `ud2`.
+        let context = unsafe { &mut *context.cast::<libc::ucontext_t>() };
+        context.uc_mcontext.gregs[libc::REG_RIP as usize] += 2;
+    }
+
+    unsafe {
+        let mut action: libc::sigaction = core::mem::zeroed();
+        action.sa_sigaction = handler as *const () as usize;
+        action.sa_flags = libc::SA_SIGINFO;
+        let mut old: libc::sigaction = core::mem::zeroed();
+        assert_eq!(libc::sigaction(libc::SIGILL, &action, &mut old), 0);
+        skip_inner_frames_fault();
+        assert_eq!(
+            libc::sigaction(libc::SIGILL, &old, core::ptr::null_mut()),
+            0
+        );
+    }
+
+    // The interrupted instruction itself is resolved, rather than the one
+    // before it as for return addresses.
+    assert_eq!(*NAMES.lock().unwrap(), ["skip_inner_frames_fault"]);
+}
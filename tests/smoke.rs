@@ -210,6 +210,34 @@ fn smoke_test_frames() {
     }
 }
 
+// MinGW stores `.debug_*` under long section names in the COFF string table,
+// make sure the gimli backend still finds them for the main executable.
+#[test]
+#[cfg(all(windows, target_env = "gnu"))]
+#[inline(never)]
+fn windows_gnu_main_exe_lineno() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let mut found = false;
+    backtrace::trace(|frame| {
+        backtrace::resolve_frame(frame, |sym| {
+            let is_this_fn = sym
+                .name()
+                .and_then(|name| name.as_str())
+                .is_some_and(|name| name.contains("windows_gnu_main_exe_lineno"));
+            if is_this_fn {
+                assert!(sym.lineno().is_some());
+                assert!(sym.filename().unwrap().ends_with("tests/smoke.rs"));
+                found = true;
+            }
+        });
+        !found
+    });
+    assert!(found);
+}
+
 #[test]
 fn many_threads() {
     let threads = (0..16)
@@ -323,3 +351,1202 @@ fn sp_smoke_test() {
         }
     }
 }
+
+#[test]
+fn symbolization_status_smoke() {
+    let status = backtrace::symbolization_status();
+    println!("{status:?}");
+
+    if cfg!(all(target_os = "linux", debug_assertions)) {
+        assert_eq!(status.backend(), backtrace::SymbolizeBackend::Gimli);
+        assert!(status.resolves_symbols());
+        assert!(status.has_debuginfo());
+    }
+    if status.has_debuginfo() {
+        assert!(status.resolves_symbols());
+    }
+}
+
+#[test]
+fn cache_stats_smoke() {
+    let _ = backtrace::Backtrace::new();
+    let before = backtrace::cache_stats();
+    let _ = backtrace::Backtrace::new();
+    let after = backtrace::cache_stats();
+    println!("{before:?} {after:?}");
+    assert!(after.mapping_hits() >= before.mapping_hits());
+    assert!(after.evictions() <= after.mapping_misses());
+
+    if cfg!(all(target_os = "linux", debug_assertions)) {
+        assert!(after.mapping_misses() > 0);
+        assert!(after.mapping_hits() > before.mapping_hits());
+    }
+}
+
+#[test]
+fn source_snippets() {
+    use backtrace::{BacktraceFmt, BytesOrWideString, PrintFmt};
+    use std::fmt;
+
+    struct Snippet(&'static str, u32, u32);
+
+    impl fmt::Display for Snippet {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut print_path =
+                |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| path.fmt(f);
+            let mut bt = BacktraceFmt::new(f, PrintFmt::Short, &mut print_path);
+            bt.enable_source_snippets(1);
+            bt.frame().print_raw_with_column(
+                1 as *mut c_void,
+                None,
+                Some(BytesOrWideString::Bytes(self.0.as_bytes())),
+                Some(self.1),
+                Some(self.2),
+            )?;
+            bt.finish()
+        }
+    }
+
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/smoke.rs");
+    let line = line!();
+    let out = Snippet(file, line, 9).to_string();
+    assert!(out.contains(&format!("{line} |     let line = line!();")));
+    assert!(out.contains(&format!("{} |     let file", line - 1)));
+    assert!(out.contains("|         ^\n"));
+
+    // Columns count bytes, but the caret is lined up by characters.
+    let line = ("ü", line!()).1;
+    let text = std::fs::read_to_string(file).unwrap();
+    let text = text.lines().nth(line as usize - 1).unwrap();
+    let column = text.find("line!").unwrap();
+    let out = Snippet(file, line, column as u32 + 1).to_string();
+    let pad = " ".repeat(text[..column].chars().count());
+    assert!(out.contains(&format!("| {pad}^\n")), "{out}");
+
+    // Unreadable files just don't get a snippet.
+    let out = Snippet("/this/does/not/exist.rs", 3, 9).to_string();
+    assert!(!out.contains(" | "));
+}
+
+#[test]
+#[cfg(all(target_os = "linux", not(miri)))]
+fn debug_precision_prints_source_snippets() {
+    let bt = backtrace::Backtrace::new();
+    let line = line!() - 1;
+    let out = format!("{bt:.1?}");
+    assert!(
+        out.contains(&format!(
+            "{line} |     let bt = backtrace::Backtrace::new();"
+        )),
+        "{out}"
+    );
+    assert!(!format!("{bt:?}").contains(" | "));
+}
+
+#[test]
+fn module_build_id() {
+    let mut build_ids = Vec::new();
+    backtrace::trace(|frame| {
+        build_ids.push(frame.module_build_id().map(|id| id.to_vec()));
+        assert!(frame.clone().module_build_id().is_none());
+        true
+    });
+    let bt = backtrace::Backtrace::new_unresolved();
+    println!("{:?}", bt.frames()[0].module_build_id());
+
+    // Rust's toolchains on Linux always ask the linker for a build ID.
+    if cfg!(target_os = "linux") {
+        assert!(build_ids[0].as_ref().is_some_and(|id| !id.is_empty()));
+        assert_eq!(bt.frames()[0].module_build_id(), build_ids[0].as_deref());
+    }
+
+    // Build IDs are cached per module while capturing, which mustn't mix up
+    // the frames of different modules, such as those of libc at the bottom.
+    let captured = bt
+        .frames()
+        .iter()
+        .rev()
+        .map(|frame| frame.module_build_id());
+    let traced = build_ids.iter().rev().map(|id| id.as_deref());
+    for (captured, traced) in captured.zip(traced) {
+        assert_eq!(captured, traced);
+    }
+}
+
+#[test]
+fn resolve_location_matches_resolve() {
+    let mut ip = None;
+    backtrace::trace(|frame| {
+        ip = Some(frame.ip());
+        false
+    });
+    let ip = ip.unwrap();
+
+    let mut expected = None;
+    backtrace::resolve(ip, |symbol| {
+        if expected.is_none() {
+            expected = symbol
+                .filename()
+                .zip(symbol.lineno())
+                .map(|(file, line)| (file.to_path_buf(), line, symbol.colno()));
+        }
+    });
+    let location = backtrace::resolve_location(ip);
+    println!("{location:?}");
+    assert_eq!(location, expected);
+
+    if cfg!(all(target_os = "linux", debug_assertions)) {
+        assert!(location.is_some());
+    }
+}
+
+#[test]
+fn symbol_name_debug() {
+    use backtrace::SymbolName;
+
+    let name = SymbolName::new(b"_ZN3foo3barE");
+    assert_eq!(
+        format!("{name:?}"),
+        r#"SymbolName { raw: "_ZN3foo3barE", demangled: "foo::bar" }"#
+    );
+    let name = SymbolName::new(b"not\"mangled");
+    assert_eq!(format!("{name:?}"), r#"SymbolName { raw: "not\"mangled" }"#);
+}
+
+#[test]
+fn symbol_name_is_demangled() {
+    use backtrace::SymbolName;
+
+    assert!(SymbolName::new(b"_ZN3foo3barE").is_demangled());
+    assert!(SymbolName::new(b"_RNvC3foo3bar").is_demangled());
+    assert!(!SymbolName::new(b"main").is_demangled());
+    assert!(!SymbolName::new(b"\xff").is_demangled());
+    assert_eq!(
+        SymbolName::new(b"_Z3foov").is_demangled(),
+        cfg!(feature = "cpp_demangle")
+    );
+}
+
+#[test]
+fn symbol_name_demangled_no_hash() {
+    use backtrace::SymbolName;
+
+    let name = SymbolName::new(b"_ZN3foo3bar17h0123456789abcdefE");
+    assert_eq!(name.to_string(), "foo::bar::h0123456789abcdef");
+    assert_eq!(name.demangled_no_hash().unwrap(), "foo::bar");
+    let name = SymbolName::new(b"_ZN3foo3bar17h0123456789abcdefE.llvm.1234567890");
+    assert_eq!(name.demangled_no_hash().unwrap(), "foo::bar");
+    assert_eq!(SymbolName::new(b"main").demangled_no_hash(), None);
+    if cfg!(feature = "cpp_demangle") {
+        let name = SymbolName::new(b"_Z3foov.llvm.42");
+        assert_eq!(name.demangled_no_hash().unwrap(), "foo()");
+    }
+}
+
+#[test]
+fn symbol_name_llvm_suffix() {
+    use backtrace::SymbolName;
+
+    let name = SymbolName::new(b"my_c_fn.llvm.9876543210");
+    assert_eq!(format!("{name:#}"), "my_c_fn");
+    assert_eq!(format!("{name}"), "my_c_fn.llvm.9876543210");
+    let name = SymbolName::new(b"_ZN3foo3bar17h0123456789abcdefE.llvm.1234567890");
+    assert_eq!(format!("{name:#}"), "foo::bar");
+    let name = SymbolName::new(b"not.llvm.a_suffix");
+    assert_eq!(format!("{name:#}"), "not.llvm.a_suffix");
+    if cfg!(feature = "cpp_demangle") {
+        let name = SymbolName::new(b"_Z3foov.llvm.42");
+        assert_eq!(format!("{name:#}"), "foo()");
+        assert_eq!(format!("{name}"), "foo() [clone .llvm.42]");
+    }
+}
+
+#[test]
+fn trim_runtime_frames() {
+    use backtrace::{BacktraceFmt, BytesOrWideString, PrintFmt, SymbolName};
+    use std::fmt;
+
+    struct Stack(PrintFmt, Option<bool>);
+
+    impl fmt::Display for Stack {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut print_path =
+                |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| path.fmt(f);
+            let mut bt = BacktraceFmt::new(f, self.0, &mut print_path);
+            if let Some(trim) = self.1 {
+                bt.trim_runtime_frames(trim);
+            }
+            let names: [&[u8]; 7] = [
+                b"foo",
+                b"_dl_fixup",
+                b"_dl_runtime_resolve_xsavec",
+                b"main",
+                b"__libc_start_call_main",
+                b"__libc_start_main_impl",
+                b"_start",
+            ];
+            for name in names {
+                bt.frame()
+                    .print_raw(1 as *mut c_void, Some(SymbolName::new(name)), None, None)?;
+            }
+            bt.finish()
+        }
+    }
+
+    let out = Stack(PrintFmt::Short, Some(true)).to_string();
+    assert_eq!(out, "   0: foo\n   3: main\n");
+    for trim in [None, Some(false)] {
+        let out = Stack(PrintFmt::Short, trim).to_string();
+        assert!(out.contains("   1: _dl_fixup\n"));
+        assert!(out.contains("   6: _start\n"));
+    }
+    let out = Stack(PrintFmt::Full, Some(true)).to_string();
+    assert!(out.contains("_dl_runtime_resolve_xsavec"));
+    assert!(out.contains("__libc_start_call_main"));
+}
+
+#[test]
+fn repeated_inline_locations() {
+    use backtrace::{BacktraceFmt, BytesOrWideString, PrintFmt, SymbolName};
+    use std::fmt;
+
+    struct Stack(PrintFmt);
+
+    impl fmt::Display for Stack {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut print_path =
+                |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| path.fmt(f);
+            let mut bt = BacktraceFmt::new(f, self.0, &mut print_path);
+            let symbols: [(&[u8], u32); 4] =
+                [(b"inner", 1), (b"middle", 1), (b"outer", 2), (b"other", 1)];
+            let mut frame = bt.frame();
+            for (name, line) in symbols {
+                frame.print_raw(
+                    1 as *mut c_void,
+                    Some(SymbolName::new(name)),
+                    Some(BytesOrWideString::Bytes(b"src/lib.rs")),
+                    Some(line),
+                )?;
+            }
+            drop(frame);
+            bt.frame().print_raw(
+                1 as *mut c_void,
+                Some(SymbolName::new(b"caller")),
+                Some(BytesOrWideString::Bytes(b"src/lib.rs")),
+                Some(1),
+            )?;
+            bt.finish()
+        }
+    }
+
+    let out = Stack(PrintFmt::Short).to_string();
+    assert_eq!(out.matches("at src/lib.rs:1\n").count(), 3, "{out}");
+    assert_eq!(out.matches("at src/lib.rs:2\n").count(), 1, "{out}");
+    let out = Stack(PrintFmt::Full).to_string();
+    assert_eq!(out.matches("at src/lib.rs:1\n").count(), 4, "{out}");
+}
+
+#[test]
+fn module_relative_addresses() {
+    use backtrace::{BacktraceFmt, BytesOrWideString, PrintFmt, SymbolName};
+    use std::fmt;
+
+    struct Stack(*mut c_void);
+
+    impl fmt::Display for Stack {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut print_path =
+                |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| path.fmt(f);
+            let mut bt = BacktraceFmt::new(f, PrintFmt::Full, &mut print_path);
+            bt.module_relative_addresses(true);
+            let mut frame = bt.frame();
+            for name in [&b"inner"[..], b"outer"] {
+                frame.print_raw(
+                    self.0,
+                    Some(SymbolName::new(name)),
+                    Some(BytesOrWideString::Bytes(b"src/lib.rs")),
+                    Some(1),
+                )?;
+            }
+            drop(frame);
+            bt.frame()
+                .print_raw(1 as *mut c_void, Some(SymbolName::new(b"foo")), None, None)?;
+            bt.finish()
+        }
+    }
+
+    let ip = module_relative_addresses as fn() as *mut c_void;
+    let out = Stack(ip).to_string();
+    assert!(!out.contains(&format!("{ip:?}")), "{out}");
+    assert!(out.contains("   1: <unknown> - foo\n"), "{out}");
+    if !cfg!(any(target_os = "linux", target_os = "macos", windows)) {
+        return;
+    }
+
+    let exe = std::env::current_exe().unwrap();
+    let exe = exe.file_name().unwrap().to_str().unwrap();
+    let lines = out.lines().collect::<Vec<_>>();
+    let prefix = format!("   0: {exe}+0x");
+    assert!(lines[0].starts_with(&prefix), "{out}");
+    assert!(lines[0].ends_with(" - inner"), "{out}");
+    // Following lines are lined up with the name of the symbol.
+    let name_column = lines[0].find("inner").unwrap();
+    assert_eq!(lines[2].find("outer"), Some(name_column), "{out}");
+    assert_eq!(lines[1].find("at "), lines[3].find("at "), "{out}");
+}
+
+#[test]
+fn frame_filter() {
+    use backtrace::{BacktraceFmt, BytesOrWideString, PrintFmt, SymbolName};
+    use std::fmt;
+
+    struct Frames(bool);
+
+    impl fmt::Display for Frames {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut print_path =
+                |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| path.fmt(f);
+            let mut bt = BacktraceFmt::new(f, PrintFmt::Short, &mut print_path);
+            if self.0 {
+                bt.set_frame_filter(|name| !name.to_string().starts_with("std::"));
+            }
+            let frames: [&[&[u8]]; 3] = [
+                &[b"std::inlined", b"app::inner"],
+                &[b"std::rt::lang_start"],
+                &[b"app::outer"],
+            ];
+            for (i, names) in frames.iter().enumerate() {
+                let mut frame = bt.frame();
+                for name in names.iter() {
+                    frame.print_raw(
+                        (i + 1) as *mut c_void,
+                        Some(SymbolName::new(name)),
+                        None,
+                        None,
+                    )?;
+                }
+            }
+            bt.finish()
+        }
+    }
+
+    assert_eq!(
+        Frames(false).to_string(),
+        "   0: std::inlined\n      app::inner\n   1: std::rt::lang_start\n   2: app::outer\n"
+    );
+    assert_eq!(
+        Frames(true).to_string(),
+        "   0: app::inner\n   2: app::outer\n"
+    );
+}
+
+#[test]
+fn special_region_labels() {
+    use backtrace::{BacktraceFmt, BytesOrWideString, PrintFmt};
+    use std::fmt;
+
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+
+    struct Unknown(*mut c_void);
+
+    impl fmt::Display for Unknown {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut print_path =
+                |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| path.fmt(f);
+            let mut bt = BacktraceFmt::new(f, PrintFmt::Short, &mut print_path);
+            bt.frame().print_raw(self.0, None, None, None)?;
+            bt.finish()
+        }
+    }
+
+    // The stack of the main thread, as tests run on threads of their own.
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+    let stack = maps
+        .lines()
+        .find(|line| line.ends_with(" [stack]"))
+        .and_then(|line| line.split('-').next())
+        .map(|start| usize::from_str_radix(start, 16).unwrap())
+        .expect("no [stack] mapping");
+    // Raw frames are printed as they come, without looking at the memory map.
+    assert_eq!(
+        Unknown(stack as *mut c_void).to_string(),
+        "   0: <unknown>\n"
+    );
+
+    // Captured frames are labeled when they're resolved, and only if they
+    // were captured in this process.
+    let stack = stack as *mut c_void;
+    let frame = backtrace::BacktraceFrame::from(Frame::new(stack, ptr::null_mut(), stack));
+    let mut captured = backtrace::Backtrace::from(vec![frame]);
+    captured.resolve();
+    assert!(format!("{captured:?}").contains("<stack>"));
+    let mut decoded = backtrace::Backtrace::from_ips(&[stack as usize]);
+    decoded.resolve();
+    assert!(format!("{decoded:?}").contains("<unknown>"));
+}
+
+#[test]
+fn linkage_and_source_names() {
+    if !cfg!(all(target_os = "linux", debug_assertions)) {
+        return;
+    }
+
+    let mut names = Vec::new();
+    source_name_target(&mut names);
+    let (name, linkage_name, source_name) = names
+        .into_iter()
+        .find(|(name, ..)| name.contains("source_name_target"))
+        .expect("didn't find source_name_target");
+    assert!(name.contains("smoke::linkage_and_source_names::source_name_target"));
+    assert_eq!(source_name.as_deref(), Some("source_name_target"));
+    let linkage_name = linkage_name.expect("didn't find a linkage name");
+    assert!(linkage_name.starts_with("_ZN") || linkage_name.starts_with("_R"));
+
+    #[inline(never)]
+    fn source_name_target(names: &mut Vec<(String, Option<String>, Option<String>)>) {
+        backtrace::trace(|frame| {
+            backtrace::resolve_frame(frame, |sym| {
+                names.push((
+                    sym.name().map(|n| n.to_string()).unwrap_or_default(),
+                    sym.linkage_name()
+                        .and_then(|n| Some(n.as_str()?.to_string())),
+                    sym.source_name().map(|n| n.to_string()),
+                ));
+            });
+            true
+        });
+    }
+}
+
+#[test]
+fn thread_backtrace_header() {
+    use backtrace::{Backtrace, ThreadBacktrace};
+
+    let bt = std::thread::Builder::new()
+        .name("worker-3".to_string())
+        .spawn(ThreadBacktrace::current)
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(bt.name(), Some("worker-3"));
+    assert!(!bt.is_crashed());
+    if cfg!(target_os = "linux") {
+        let id = bt.id().unwrap();
+        assert!(format!("{bt:?}").starts_with(&format!("Thread \"worker-3\" (id {id}):\n")));
+    }
+
+    let mut bt = ThreadBacktrace::new(Backtrace::from(Vec::new()), None, Some(7));
+    bt.set_crashed(true);
+    assert!(format!("{bt:?}").starts_with("Thread <unnamed> (id 7, crashed):\n"));
+}
+
+#[test]
+fn resolve_batch_matches_resolve() {
+    let mut ips = Vec::new();
+    backtrace::trace(|frame| {
+        ips.push(frame.ip());
+        true
+    });
+    // Some addresses twice and one which isn't in any library.
+    ips.extend_from_within(..2);
+    ips.push(0x10 as *mut c_void);
+
+    let name = |symbol: &backtrace::Symbol| symbol.name().map(|name| name.to_string());
+    let mut expected = vec![Vec::new(); ips.len()];
+    for (i, &ip) in ips.iter().enumerate() {
+        backtrace::resolve(ip, |symbol| expected[i].push(name(symbol)));
+    }
+    let mut names = vec![Vec::new(); ips.len()];
+    backtrace::resolve_batch(&ips, |i, symbol| names[i].push(name(symbol)));
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn symbol_module_is_executable() {
+    let mut modules = Vec::new();
+    // `resolve` looks up the instruction before the address it's given.
+    let addr = (symbol_module_is_executable as fn() as usize + 1) as *mut c_void;
+    backtrace::resolve(addr, |symbol| {
+        modules.push(symbol.module().map(|path| path.to_path_buf()));
+    });
+    if !cfg!(all(target_os = "linux", not(miri))) {
+        return;
+    }
+    let exe = std::env::current_exe().unwrap().canonicalize().unwrap();
+    assert!(!modules.is_empty());
+    for module in modules {
+        assert_eq!(module.unwrap().canonicalize().unwrap(), exe);
+    }
+}
+
+#[test]
+fn resolve_owned_matches_resolve() {
+    // `resolve` looks up the instruction before the address it's given.
+    let addr = (resolve_owned_matches_resolve as fn() as usize + 1) as *mut c_void;
+    let mut expected = Vec::new();
+    backtrace::resolve(addr, |symbol| {
+        expected.push((
+            symbol.name().map(|name| name.to_string()),
+            symbol.filename().map(|path| path.to_path_buf()),
+            symbol.lineno(),
+        ));
+    });
+    let owned = backtrace::resolve_owned(addr);
+    let owned = owned
+        .iter()
+        .map(|symbol| {
+            (
+                symbol.name().map(|name| name.to_string()),
+                symbol.filename().map(|path| path.to_path_buf()),
+                symbol.lineno(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(owned, expected);
+    if cfg!(all(target_os = "linux", debug_assertions)) {
+        let name = owned[0].0.as_deref().unwrap();
+        assert!(name.contains("resolve_owned_matches_resolve"), "{name}");
+    }
+}
+
+#[test]
+fn native_libraries_contain_code() {
+    let libraries = backtrace::native_libraries();
+    if !cfg!(any(target_os = "linux", target_os = "macos", windows)) {
+        return;
+    }
+    let addr = native_libraries_contain_code as fn() as usize;
+    let library = libraries.iter().find(|library| {
+        library.segments().iter().any(|segment| {
+            let start = segment
+                .stated_virtual_memory_address()
+                .wrapping_add(library.bias());
+            start <= addr && addr - start < segment.size()
+        })
+    });
+    assert!(library.is_some(), "{addr:#x} not in {libraries:#?}");
+}
+
+#[test]
+fn symbolizer_matches_resolve() {
+    let mut frames = Vec::new();
+    backtrace::trace(|frame| {
+        frames.push(frame.clone());
+        true
+    });
+
+    let name = |symbol: &backtrace::Symbol| symbol.name().map(|name| name.to_string());
+    // Warmed up on one thread, used on another.
+    let mut symbolizer = std::thread::spawn({
+        let ips = frames
+            .iter()
+            .map(|frame| frame.ip() as usize)
+            .collect::<Vec<_>>();
+        move || {
+            let mut symbolizer = backtrace::Symbolizer::new();
+            for ip in ips {
+                symbolizer.preload(ip as *mut _);
+            }
+            symbolizer
+        }
+    })
+    .join()
+    .unwrap();
+    for frame in &frames {
+        let mut expected = Vec::new();
+        backtrace::resolve_frame(frame, |symbol| expected.push(name(symbol)));
+        let mut names = Vec::new();
+        symbolizer.resolve_frame(frame, |symbol| names.push(name(symbol)));
+        assert_eq!(names, expected);
+
+        let mut expected = Vec::new();
+        backtrace::resolve(frame.ip(), |symbol| expected.push(name(symbol)));
+        let mut names = Vec::new();
+        symbolizer.resolve(frame.ip(), |symbol| names.push(name(symbol)));
+        assert_eq!(names, expected);
+    }
+}
+
+#[test]
+fn frames_matches_trace() {
+    let frames = backtrace::frames().collect::<Vec<_>>();
+    assert!(!frames.is_empty());
+    assert_eq!(backtrace::frames().len(), frames.len());
+
+    let mut traced = Vec::new();
+    backtrace::trace(|frame| {
+        traced.push(frame.ip());
+        true
+    });
+    // Only the innermost frames, of the capturing machinery, differ.
+    assert_eq!(
+        traced.last(),
+        frames.last().map(|frame| frame.ip()).as_ref()
+    );
+}
+
+#[test]
+fn trace_validated_smoke() {
+    let mut count = 0;
+    let result = backtrace::trace_validated(|_| {
+        count += 1;
+        true
+    });
+    assert_eq!(result, backtrace::TraceResult::Finished);
+    assert!(count > 0);
+
+    let result = backtrace::trace_validated(|_| false);
+    assert_eq!(result, backtrace::TraceResult::Stopped);
+}
+
+#[test]
+#[cfg(all(target_os = "linux", not(miri)))]
+fn resolve_candidates_include_resolved_name() {
+    let mut ip = None;
+    backtrace::trace(|frame| {
+        ip = Some(frame.ip());
+        false
+    });
+    let ip = ip.unwrap();
+
+    let mut resolved = None;
+    backtrace::resolve(ip, |symbol| {
+        resolved = symbol.name().map(|n| format!("{n:#}"))
+    });
+    let mut candidates = Vec::new();
+    backtrace::resolve_candidates(ip, |symbol| {
+        assert!(symbol.filename().is_none());
+        candidates.extend(symbol.name().map(|n| format!("{n:#}")));
+    });
+    let resolved = resolved.expect("top frame has no name");
+    assert!(
+        candidates.contains(&resolved),
+        "{resolved} not in {candidates:?}"
+    );
+}
+
+#[test]
+#[cfg(all(target_os = "linux", not(miri)))]
+fn resolve_name_only_matches_symbol_table() {
+    let mut ip = None;
+    backtrace::trace(|frame| {
+        ip = Some(frame.ip());
+        false
+    });
+    let ip = ip.unwrap();
+
+    let mut candidates = Vec::new();
+    backtrace::resolve_candidates(ip, |symbol| {
+        candidates.extend(symbol.name().map(|n| n.as_bytes().to_vec()));
+    });
+    let mut names = Vec::new();
+    backtrace::resolve_name_only(ip, |symbol| {
+        assert!(symbol.filename().is_none());
+        names.extend(symbol.name().map(|n| n.as_bytes().to_vec()));
+    });
+    assert_eq!(names.len(), 1);
+    assert!(candidates.contains(&names[0]));
+}
+
+#[test]
+#[cfg(all(target_os = "linux", not(miri)))]
+fn preload_library_smoke() {
+    let exe = std::env::current_exe().unwrap();
+    assert!(backtrace::preload_library(&exe));
+    assert!(!backtrace::preload_library(std::path::Path::new(
+        "/nonexistent/libbacktrace-smoke.so"
+    )));
+}
+
+#[test]
+fn function_name_from_unwinder_smoke() {
+    let mut names = Vec::new();
+    backtrace::trace(|frame| {
+        names.push(frame.function_name_from_unwinder().map(|n| n.to_string()));
+        true
+    });
+    assert!(!names.is_empty());
+    if cfg!(miri) {
+        assert!(names[0].is_some());
+    } else if !cfg!(unix) {
+        assert!(names.iter().all(Option::is_none));
+    }
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn function_name_from_unwinder_exported() {
+    use std::ffi::{c_int, c_void};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static FOUND: AtomicBool = AtomicBool::new(false);
+    static MISNAMED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" {
+        fn qsort(
+            base: *mut c_void,
+            len: usize,
+            size: usize,
+            compare: extern "C" fn(*const c_void, *const c_void) -> c_int,
+        );
+    }
+
+    // Called back from libc, whose exported `qsort` is then on the stack.
+    extern "C" fn compare(_: *const c_void, _: *const c_void) -> c_int {
+        let mut found = false;
+        let mut misnamed = false;
+        backtrace::trace(|frame| {
+            let name = frame.function_name_from_unwinder();
+            found |= name
+                .as_ref()
+                .is_some_and(|n| n.as_bytes().starts_with(b"qsort"));
+            // Not exported, so not to be named after whatever precedes it.
+            if std::ptr::eq(frame.symbol_address(), compare as *const c_void) {
+                misnamed |= name.is_some();
+            }
+            true
+        });
+        // Panicking here would abort, so only record what was found.
+        FOUND.fetch_or(found, Ordering::Relaxed);
+        MISNAMED.fetch_or(misnamed, Ordering::Relaxed);
+        0
+    }
+
+    let mut items = [0u32; 2];
+    unsafe { qsort(items.as_mut_ptr().cast(), 2, 4, compare) };
+    assert!(FOUND.load(Ordering::Relaxed));
+    assert!(!MISNAMED.load(Ordering::Relaxed));
+}
+
+#[test]
+fn trace_lazy_matches_trace() {
+    let mut expected = Vec::new();
+    backtrace::trace(|frame| {
+        expected.push(frame.symbol_address() as usize);
+        true
+    });
+
+    // Resolving from the consumer must not deadlock on the global lock.
+    let (addrs, resolved) = backtrace::trace_lazy(|frames| {
+        let mut addrs = Vec::new();
+        let mut resolved = false;
+        for frame in frames {
+            backtrace::resolve_frame(&frame, |_| resolved = true);
+            addrs.push(frame.symbol_address() as usize);
+        }
+        (addrs, resolved)
+    });
+    assert!(resolved || expected.is_empty());
+
+    // Both walks start inside different functions, but have the outermost
+    // frames (the test itself and the harness' thread) in common.
+    let shared = addrs
+        .iter()
+        .rev()
+        .zip(expected.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    assert!(expected.is_empty() || shared > 0);
+
+    // Dropping the iterator stops the walk early.
+    let taken = backtrace::trace_lazy(|frames| frames.take(1).count());
+    assert!(taken <= 1);
+
+    // Asking for frames past the end, or not at all, is fine too.
+    let rest = backtrace::trace_lazy(|mut frames| {
+        frames.by_ref().count();
+        frames.next().is_none()
+    });
+    assert!(rest);
+    backtrace::trace_lazy(|_| ());
+
+    // Other threads are kept out of this crate until the consumer is done.
+    let (tx, rx) = std::sync::mpsc::channel();
+    backtrace::trace_lazy(|mut frames| {
+        thread::spawn(move || {
+            backtrace::trace(|_| false);
+            tx.send(()).unwrap();
+        });
+        frames.next();
+        thread::sleep(std::time::Duration::from_millis(50));
+        assert!(rx.try_recv().is_err());
+        rx
+    })
+    .recv()
+    .unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn core_symbolizer_from_own_maps() {
+    // Our own `/proc/self/maps` has the same information as an `NT_FILE` note.
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+    let files = maps.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let (start, end) = fields.next()?.split_once('-')?;
+        let offset = fields.nth(1)?;
+        let path = fields.nth(2).filter(|p| p.starts_with('/'))?;
+        Some(backtrace::MappedFile::new(
+            path,
+            u64::from_str_radix(start, 16).ok()?,
+            u64::from_str_radix(end, 16).ok()?,
+            u64::from_str_radix(offset, 16).ok()?,
+        ))
+    });
+    let mut symbolizer = backtrace::CoreSymbolizer::from_nt_file(files);
+
+    // Both `resolve`s look up the instruction before the address given.
+    let addr = core_symbolizer_from_own_maps as fn() as usize + 1;
+    let mut expected = None;
+    backtrace::resolve(addr as *mut _, |sym| {
+        expected = sym.name().map(|n| n.to_string());
+    });
+    let mut actual = None;
+    symbolizer.resolve(addr as u64, |sym| {
+        actual = sym.name().map(|n| n.to_string());
+    });
+    assert_eq!(actual, expected);
+    if expected.is_some() {
+        assert!(actual.unwrap().contains("core_symbolizer_from_own_maps"));
+    }
+
+    // Addresses outside of any mapped file resolve to nothing.
+    symbolizer.resolve(0, |_| panic!("resolved a null address"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn core_symbolizer_from_maps_text() {
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+    let files = backtrace::MappedFile::parse_maps(&maps);
+    assert!(!files.is_empty());
+    assert!(files
+        .iter()
+        .all(|f| f.path().is_absolute() && f.start() < f.end()));
+    let exe = std::env::current_exe().unwrap();
+    assert!(files.iter().any(|f| f.path() == exe));
+
+    // Files are read through the opener, not from their listed paths.
+    let opened = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut symbolizer = backtrace::CoreSymbolizer::from_maps_text(&maps, {
+        let opened = opened.clone();
+        move |path| {
+            opened.lock().unwrap().push(path.to_owned());
+            std::fs::read(path).ok()
+        }
+    });
+    assert!(opened.lock().unwrap().contains(&exe));
+    let addr = core_symbolizer_from_maps_text as fn() as usize + 1;
+    let mut expected = None;
+    backtrace::resolve(addr as *mut _, |sym| {
+        expected = sym.name().map(|n| n.to_string());
+    });
+    let mut actual = None;
+    symbolizer.resolve(addr as u64, |sym| {
+        actual = sym.name().map(|n| n.to_string());
+    });
+    assert_eq!(actual, expected);
+
+    // Nothing is resolved in files the opener doesn't have.
+    let mut symbolizer = backtrace::CoreSymbolizer::from_maps_text(&maps, |_| None::<Vec<u8>>);
+    symbolizer.resolve(addr as u64, |_| panic!("resolved without any files"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn core_symbolizer_ignores_extra_mappings() {
+    // A debugger or this crate may have mapped the whole executable somewhere
+    // else too, which mustn't be taken for where it was loaded.
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+    let mut files = backtrace::MappedFile::parse_maps(&maps);
+    let exe = std::env::current_exe().unwrap();
+    let len = std::fs::metadata(&exe).unwrap().len();
+    let end = files.iter().map(|f| f.end()).max().unwrap();
+    let start = (end + 0xfff_ffff) & !0xfff_ffff;
+    files.push(backtrace::MappedFile::new(&exe, start, start + len, 0));
+    let mut symbolizer = backtrace::CoreSymbolizer::from_nt_file(files);
+
+    let addr = core_symbolizer_ignores_extra_mappings as fn() as usize + 1;
+    let mut expected = None;
+    backtrace::resolve(addr as *mut _, |sym| {
+        expected = sym.name().map(|n| n.to_string());
+    });
+    let mut actual = None;
+    symbolizer.resolve(addr as u64, |sym| {
+        actual = sym.name().map(|n| n.to_string());
+    });
+    assert_eq!(actual, expected);
+    symbolizer.resolve(start + 1, |_| panic!("resolved in the extra mapping"));
+}
+
+#[test]
+fn call_target_smoke() {
+    // Call site information is only emitted for optimized code, so all we can
+    // check in general is that looking for it works on real frames.
+    let mut targets = Vec::new();
+    backtrace::trace(|frame| {
+        let mut innermost = true;
+        backtrace::resolve_frame(frame, |sym| {
+            if innermost {
+                targets.push(sym.call_target().map(|n| n.to_string()));
+            }
+            innermost = false;
+        });
+        true
+    });
+    for target in targets.into_iter().flatten() {
+        assert!(!target.is_empty());
+    }
+}
+
+/// Compiles `source` into a shared library named `name` with the C compiler,
+/// if there is one, and loads it. The library is built in a directory of its
+/// own, which is returned along with it for the caller to remove.
+#[cfg(all(target_os = "linux", not(miri)))]
+fn load_c_library(
+    name: &str,
+    source: &str,
+    args: &[&str],
+) -> Option<(std::path::PathBuf, libloading::Library)> {
+    let dir = std::env::temp_dir().join(format!("backtrace-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source_path = dir.join(format!("{name}.c"));
+    let lib = dir.join(format!("lib{name}.so"));
+    std::fs::write(&source_path, source).unwrap();
+    let built = std::process::Command::new("cc")
+        .args(["-shared", "-fPIC"])
+        .args(args)
+        .arg("-o")
+        .arg(&lib)
+        .arg(&source_path)
+        .status();
+    if !built.is_ok_and(|status| status.success()) {
+        std::fs::remove_dir_all(&dir).unwrap();
+        return None;
+    }
+    let lib = unsafe { libloading::Library::new(&lib).unwrap() };
+    Some((dir, lib))
+}
+
+/// The type of the C functions `load_c_library` is used to test, which pass
+/// `data` on to `cb` from somewhere further down the stack.
+#[cfg(all(target_os = "linux", not(miri)))]
+type CallsBack = extern "C" fn(extern "C" fn(*mut c_void), *mut c_void) -> i32;
+
+#[test]
+#[cfg(all(target_os = "linux", not(miri)))]
+fn call_target_names_callee() {
+    // Build some optimized code with call site information to call through.
+    let source = "\
+        __attribute__((noinline)) int callee(void (*cb)(void *), void *data) {
+            cb(data);
+            return 1;
+        }
+        int caller(void (*cb)(void *), void *data) {
+            return callee(cb, data) + 1;
+        }
+    ";
+    let Some((dir, lib)) = load_c_library("call-target", source, &["-O2", "-g"]) else {
+        return;
+    };
+
+    // Records the call target of the innermost symbol of each frame, by the
+    // name of that symbol.
+    extern "C" fn record(data: *mut c_void) {
+        let targets = unsafe { &mut *data.cast::<Vec<(String, Option<String>)>>() };
+        backtrace::trace(|frame| {
+            let mut innermost = true;
+            backtrace::resolve_frame(frame, |sym| {
+                if innermost {
+                    let name = sym.name().map(|n| n.to_string()).unwrap_or_default();
+                    targets.push((name, sym.call_target().map(|n| n.to_string())));
+                }
+                innermost = false;
+            });
+            true
+        });
+    }
+
+    let mut targets = Vec::<(String, Option<String>)>::new();
+    unsafe {
+        let caller = lib.get::<CallsBack>(b"caller").unwrap();
+        assert_eq!(caller(record, ptr::addr_of_mut!(targets).cast()), 2);
+    }
+    drop(lib);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let caller = targets.iter().find(|(name, _)| name == "caller");
+    assert_eq!(
+        caller.and_then(|(_, target)| target.as_deref()),
+        Some("callee"),
+        "{targets:?}"
+    );
+}
+
+#[test]
+#[cfg(all(target_os = "linux", not(miri)))]
+fn symtab_names_frames_without_dwarf() {
+    // Records the names of the symbols of each frame, innermost first.
+    extern "C" fn record(data: *mut c_void) {
+        let frames = unsafe { &mut *data.cast::<Vec<Vec<Option<String>>>>() };
+        backtrace::trace(|frame| {
+            let mut names = Vec::new();
+            backtrace::resolve_frame(frame, |sym| {
+                names.push(sym.name().map(|n| n.to_string()));
+            });
+            frames.push(names);
+            true
+        });
+    }
+
+    // Returns the names of the symbols of the frame running `outer`.
+    fn outer_frame(lib: &libloading::Library) -> Option<Vec<Option<String>>> {
+        let mut frames = Vec::<Vec<Option<String>>>::new();
+        unsafe {
+            let outer = lib.get::<CallsBack>(b"outer").unwrap();
+            assert_eq!(outer(record, ptr::addr_of_mut!(frames).cast()), 2);
+        }
+        frames
+            .into_iter()
+            .find(|names| names.last() == Some(&Some("outer".to_string())))
+    }
+
+    let source = "\
+        static inline __attribute__((always_inline))
+        int inner(void (*cb)(void *), void *data) {
+            cb(data);
+            return 1;
+        }
+        int outer(void (*cb)(void *), void *data) {
+            return inner(cb, data) + 1;
+        }
+    ";
+    let Some((without_dir, without)) = load_c_library("without-dwarf", source, &["-O2"]) else {
+        return;
+    };
+    let (with_dir, with) = load_c_library("with-dwarf", source, &["-O2", "-g"]).unwrap();
+
+    // Without any debug information, the symbol table names the function.
+    assert_eq!(outer_frame(&without).unwrap(), [Some("outer".to_string())]);
+
+    // With it, each function inlined into the frame gets its own name rather
+    // than that of the function containing it in the symbol table.
+    assert_eq!(
+        outer_frame(&with).unwrap(),
+        [Some("inner".to_string()), Some("outer".to_string())]
+    );
+
+    drop((without, with));
+    std::fs::remove_dir_all(&without_dir).unwrap();
+    std::fs::remove_dir_all(&with_dir).unwrap();
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+fn resolve_in_archive_smoke() {
+    // Rust libraries are archives of object files, so use one of our
+    // dependencies' as a test subject.
+    let deps = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let rlib = std::fs::read_dir(&deps)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            name.starts_with("librustc_demangle-") && name.ends_with(".rlib")
+        });
+    let rlib = match rlib {
+        Some(rlib) => rlib,
+        None => return,
+    };
+
+    // Find the name of an object file in the archive, which is stored in the
+    // GNU format with long names in a separate `//` member.
+    let data = std::fs::read(&rlib).unwrap();
+    assert!(data.starts_with(b"!<arch>\n"));
+    let mut pos = 8;
+    let mut long_names: &[u8] = &[];
+    let mut member = None;
+    while pos + 60 <= data.len() && member.is_none() {
+        let header = &data[pos..pos + 60];
+        let size: usize = std::str::from_utf8(&header[48..58])
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        let body = &data[pos + 60..pos + 60 + size];
+        let name = std::str::from_utf8(&header[..16]).unwrap().trim_end();
+        if name == "//" {
+            long_names = body;
+        } else if let Some(offset) = name.strip_prefix('/').and_then(|n| n.parse().ok()) {
+            let long_name = &long_names[offset..];
+            let end = long_name.iter().position(|&b| b == b'/').unwrap();
+            let long_name = std::str::from_utf8(&long_name[..end]).unwrap();
+            if long_name.ends_with(".o") {
+                member = Some(long_name.to_string());
+            }
+        } else if name.trim_end_matches('/').ends_with(".o") {
+            member = Some(name.trim_end_matches('/').to_string());
+        }
+        pos += 60 + size + size % 2;
+    }
+    let member = member.expect("no object file in rlib");
+
+    let mut names = Vec::new();
+    backtrace::resolve_in_archive(&rlib, &member, 0, |sym| {
+        names.push(sym.name().map(|n| n.to_string()));
+        // Debug info in object files isn't usable without relocations.
+        assert!(sym.filename().is_none());
+    });
+    assert_eq!(names.len(), 1);
+    assert!(names[0].is_some());
+
+    let mut called = false;
+    backtrace::resolve_in_archive(&rlib, "not-a-member.o", 0, |_| called = true);
+    backtrace::resolve_in_archive(&deps.join("does-not-exist.a"), &member, 0, |_| {
+        called = true
+    });
+    assert!(!called);
+}
+
+#[test]
+fn backend_info() {
+    let info = backtrace::backend_info();
+    if cfg!(target_os = "linux") {
+        assert_eq!(info.unwind(), "libunwind");
+        assert_eq!(info.symbolize(), "gimli");
+    }
+    assert_eq!(
+        info.to_string(),
+        format!("unwind: {}, symbolize: {}", info.unwind(), info.symbolize())
+    );
+}
+
+#[test]
+fn backtrace_internal_symbols() {
+    let mut names = Vec::new();
+    backtrace::trace(|frame| {
+        backtrace::resolve_frame(frame, |symbol| {
+            names.push((
+                symbol.name().map(|n| format!("{n:#}")),
+                symbol.is_backtrace_internal(),
+            ));
+        });
+        true
+    });
+
+    // `trace` and the unwinder it calls are above the caller of `trace`.
+    let this_fn = names.iter().position(|(name, _)| {
+        name.as_deref()
+            .is_some_and(|n| n.ends_with("backtrace_internal_symbols"))
+    });
+    if let Some(this_fn) = this_fn {
+        assert!(names[..this_fn].iter().any(|&(_, internal)| internal));
+        assert!(!names[this_fn].1);
+    }
+}
@@ -0,0 +1,110 @@
+// Deployments which run a binary through a symlink may put its debug files
+// next to the symlink rather than the binary it points to. This strips the
+// debug info out of a copy of this test, leaves it only beside a symlink to
+// that copy, and checks a backtrace taken through the symlink still has it.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+
+mod common;
+
+fn main() {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+
+    if std::env::var(VAR).is_err() {
+        match parent() {
+            Ok(()) => println!("test result: ok"),
+            Err(EarlyExit::IgnoreTest) => println!("test result: ignored"),
+            Err(EarlyExit::IoError(e)) => {
+                println!("{} parent encountered IoError: {:?}", file!(), e);
+                panic!();
+            }
+        }
+    } else {
+        child();
+    }
+}
+
+const VAR: &str = "__BACKTRACE_SYMLINKED_EXE";
+
+#[derive(Debug)]
+enum EarlyExit {
+    IgnoreTest,
+    IoError(std::io::Error),
+}
+
+impl From<std::io::Error> for EarlyExit {
+    fn from(e: std::io::Error) -> Self {
+        EarlyExit::IoError(e)
+    }
+}
+
+fn parent() -> Result<(), EarlyExit> {
+    if common::cannot_reexec_the_test() {
+        return Err(EarlyExit::IgnoreTest);
+    }
+
+    let me = std::env::current_exe()?;
+    let dir = std::env::temp_dir().join(format!("backtrace-symlinked-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("bin"))?;
+
+    let result = run(&me, &dir);
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn run(me: &Path, dir: &Path) -> Result<(), EarlyExit> {
+    // `dir/bin/real` has no debug info and links to `real.debug`, which only
+    // exists in `dir`, next to the `dir/run` symlink.
+    let debug = dir.join("real.debug");
+    let real = dir.join("bin").join("real");
+    objcopy(&["--only-keep-debug".as_ref(), me.as_ref(), debug.as_ref()])?;
+    let link = format!("--add-gnu-debuglink={}", debug.display());
+    objcopy(&[
+        "--strip-debug".as_ref(),
+        link.as_ref(),
+        me.as_ref(),
+        real.as_ref(),
+    ])?;
+    let run = dir.join("run");
+    std::os::unix::fs::symlink(&real, &run)?;
+
+    let result = Command::new(&run).env(VAR, "1").output()?;
+    if result.status.success() {
+        return Ok(());
+    }
+    println!("stdout:\n{}", String::from_utf8_lossy(&result.stdout));
+    println!("stderr:\n{}", String::from_utf8_lossy(&result.stderr));
+    println!("code: {}", result.status);
+    panic!();
+}
+
+// If `objcopy` isn't around, or this test was built without debug info to
+// split out, there's nothing to test.
+fn objcopy(args: &[&OsStr]) -> Result<(), EarlyExit> {
+    let status = Command::new("objcopy")
+        .args(args)
+        .status()
+        .map_err(|_| EarlyExit::IgnoreTest)?;
+    if !status.success() {
+        return Err(EarlyExit::IgnoreTest);
+    }
+    Ok(())
+}
+
+fn child() {
+    let bt = backtrace::Backtrace::new();
+    let found = bt
+        .frames()
+        .iter()
+        .flat_map(|frame| frame.symbols())
+        .any(|sym| {
+            sym.filename()
+                .is_some_and(|filename| filename.ends_with(file!()))
+        });
+    assert!(found, "{bt:?}");
+}